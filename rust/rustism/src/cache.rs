@@ -0,0 +1,153 @@
+use crate::model::{ReferenceOrbit, RenderParams, TileData};
+use std::{
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+};
+
+// Tile origins are keyed to 1/256 of a pixel, so panning by whole pixels
+// lands on the same keys despite rounding in the center
+const ORIGIN_STEPS_PER_PIXEL: f64 = 256.0;
+
+/// A finished grid tile, before coloring, so a hit can take any palette.
+#[derive(Debug, Clone)]
+pub struct CachedTile {
+    pub values: TileData,
+    pub min_iter: u32,
+    pub max_iter_reached: u32,
+    // Of the values before coloring, for with_checksum
+    pub checksum: u64,
+}
+
+/// Least-recently-used store of finished tiles, keyed by `tile_key`.
+#[derive(Debug)]
+pub struct TileCache {
+    capacity: usize,
+    // Entries with the tick they were last used at
+    entries: HashMap<u64, (u64, CachedTile)>,
+    tick: u64,
+}
+
+impl TileCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::with_capacity(capacity),
+            tick: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn get(&mut self, key: u64) -> Option<&CachedTile> {
+        self.tick += 1;
+        let (used, tile) = self.entries.get_mut(&key)?;
+        *used = self.tick;
+        Some(tile)
+    }
+
+    pub fn insert(&mut self, key: u64, tile: CachedTile) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(&key) {
+            // A linear scan, but caches hold a few frames of tiles at most
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (used, _))| *used)
+                .map(|(&key, _)| key);
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.tick += 1;
+        self.entries.insert(key, (self.tick, tile));
+    }
+}
+
+/// Hash of everything but the view center that decides a frame's values.
+pub fn frame_key(params: &RenderParams, orbit: Option<&ReferenceOrbit>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    // The enums hold f64s, whose Debug output is exact
+    format!(
+        "{:?} {:?} {:?} {:?} {:?} {:?} {:?}",
+        params.kind,
+        params.output,
+        params.precision,
+        params.trap,
+        params.light,
+        params.aa_threshold,
+        params.sample_pattern
+    )
+    .hash(&mut hasher);
+    (
+        params.max_iter,
+        params.viewport.scale.to_bits(),
+        params.samples_per_axis,
+        params.power,
+        params.escape_radius.to_bits(),
+        params.interior,
+        params.periodicity,
+        params.flip_y,
+        params.solid_guess,
+        params.check_every,
+        params.pixel_center,
+        params.stripe_freq.to_bits(),
+    )
+        .hash(&mut hasher);
+    // Deep zooms measure every pixel from the reference, so it's part of
+    // the key and only repeated frames hit
+    if let Some(orbit) = orbit {
+        format!("{:?} {:?}", orbit.center_re, orbit.center_im).hash(&mut hasher);
+    }
+    // Likewise a double-double center, past what the tile origins resolve
+    if let Some(center) = params.dd_center {
+        format!("{center:?}").hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// Key of the tile at `(x, y, w, h)` in the frame `frame_key` was taken of.
+/// The tile's position is measured on the plane rather than in the image,
+/// so a tile that a pan moved to another grid slot still hits.
+pub fn tile_key(
+    frame_key: u64,
+    params: &RenderParams,
+    x: usize,
+    y: usize,
+    w: usize,
+    h: usize,
+) -> u64 {
+    let viewport = &params.viewport;
+    // Flipped tiles are measured from the row their top edge ends up on
+    let y = if params.flip_y {
+        params.height.saturating_sub(y + h)
+    } else {
+        y
+    };
+    let origin = |center: f64, offset: usize, extent: usize| {
+        let pixels = center / viewport.scale + offset as f64 - extent as f64 / 2.0;
+        (pixels * ORIGIN_STEPS_PER_PIXEL).round().to_bits()
+    };
+
+    let mut hasher = DefaultHasher::new();
+    (
+        frame_key,
+        origin(viewport.center_re, x, params.width),
+        origin(viewport.center_im, y, params.height),
+        w,
+        h,
+    )
+        .hash(&mut hasher);
+    hasher.finish()
+}
@@ -0,0 +1,338 @@
+use crate::model::OutputMode;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+pub const PALETTE_GRAYSCALE: u8 = 0;
+pub const PALETTE_FIRE: u8 = 1;
+pub const PALETTE_OCEAN: u8 = 2;
+pub const PALETTE_CLASSIC: u8 = 3;
+
+/// Built-in gradients over the escape value, from 0 up to `max_iter`.
+/// Interior pixels are always black.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Palette {
+    Grayscale,
+    /// Black through red and yellow to white
+    Fire,
+    /// Black through navy and teal to white
+    Ocean,
+    /// The usual blue-white-gold Mandelbrot gradient
+    Classic,
+}
+
+// (position, rgb) stops, linearly interpolated
+type Stops = &'static [(f64, [u8; 3])];
+
+const GRAYSCALE: Stops = &[(0.0, [0, 0, 0]), (1.0, [255, 255, 255])];
+const FIRE: Stops = &[
+    (0.0, [0, 0, 0]),
+    (0.33, [200, 24, 0]),
+    (0.66, [255, 200, 0]),
+    (1.0, [255, 255, 255]),
+];
+const OCEAN: Stops = &[
+    (0.0, [0, 0, 0]),
+    (0.33, [0, 24, 110]),
+    (0.66, [0, 160, 170]),
+    (1.0, [255, 255, 255]),
+];
+const CLASSIC: Stops = &[
+    (0.0, [0, 7, 100]),
+    (0.16, [32, 107, 203]),
+    (0.42, [237, 255, 255]),
+    (0.6425, [255, 170, 0]),
+    (0.8575, [0, 2, 0]),
+    (1.0, [0, 7, 100]),
+];
+
+impl Palette {
+    pub fn from_code(code: u8) -> PyResult<Self> {
+        match code {
+            PALETTE_GRAYSCALE => Ok(Palette::Grayscale),
+            PALETTE_FIRE => Ok(Palette::Fire),
+            PALETTE_OCEAN => Ok(Palette::Ocean),
+            PALETTE_CLASSIC => Ok(Palette::Classic),
+            _ => Err(PyValueError::new_err(format!("unknown palette {code}"))),
+        }
+    }
+
+    fn stops(self) -> Stops {
+        match self {
+            Palette::Grayscale => GRAYSCALE,
+            Palette::Fire => FIRE,
+            Palette::Ocean => OCEAN,
+            Palette::Classic => CLASSIC,
+        }
+    }
+
+    /// Color of an (integer or smooth) escape value.
+    pub fn rgb(self, value: f64, max_iter: u32) -> [u8; 3] {
+        // Negative values are interior, see `interior_value`
+        if value < 0.0 || value >= max_iter as f64 || max_iter == 0 {
+            return [0, 0, 0];
+        }
+
+        let t = (value / max_iter as f64).clamp(0.0, 1.0);
+        let stops = self.stops();
+        let upper = stops
+            .iter()
+            .position(|&(position, _)| position >= t)
+            .unwrap_or(stops.len() - 1)
+            .max(1);
+        let (p0, c0) = stops[upper - 1];
+        let (p1, c1) = stops[upper];
+        let f = (t - p0) / (p1 - p0);
+
+        std::array::from_fn(|i| (c0[i] as f64 + (c1[i] as f64 - c0[i] as f64) * f).round() as u8)
+    }
+
+    /// `rgb` packed as an opaque RGBA pixel. The bytes are in R, G, B, A
+    /// order in memory on any platform.
+    pub fn rgba(self, value: f64, max_iter: u32) -> u32 {
+        let [r, g, b] = self.rgb(value, max_iter);
+        u32::from_ne_bytes([r, g, b, 255])
+    }
+}
+
+/// Value of a render's `palette` argument: a built-in palette code, or a
+/// callable mapping a value in `[0, 1]` to an `(r, g, b)` tuple.
+#[derive(FromPyObject)]
+pub enum PaletteArg {
+    Code(u8),
+    Callback(Py<PyAny>),
+}
+
+/// Exposure and gamma applied to an escape value, normalized by
+/// `max_iter`, before it is looked up: `t = (t * exposure)^(1 / gamma)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tone {
+    pub exposure: f64,
+    pub gamma: f64,
+}
+
+impl Tone {
+    pub fn new(exposure: f64, gamma: f64) -> PyResult<Self> {
+        if !(exposure > 0.0 && exposure.is_finite()) {
+            return Err(PyValueError::new_err("exposure must be positive"));
+        }
+        if !(gamma > 0.0 && gamma.is_finite()) {
+            return Err(PyValueError::new_err("gamma must be positive"));
+        }
+        Ok(Self { exposure, gamma })
+    }
+
+    pub fn is_identity(self) -> bool {
+        self.exposure == 1.0 && self.gamma == 1.0
+    }
+
+    /// The escape value with the tone curve applied. Interior values are
+    /// left alone, and bright exterior ones saturate just below `max_iter`
+    /// so they keep the top of the gradient rather than turning interior.
+    #[inline(always)]
+    pub fn apply(self, value: f64, max_iter: u32) -> f64 {
+        if self.is_identity() || value < 0.0 || value >= max_iter as f64 {
+            return value;
+        }
+
+        let max = max_iter as f64;
+        let t = ((value / max) * self.exposure).powf(1.0 / self.gamma);
+        (t * max).min(max.next_down())
+    }
+}
+
+/// Colors of an escape value, before any `Tone` is applied.
+#[derive(Debug, Clone)]
+pub enum Gradient {
+    Builtin(Palette),
+    /// Colors of escape buckets `0..=max_iter`; smooth values are floored
+    Lut(Vec<u32>),
+}
+
+/// How one render turns escape values into RGBA pixels.
+#[derive(Debug, Clone)]
+pub struct Coloring {
+    pub gradient: Gradient,
+    pub tone: Tone,
+    // Fraction of the gradient that exterior values are rotated by, in
+    // `[0, 1)`; see `cycle`
+    pub offset: f64,
+}
+
+impl Coloring {
+    /// Resolves the optional `palette` argument. A callback is called once
+    /// per bucket up front, so a failing one aborts before any rendering.
+    /// `palette_offset` is in whole gradients, so only its fraction counts.
+    pub fn new(
+        py: Python<'_>,
+        palette: Option<PaletteArg>,
+        tone: Tone,
+        palette_offset: f32,
+        output: OutputMode,
+        max_iter: u32,
+    ) -> PyResult<Option<Self>> {
+        if !palette_offset.is_finite() {
+            return Err(PyValueError::new_err("palette_offset must be finite"));
+        }
+        let Some(palette) = palette else {
+            if !tone.is_identity() {
+                return Err(PyValueError::new_err(
+                    "exposure and gamma only apply with a palette",
+                ));
+            }
+            if palette_offset != 0.0 {
+                return Err(PyValueError::new_err(
+                    "palette_offset only applies with a palette",
+                ));
+            }
+            return Ok(None);
+        };
+        if !output.is_escape_value() {
+            return Err(PyValueError::new_err(
+                "palettes only color the iteration and smooth output modes",
+            ));
+        }
+
+        let gradient = match palette {
+            PaletteArg::Code(code) => Gradient::Builtin(Palette::from_code(code)?),
+            PaletteArg::Callback(callback) => {
+                let callback = callback.bind(py);
+                if !callback.is_callable() {
+                    return Err(PyValueError::new_err(
+                        "palette must be a palette code or a callable",
+                    ));
+                }
+
+                let lut = (0..=max_iter)
+                    .map(|i| {
+                        let t = if max_iter == 0 {
+                            0.0
+                        } else {
+                            i as f64 / max_iter as f64
+                        };
+                        let (r, g, b): (u8, u8, u8) = callback.call1((t,))?.extract()?;
+                        Ok(u32::from_ne_bytes([r, g, b, 255]))
+                    })
+                    .collect::<PyResult<_>>()?;
+                Gradient::Lut(lut)
+            }
+        };
+        Ok(Some(Coloring {
+            gradient,
+            tone,
+            // Whole turns drop out exactly here, so they color as 0 does
+            offset: (palette_offset as f64).rem_euclid(1.0),
+        }))
+    }
+
+    // Rotates an exterior value by `offset` of the gradient, wrapping
+    // around at `max_iter`. Interior values keep their color.
+    #[inline(always)]
+    fn cycle(&self, value: f64, max_iter: u32) -> f64 {
+        let max = max_iter as f64;
+        if self.offset == 0.0 || value < 0.0 || value >= max {
+            return value;
+        }
+        let value = value + self.offset * max;
+        if value >= max { value - max } else { value }
+    }
+
+    #[inline(always)]
+    pub fn rgba(&self, value: f64, max_iter: u32) -> u32 {
+        let value = self.cycle(self.tone.apply(value, max_iter), max_iter);
+        match &self.gradient {
+            Gradient::Builtin(palette) => palette.rgba(value, max_iter),
+            Gradient::Lut(lut) if value < 0.0 => lut[lut.len() - 1],
+            Gradient::Lut(lut) => lut[(value as usize).min(lut.len() - 1)],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_values_map_to_their_colors() {
+        assert_eq!(Palette::Grayscale.rgb(0.0, 100), [0, 0, 0]);
+        assert_eq!(Palette::Grayscale.rgb(50.0, 100), [128, 128, 128]);
+        assert_eq!(Palette::Fire.rgb(33.0, 100), [200, 24, 0]);
+        assert_eq!(Palette::Ocean.rgb(66.0, 100), [0, 160, 170]);
+        assert_eq!(Palette::Classic.rgb(0.0, 100), [0, 7, 100]);
+        // Halfway between the first two classic stops
+        assert_eq!(Palette::Classic.rgb(8.0, 100), [16, 57, 152]);
+    }
+
+    #[test]
+    fn interior_is_black() {
+        for palette in [
+            Palette::Grayscale,
+            Palette::Fire,
+            Palette::Ocean,
+            Palette::Classic,
+        ] {
+            assert_eq!(palette.rgb(100.0, 100), [0, 0, 0]);
+            assert_eq!(palette.rgb(-1.0, 100), [0, 0, 0]);
+        }
+    }
+
+    #[test]
+    fn rgba_bytes_are_in_order() {
+        let pixel = Palette::Fire.rgba(33.0, 100);
+        assert_eq!(pixel.to_ne_bytes(), [200, 24, 0, 255]);
+    }
+
+    #[test]
+    fn tone_curve_transforms_known_values() {
+        let tone = Tone::new(2.0, 2.0).unwrap();
+        // (25 / 100 * 2)^(1 / 2) of the way up
+        assert!((tone.apply(25.0, 100) - 100.0 * 0.5f64.sqrt()).abs() < 1e-12);
+        // Overexposed values saturate, but stay exterior
+        assert_eq!(tone.apply(80.0, 100), 100f64.next_down());
+        assert_eq!(tone.apply(100.0, 100), 100.0);
+        assert_eq!(tone.apply(-1.0, 100), -1.0);
+
+        let identity = Tone::new(1.0, 1.0).unwrap();
+        assert!(identity.is_identity());
+        assert_eq!(identity.apply(37.25, 100), 37.25);
+    }
+
+    #[test]
+    fn tone_rejects_non_positive_parameters() {
+        for (exposure, gamma) in [
+            (0.0, 1.0),
+            (1.0, -1.0),
+            (f64::NAN, 1.0),
+            (1.0, f64::INFINITY),
+        ] {
+            assert!(Tone::new(exposure, gamma).is_err());
+        }
+    }
+
+    fn cycled(palette_offset: f32) -> Coloring {
+        Python::initialize();
+        Python::attach(|py| {
+            let palette = Some(PaletteArg::Code(PALETTE_CLASSIC));
+            let tone = Tone::new(1.0, 1.0).unwrap();
+            Coloring::new(py, palette, tone, palette_offset, OutputMode::Smooth, 100)
+                .unwrap()
+                .unwrap()
+        })
+    }
+
+    #[test]
+    fn whole_turns_of_palette_offset_color_alike() {
+        let values = (0..400).map(|i| i as f64 * 0.25 - 0.5);
+        let colors = |coloring: &Coloring| values.clone().map(|v| coloring.rgba(v, 100)).collect();
+        let still: Vec<u32> = colors(&cycled(0.0));
+        for offset in [1.0, 3.0, -2.0] {
+            assert_eq!(colors(&cycled(offset)), still, "at offset {offset}");
+        }
+        let half: Vec<u32> = colors(&cycled(0.5));
+        assert_ne!(half, still);
+        assert_eq!(colors(&cycled(-0.5)), half);
+        // A quarter turn moves value 0 to where 25 was
+        assert_eq!(cycled(0.25).rgba(0.0, 100), still[102]);
+        // Interior keeps its color
+        assert_eq!(cycled(0.5).rgba(100.0, 100), cycled(0.0).rgba(100.0, 100));
+    }
+}
@@ -0,0 +1,247 @@
+use std::{
+    ops::{Add, Div, Mul, Neg, Sub},
+    str::FromStr,
+};
+
+/// Unevaluated sum `hi + lo` of two f64s, good for about 31 significant
+/// digits. Enough to place a reference orbit for zooms well past 1e-20.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DoubleDouble {
+    pub hi: f64,
+    pub lo: f64,
+}
+
+impl DoubleDouble {
+    pub const ZERO: Self = Self { hi: 0.0, lo: 0.0 };
+
+    pub fn from_f64(value: f64) -> Self {
+        Self { hi: value, lo: 0.0 }
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.hi + self.lo
+    }
+
+    pub fn sqr(self) -> Self {
+        self * self
+    }
+}
+
+// Error-free a + b, for any magnitudes
+#[inline(always)]
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+    let s = a + b;
+    let bb = s - a;
+    (s, (a - (s - bb)) + (b - bb))
+}
+
+// Error-free a + b, assuming |a| >= |b|
+#[inline(always)]
+fn quick_two_sum(a: f64, b: f64) -> (f64, f64) {
+    let s = a + b;
+    (s, b - (s - a))
+}
+
+// Error-free a * b
+#[inline(always)]
+fn two_prod(a: f64, b: f64) -> (f64, f64) {
+    let p = a * b;
+    (p, a.mul_add(b, -p))
+}
+
+impl Add for DoubleDouble {
+    type Output = Self;
+
+    #[inline(always)]
+    fn add(self, rhs: Self) -> Self {
+        let (s, e) = two_sum(self.hi, rhs.hi);
+        let (t, f) = two_sum(self.lo, rhs.lo);
+        let (s, e) = quick_two_sum(s, e + t);
+        let (hi, lo) = quick_two_sum(s, e + f);
+        Self { hi, lo }
+    }
+}
+
+impl Neg for DoubleDouble {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self {
+            hi: -self.hi,
+            lo: -self.lo,
+        }
+    }
+}
+
+impl Sub for DoubleDouble {
+    type Output = Self;
+
+    #[inline(always)]
+    fn sub(self, rhs: Self) -> Self {
+        self + -rhs
+    }
+}
+
+impl Mul for DoubleDouble {
+    type Output = Self;
+
+    #[inline(always)]
+    fn mul(self, rhs: Self) -> Self {
+        let (p, e) = two_prod(self.hi, rhs.hi);
+        let e = e + (self.hi * rhs.lo + self.lo * rhs.hi);
+        let (hi, lo) = quick_two_sum(p, e);
+        Self { hi, lo }
+    }
+}
+
+impl Mul<f64> for DoubleDouble {
+    type Output = Self;
+
+    #[inline(always)]
+    fn mul(self, rhs: f64) -> Self {
+        let (p, e) = two_prod(self.hi, rhs);
+        let (hi, lo) = quick_two_sum(p, e + self.lo * rhs);
+        Self { hi, lo }
+    }
+}
+
+impl Div for DoubleDouble {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self {
+        // Long division, one f64 quotient digit at a time
+        let q1 = self.hi / rhs.hi;
+        let r = self - rhs * q1;
+        let q2 = r.hi / rhs.hi;
+        let r = r - rhs * q2;
+        let q3 = r.hi / rhs.hi;
+
+        let (hi, lo) = quick_two_sum(q1, q2);
+        Self { hi, lo } + Self::from_f64(q3)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDoubleDoubleError;
+
+impl FromStr for DoubleDouble {
+    type Err = ParseDoubleDoubleError;
+
+    /// Parses a decimal like `-1.25`, `.5` or `3e-21` without going through
+    /// f64, so digits past the 17th still count.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (negative, s) = match s.as_bytes().first() {
+            Some(b'-') => (true, &s[1..]),
+            Some(b'+') => (false, &s[1..]),
+            _ => (false, s),
+        };
+        let (mantissa, exponent) = match s.find(['e', 'E']) {
+            Some(at) => (
+                &s[..at],
+                s[at + 1..]
+                    .parse::<i32>()
+                    .map_err(|_| ParseDoubleDoubleError)?,
+            ),
+            None => (s, 0),
+        };
+
+        let mut value = Self::ZERO;
+        let mut exponent = exponent;
+        let mut digits = 0;
+        let mut seen_point = false;
+        for b in mantissa.bytes() {
+            match b {
+                b'.' if !seen_point => seen_point = true,
+                b'0'..=b'9' => {
+                    // Past ~34 digits there's nothing left to gain
+                    if digits < 34 {
+                        value = value * 10.0 + Self::from_f64((b - b'0') as f64);
+                        if value.hi != 0.0 {
+                            digits += 1;
+                        }
+                        if seen_point {
+                            exponent -= 1;
+                        }
+                    } else if !seen_point {
+                        exponent += 1;
+                    }
+                }
+                _ => return Err(ParseDoubleDoubleError),
+            }
+        }
+        if mantissa.bytes().all(|b| b == b'.') {
+            return Err(ParseDoubleDoubleError);
+        }
+
+        // Far outside the f64 range anyway
+        if exponent.unsigned_abs() > 400 {
+            let magnitude = if exponent < 0 || value.hi == 0.0 {
+                Self::ZERO
+            } else {
+                Self::from_f64(f64::INFINITY)
+            };
+            return Ok(if negative { -magnitude } else { magnitude });
+        }
+
+        // 10^exponent itself can overflow, so scale by at most 1e300 at a
+        // time. A value past f64::MAX comes out infinite.
+        while exponent != 0 && value.hi.is_finite() {
+            let step = exponent.clamp(-300, 300);
+            let mut pow10 = Self::from_f64(1.0);
+            for _ in 0..step.unsigned_abs() {
+                pow10 = pow10 * 10.0;
+            }
+            value = if step < 0 {
+                value / pow10
+            } else if (value.hi * pow10.hi).is_finite() {
+                value * pow10
+            } else {
+                Self::from_f64(f64::INFINITY)
+            };
+            exponent -= step;
+        }
+
+        Ok(if negative { -value } else { value })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> DoubleDouble {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn keeps_digits_past_f64() {
+        let tenth = parse("0.1");
+        assert_eq!(tenth.hi, 0.1);
+        // 0.1 - f64(0.1), to f64 precision
+        assert_eq!(tenth.lo, -5.551115123125783e-18);
+        assert_eq!(parse("-1.25"), DoubleDouble::from_f64(-1.25));
+        assert_eq!(parse(".5"), DoubleDouble::from_f64(0.5));
+        assert_eq!(parse("3e-21").hi, 3e-21);
+        let deep = "-0.743643887037158704752191506114774";
+        assert_eq!(parse(deep).hi, deep.parse::<f64>().unwrap());
+        assert_ne!(parse(deep).lo, 0.0);
+    }
+
+    #[test]
+    fn exponents_past_the_f64_range_scale_in_steps() {
+        assert_eq!(parse("1e-310").to_f64(), 1e-310);
+        assert_eq!(parse("100000000000000000000e-320").to_f64(), 1e-300);
+        assert_eq!(parse("1e308").to_f64(), 1e308);
+        assert_eq!(parse("1e-400"), DoubleDouble::ZERO);
+        assert_eq!(parse("1e309").hi, f64::INFINITY);
+        assert_eq!(parse("-1e400").hi, f64::NEG_INFINITY);
+    }
+
+    #[test]
+    fn rejects_malformed_decimals() {
+        for s in ["", ".", "abc", "1.2.3", "e5", "1e", "--1", "1e5.5", "nan"] {
+            assert!(s.parse::<DoubleDouble>().is_err(), "{s:?}");
+        }
+    }
+}
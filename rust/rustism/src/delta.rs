@@ -0,0 +1,94 @@
+/// Encodes escape counts, in the order given, for `Encoding::DeltaVarint`.
+/// Each count is stored as its difference from the one before it, with 0
+/// before the first. The signed difference is zigzag mapped to unsigned
+/// (0, -1, 1, -2, 2, ... become 0, 1, 2, 3, 4, ...) and written as an
+/// LEB128 varint: seven bits per byte, least significant group first, the
+/// top bit set on every byte but the last. Neighbors mostly differ by a
+/// few iterations, so most counts take a single byte.
+pub fn encode_counts(counts: &[u32]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(counts.len());
+    let mut previous = 0i64;
+    for &count in counts {
+        let delta = count as i64 - previous;
+        previous = count as i64;
+        let mut zigzag = ((delta << 1) ^ (delta >> 63)) as u64;
+        while zigzag >= 0x80 {
+            out.push(zigzag as u8 | 0x80);
+            zigzag >>= 7;
+        }
+        out.push(zigzag as u8);
+    }
+    out
+}
+
+/// Inverse of `encode_counts`, or `None` if `data` ends mid-varint or
+/// decodes to counts outside u32.
+pub fn decode_counts(data: &[u8]) -> Option<Vec<u32>> {
+    let mut counts = Vec::with_capacity(data.len());
+    let mut previous = 0i64;
+    let (mut zigzag, mut shift) = (0u64, 0);
+    for &byte in data {
+        // A u32 difference zigzags into at most 33 bits, five bytes
+        if shift > 28 {
+            return None;
+        }
+        zigzag |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 != 0 {
+            shift += 7;
+            continue;
+        }
+        let delta = (zigzag >> 1) as i64 ^ -((zigzag & 1) as i64);
+        previous += delta;
+        counts.push(u32::try_from(previous).ok()?);
+        (zigzag, shift) = (0, 0);
+    }
+    (shift == 0).then_some(counts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_round_trip() {
+        let cases: [&[u32]; 6] = [
+            &[],
+            &[0],
+            &[5, 5, 6, 4, 4, 200, 3],
+            // The largest differences either way
+            &[u32::MAX, 0, u32::MAX, u32::MAX],
+            &[0, u32::MAX, u32::MAX - 1, 1],
+            &[1000, 999, 998, 70_000, 65_535, 65_536],
+        ];
+        for counts in cases {
+            let encoded = encode_counts(counts);
+            assert_eq!(decode_counts(&encoded).as_deref(), Some(counts));
+        }
+        let ramp: Vec<u32> = (0..5000).map(|i| (i * 7919) % 3001).collect();
+        assert_eq!(decode_counts(&encode_counts(&ramp)), Some(ramp));
+    }
+
+    #[test]
+    fn known_encodings() {
+        // Deltas 3, -1, 1, -2 zigzag to 6, 1, 2, 3
+        assert_eq!(encode_counts(&[0]), [0]);
+        assert_eq!(encode_counts(&[3, 2, 3, 1]), [6, 1, 2, 3]);
+        // 64 zigzags to 128, which takes a second byte
+        assert_eq!(encode_counts(&[64]), [0x80, 0x01]);
+        // u32::MAX zigzags to 2^33 - 2, five bytes
+        assert_eq!(encode_counts(&[u32::MAX]), [0xfe, 0xff, 0xff, 0xff, 0x1f]);
+        assert_eq!(encode_counts(&[u32::MAX, 0]).len(), 10);
+    }
+
+    #[test]
+    fn malformed_data_is_rejected() {
+        // Ends mid-varint
+        assert_eq!(decode_counts(&[0x80]), None);
+        assert_eq!(decode_counts(&[6, 0xff]), None);
+        // A varint longer than any u32 difference needs
+        assert_eq!(decode_counts(&[0x80, 0x80, 0x80, 0x80, 0x80, 0x01]), None);
+        // -1 before any count, then u32::MAX + 1
+        assert_eq!(decode_counts(&[1]), None);
+        assert_eq!(decode_counts(&[0xfe, 0xff, 0xff, 0xff, 0x1f, 2]), None);
+    }
+}
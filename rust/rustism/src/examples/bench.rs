@@ -0,0 +1,129 @@
+use crate::model::{
+    RenderParams, RenderSummary, TaskRecord, TileData, Viewport, mandelbrot_iterations,
+    render_tile, tiles_for,
+};
+use crate::pool::{ThreadPool, available_threads};
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    time::Instant,
+};
+
+/// Renders the default full-set view of the Mandelbrot set on
+/// `num_threads` threads without calling back into Python, and returns its
+/// summary with the throughput filled in. `iterations_per_sec` counts the
+/// iterations actually run, so interior points skipped by the main-body
+/// check count for nothing.
+#[pyfunction]
+pub fn bench(
+    py: Python<'_>,
+    width: usize,
+    height: usize,
+    tile_w: usize,
+    tile_h: usize,
+    max_iter: u32,
+    num_threads: usize,
+) -> PyResult<RenderSummary> {
+    if tile_w == 0 || tile_h == 0 {
+        return Err(PyValueError::new_err("tile dimensions must be positive"));
+    }
+    // 0 means "one thread per available core", as in `concurrent`
+    let num_threads = match num_threads {
+        0 => available_threads(),
+        n => n,
+    };
+
+    let params = RenderParams::plain_mandelbrot(
+        width,
+        height,
+        max_iter,
+        Viewport::default_for(width, height),
+    );
+    params.validate()?;
+    let tiles = Arc::new(tiles_for(width, height, tile_w, tile_h));
+    let next = Arc::new(AtomicUsize::new(0));
+    let (sender, receiver) = mpsc::channel::<(TaskRecord, u64)>();
+
+    // Spawning the threads is left out of the timing
+    let pool = ThreadPool::new(num_threads);
+    let overall_start = Instant::now();
+    for thread_id in 0..pool.size().min(tiles.len()) {
+        let (tiles, next, sender) = (Arc::clone(&tiles), Arc::clone(&next), sender.clone());
+        pool.execute(move || {
+            let mut scratch = TileData::default();
+            loop {
+                // Tiles are handed out in task_id order, as in `concurrent`
+                let task_id = next.fetch_add(1, Ordering::Relaxed);
+                let Some(&(tx, ty, tw, th)) = tiles.get(task_id) else {
+                    break;
+                };
+                let start = Instant::now();
+                let start_time_ms = overall_start.elapsed().as_millis();
+                render_tile(&params, tx, ty, tw, th, &mut scratch);
+
+                let TileData::Iterations(counts) = &scratch else {
+                    unreachable!("iteration output always renders counts")
+                };
+                let radius_sq = params.escape_radius_sq();
+                let iterations = counts
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &count)| {
+                        let (re, im) =
+                            params.to_complex((tx + i % tw) as f64, (ty + i / tw) as f64);
+                        mandelbrot_iterations(re, im, count, max_iter, radius_sq) as u64
+                    })
+                    .sum();
+                let (min_iter, max_iter_reached) = scratch.iteration_range(max_iter);
+
+                let record = TaskRecord {
+                    task_id: task_id as u32,
+                    thread_id: thread_id as u32,
+                    tile_x: tx as u32,
+                    tile_y: ty as u32,
+                    tile_w: tw as u32,
+                    tile_h: th as u32,
+                    start_time_ms,
+                    duration_ms: start.elapsed().as_millis(),
+                    pixels_computed: counts.len() as u32,
+                    depth: 0,
+                    min_iter,
+                    max_iter_reached,
+                    cached: false,
+                    supersampled: 0,
+                    guessed: 0,
+                    checksum: None,
+                    uniform_value: None,
+                };
+                if sender.send((record, iterations)).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+    drop(sender);
+
+    // Ends once every worker has dropped its sender
+    let results: Vec<(TaskRecord, u64)> = py.detach(move || receiver.iter().collect());
+    let elapsed = overall_start.elapsed();
+    if results.len() < tiles.len() {
+        return Err(PyRuntimeError::new_err("a benchmark worker panicked"));
+    }
+
+    let iterations: u64 = results.iter().map(|&(_, iterations)| iterations).sum();
+    let mut records: Vec<TaskRecord> = results.into_iter().map(|(record, _)| record).collect();
+    records.sort_by_key(|record| record.task_id);
+    let seconds = elapsed.as_secs_f64().max(f64::MIN_POSITIVE);
+
+    let summary = RenderSummary::new(&records, tiles.len(), elapsed.as_millis());
+    Ok(RenderSummary {
+        pixels_per_sec: summary.total_pixels as f64 / seconds,
+        iterations_per_sec: iterations as f64 / seconds,
+        ..summary
+    })
+}
@@ -0,0 +1,33 @@
+use crate::pool::available_threads;
+use pyo3::IntoPyObject;
+use pyo3::prelude::*;
+
+/// What this build of the extension can do, returned as a dict.
+#[derive(Debug, IntoPyObject)]
+pub struct Capabilities {
+    // Crate version the extension was built from
+    pub version: &'static str,
+    // Optional Cargo features compiled in
+    pub simd: bool,
+    pub trace: bool,
+    pub double_double: bool,
+    pub pin_threads: bool,
+    pub metrics: bool,
+    // Threads `num_threads = 0` uses, see `recommended_threads`
+    pub logical_cores: usize,
+}
+
+/// How the installed extension was built: its version, which optional
+/// features are compiled in, and how many logical cores it sees.
+#[pyfunction]
+pub fn capabilities() -> Capabilities {
+    Capabilities {
+        version: env!("CARGO_PKG_VERSION"),
+        simd: cfg!(feature = "simd"),
+        trace: cfg!(feature = "trace"),
+        double_double: cfg!(feature = "double-double"),
+        pin_threads: cfg!(feature = "pin-threads"),
+        metrics: cfg!(feature = "metrics"),
+        logical_cores: available_threads(),
+    }
+}
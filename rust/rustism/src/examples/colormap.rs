@@ -0,0 +1,76 @@
+use crate::color::{Coloring, PALETTE_CLASSIC, PaletteArg, Tone};
+use crate::model::OutputMode;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use std::sync::{Mutex, PoisonError};
+
+// (palette code, max_iter, exposure, gamma, palette_offset bits)
+type LutKey = (u8, u32, u64, u64, u32);
+
+// The last built-in colormap handed out, since a render loop asks for the
+// same one every frame
+static LAST_LUT: Mutex<Option<(LutKey, Py<PyBytes>)>> = Mutex::new(None);
+
+/// RGB color of every escape count from 0 to `max_iter`, as a render with
+/// the same palette and tone would color it: `(max_iter + 1) * 3` bytes,
+/// row `i` for count `i`. Interior pixels, at `max_iter`, are black. Read
+/// it with `numpy.frombuffer(lut, numpy.uint8).reshape(-1, 3)` and color a
+/// tile of counts with `lut[counts]`. Built-in palettes are cached, so
+/// asking again for the last one returns the same bytes without building it.
+#[pyfunction]
+#[pyo3(signature = (
+    max_iter,
+    palette = PaletteArg::Code(PALETTE_CLASSIC),
+    exposure = 1.0,
+    gamma = 1.0,
+    palette_offset = 0.0,
+))]
+pub fn colormap(
+    py: Python<'_>,
+    max_iter: u32,
+    palette: PaletteArg,
+    exposure: f64,
+    gamma: f64,
+    palette_offset: f32,
+) -> PyResult<Py<PyBytes>> {
+    let tone = Tone::new(exposure, gamma)?;
+    let key = match &palette {
+        PaletteArg::Code(code) => Some((
+            *code,
+            max_iter,
+            exposure.to_bits(),
+            gamma.to_bits(),
+            palette_offset.to_bits(),
+        )),
+        PaletteArg::Callback(_) => None,
+    };
+    if let Some(key) = key
+        && let Some((last, lut)) = &*LAST_LUT.lock().unwrap_or_else(PoisonError::into_inner)
+        && *last == key
+    {
+        return Ok(lut.clone_ref(py));
+    }
+
+    let Some(coloring) = Coloring::new(
+        py,
+        Some(palette),
+        tone,
+        palette_offset,
+        OutputMode::Iterations,
+        max_iter,
+    )?
+    else {
+        unreachable!("a palette was passed")
+    };
+    let rgb: Vec<u8> = (0..=max_iter)
+        .flat_map(|i| {
+            let [r, g, b, _] = coloring.rgba(i as f64, max_iter).to_ne_bytes();
+            [r, g, b]
+        })
+        .collect();
+    let lut = PyBytes::new(py, &rgb).unbind();
+    if let Some(key) = key {
+        *LAST_LUT.lock().unwrap_or_else(PoisonError::into_inner) = Some((key, lut.clone_ref(py)));
+    }
+    Ok(lut)
+}
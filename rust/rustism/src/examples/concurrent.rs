@@ -1,130 +1,1700 @@
-use crate::model::{TaskRecord, TileUpdate, render_tile};
+use crate::cache::{CachedTile, TileCache, frame_key, tile_key};
+use crate::color::{Coloring, PaletteArg};
+use crate::metrics::TileInFlight;
+use crate::model::{
+    self, CoordinateArg, Cutoff, DEFAULT_CENTER_IM, DEFAULT_CENTER_RE, ENCODING_RAW, Emitter,
+    Encoding, FRACTAL_MANDELBROT, OUTPUT_ITERATIONS, OrbitState, PRECISION_F64, PixelRect,
+    Progress, ReferenceOrbit, RenderArgs, RenderOutput, RenderParams, RenderRequest, RenderSummary,
+    SAMPLES_GRID, TRAP_POINT, TRAVERSAL_ROW_MAJOR, TaskRecord, TileData, TileFormat, TileLayout,
+    TileUpdate, cancel_requested, check_coarse_iter, check_final_z, check_iteration_budget,
+    estimate_tile_cost, final_z_tile, overlaps, preview_dims, render_tile_coarse,
+    render_tile_perturbed_until, render_tile_preview, render_tile_until, resume_tile, subdivide,
+    tiles_for,
+};
+use crate::pool::{ThreadPool, available_threads};
+use crate::trace::trace_event;
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
 use pyo3::prelude::*;
 use std::{
+    any::Any,
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+    panic::{self, AssertUnwindSafe},
     sync::{
-        Arc, Mutex,
-        atomic::{AtomicBool, Ordering},
+        Arc, Condvar, Mutex, MutexGuard, PoisonError,
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        mpsc,
     },
     time::{Duration, Instant},
 };
 
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+// Tiles are only subdivided while both quadrants stay at least this big
+const MIN_SUBTILE: usize = 8;
+
+// Task whose worker panics before rendering it, see `_panic_at_task`
+#[cfg(debug_assertions)]
+static PANIC_AT_TASK: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+/// Debug builds only: makes the worker that takes tile `task_id` of later
+/// renders panic, so tests can check the panic comes back as an exception.
+/// `None` turns it off again.
+#[cfg(debug_assertions)]
+#[pyfunction]
+#[pyo3(signature = (task_id = None))]
+pub fn _panic_at_task(task_id: Option<usize>) {
+    PANIC_AT_TASK.store(task_id.unwrap_or(usize::MAX), Ordering::Relaxed);
+}
+
+/// Debug builds only: worker threads pools have spawned so far, so tests
+/// can check a render ran without any.
+#[cfg(debug_assertions)]
 #[pyfunction]
+pub fn _threads_spawned() -> usize {
+    crate::pool::THREADS_SPAWNED.load(Ordering::Relaxed)
+}
+
+#[pyfunction]
+#[pyo3(signature = (
+    width,
+    height,
+    tile_w,
+    tile_h,
+    max_iter,
+    emit_tile,
+    time_limit_ms,
+    num_threads,
+    center_re = CoordinateArg::Float(DEFAULT_CENTER_RE),
+    center_im = CoordinateArg::Float(DEFAULT_CENTER_IM),
+    scale = None,
+    fractal_kind = FRACTAL_MANDELBROT,
+    julia_re = 0.0,
+    julia_im = 0.0,
+    smooth = false,
+    contiguous = false,
+    should_cancel = None,
+    on_progress = None,
+    precision = PRECISION_F64,
+    histogram = false,
+    power = 2,
+    subdivide_after_ms = None,
+    output_mode = OUTPUT_ITERATIONS,
+    samples_per_axis = 1,
+    deep_center = None,
+    escape_radius = 2.0,
+    palette = None,
+    interior = false,
+    trap = TRAP_POINT,
+    trap_re = 0.0,
+    trap_im = 0.0,
+    summary = false,
+    simulate_load = false,
+    skip_task_ids = None,
+    batch_size = None,
+    periodicity = false,
+    emit_during = false,
+    max_pending_tiles = None,
+    focus_x = None,
+    focus_y = None,
+    light_angle = 45.0,
+    light_height = 1.5,
+    preview_stride = None,
+    flip_y = false,
+    with_checksum = false,
+    dirty_x = None,
+    dirty_y = None,
+    dirty_w = None,
+    dirty_h = None,
+    exposure = 1.0,
+    gamma = 1.0,
+    aa_threshold = None,
+    solid_guess = false,
+    sample_pattern = SAMPLES_GRID,
+    check_every = 1,
+    palette_offset = 0.0,
+    iteration_budget = None,
+    pixel_center = false,
+    coarse_iter = None,
+    encoding = ENCODING_RAW,
+    cost_balance = false,
+    reference_orbit = None,
+    deterministic = false,
+    final_z = false,
+    stripe_freq = 5.0,
+    pin_threads = false,
+    skip_uniform = false,
+    stats_only = false,
+    traversal = TRAVERSAL_ROW_MAJOR,
+))]
+#[allow(clippy::too_many_arguments)]
 pub fn concurrent(
     py: Python<'_>,
     width: usize,
     height: usize,
     tile_w: usize,
     tile_h: usize,
-    max_iter: u16,
-    emit_tile: PyObject,
+    max_iter: u32,
+    emit_tile: Option<Py<PyAny>>,
     time_limit_ms: u64,
     num_threads: usize,
-) -> PyResult<Vec<TaskRecord>> {
-    // Collect all tile coordinates with their task_ids first
-    let mut tiles = Vec::new();
-    let mut task_id = 0;
-    for ty in (0..height).step_by(tile_h) {
-        for tx in (0..width).step_by(tile_w) {
-            tiles.push((task_id, tx, ty));
-            task_id += 1;
-        }
-    }
-
-    let overall_start = Instant::now();
-    let time_limit = Duration::from_millis(time_limit_ms);
-
-    // Shared state for time limit checking
-    let time_exceeded = Arc::new(AtomicBool::new(false));
-    // Shared state for collecting results
-    let records = Arc::new(Mutex::new(Vec::new()));
-
-    // Scoped threads to share references during computations
-    std::thread::scope(|s| {
-        // Divide work among threads
-        let chunk_size = (tiles.len() + num_threads - 1) / num_threads;
-
-        for (thread_id, tile_chunk) in tiles.chunks(chunk_size).enumerate() {
-            let time_exceeded = Arc::clone(&time_exceeded);
-            let records = Arc::clone(&records);
-            let overall_start = overall_start; // Copy for thread
-
-            // Spawn a thread for this chunk of tiles
-            s.spawn(move || {
-                for &(task_id, tx, ty) in tile_chunk {
-                    // Check if TLE
-                    if time_exceeded.load(Ordering::Relaxed) {
-                        break;
-                    }
+    center_re: CoordinateArg,
+    center_im: CoordinateArg,
+    scale: Option<f64>,
+    fractal_kind: u8,
+    julia_re: f64,
+    julia_im: f64,
+    smooth: bool,
+    contiguous: bool,
+    should_cancel: Option<Py<PyAny>>,
+    on_progress: Option<Py<PyAny>>,
+    precision: u8,
+    histogram: bool,
+    power: u32,
+    subdivide_after_ms: Option<u64>,
+    output_mode: u8,
+    samples_per_axis: u32,
+    deep_center: Option<(String, String)>,
+    escape_radius: f64,
+    palette: Option<PaletteArg>,
+    interior: bool,
+    trap: u8,
+    trap_re: f64,
+    trap_im: f64,
+    summary: bool,
+    simulate_load: bool,
+    skip_task_ids: Option<HashSet<u32>>,
+    batch_size: Option<usize>,
+    periodicity: bool,
+    emit_during: bool,
+    max_pending_tiles: Option<usize>,
+    focus_x: Option<f64>,
+    focus_y: Option<f64>,
+    light_angle: f64,
+    light_height: f64,
+    preview_stride: Option<usize>,
+    flip_y: bool,
+    with_checksum: bool,
+    dirty_x: Option<usize>,
+    dirty_y: Option<usize>,
+    dirty_w: Option<usize>,
+    dirty_h: Option<usize>,
+    exposure: f64,
+    gamma: f64,
+    aa_threshold: Option<f64>,
+    solid_guess: bool,
+    sample_pattern: u8,
+    check_every: u16,
+    palette_offset: f32,
+    iteration_budget: Option<u64>,
+    pixel_center: bool,
+    coarse_iter: Option<u32>,
+    encoding: u8,
+    cost_balance: bool,
+    reference_orbit: Option<PyRef<'_, ReferenceOrbit>>,
+    deterministic: bool,
+    final_z: bool,
+    stripe_freq: f64,
+    pin_threads: bool,
+    skip_uniform: bool,
+    stats_only: bool,
+    traversal: u8,
+) -> PyResult<RenderOutput> {
+    // 0 means "one thread per available core"
+    let num_threads = match num_threads {
+        0 => available_threads(),
+        n => n,
+    };
+
+    let RenderRequest {
+        params,
+        orbit,
+        coloring,
+        encoding,
+        histogram,
+        summary,
+        dirty,
+        focus,
+    } = RenderRequest::from_args(
+        py,
+        RenderArgs {
+            width,
+            height,
+            max_iter,
+            emit_tile: emit_tile.as_ref(),
+            center_re,
+            center_im,
+            scale,
+            fractal_kind,
+            julia_re,
+            julia_im,
+            smooth,
+            precision,
+            histogram,
+            summary,
+            stats_only,
+            power,
+            output_mode,
+            samples_per_axis,
+            sample_pattern,
+            deep_center,
+            reference_orbit: reference_orbit.as_deref(),
+            escape_radius,
+            palette,
+            exposure,
+            gamma,
+            palette_offset,
+            interior,
+            trap,
+            trap_re,
+            trap_im,
+            light_angle,
+            light_height,
+            stripe_freq,
+            periodicity,
+            flip_y,
+            aa_threshold,
+            solid_guess,
+            check_every,
+            pixel_center,
+            traversal,
+            encoding,
+            dirty_x,
+            dirty_y,
+            dirty_w,
+            dirty_h,
+            focus_x,
+            focus_y,
+        },
+    )?;
+
+    // With no more tiles than threads, spawning the pool costs more than
+    // it saves, so they run on this thread instead. Polling callbacks and
+    // bounded tiles need this thread free while the workers run, so those
+    // keep the pool. Zero tile sizes are rejected in `render_on_pool`.
+    let tiles = width.div_ceil(tile_w.max(1)) * height.div_ceil(tile_h.max(1));
+    let inline = tiles <= num_threads
+        && !pin_threads
+        && should_cancel.is_none()
+        && on_progress.is_none()
+        && max_pending_tiles.is_none();
+    // A fresh pool per call; `Renderer` keeps one alive across calls instead
+    let pool = match pin_threads {
+        _ if inline => None,
+        false => Some(ThreadPool::new(num_threads)),
+        #[cfg(feature = "pin-threads")]
+        true => Some(ThreadPool::pinned(num_threads)),
+        #[cfg(not(feature = "pin-threads"))]
+        true => {
+            return Err(PyValueError::new_err(
+                "pin_threads needs the pin-threads feature",
+            ));
+        }
+    };
+    render_on_pool(
+        py,
+        pool.as_ref(),
+        params,
+        orbit,
+        coloring,
+        tile_w,
+        tile_h,
+        time_limit_ms,
+        iteration_budget,
+        subdivide_after_ms,
+        histogram,
+        summary,
+        simulate_load,
+        skip_task_ids.as_ref(),
+        dirty,
+        focus,
+        batch_size,
+        emit_during,
+        max_pending_tiles,
+        preview_stride,
+        coarse_iter,
+        cost_balance,
+        deterministic,
+        final_z,
+        skip_uniform,
+        with_checksum,
+        None,
+        None,
+        contiguous,
+        encoding,
+        emit_tile.as_ref(),
+        should_cancel.as_ref(),
+        on_progress.as_ref(),
+    )
+}
+
+// (task_id, depth, x, y, w, h)
+type Job = (usize, u32, usize, usize, usize, usize);
+// Emitted copy of a tile's data, its format, and its final_z if asked for
+type Emitted = (TileData, TileFormat, Option<TileData>);
+
+/// The quick pass over every tile that goes out before the full one.
+#[derive(Debug, Clone, Copy)]
+enum FirstPass {
+    /// Every `stride`-th pixel, see `render_tile_preview`
+    Strided(usize),
+    /// Every pixel up to `coarse_iter`, see `render_tile_coarse`
+    Coarse(u32),
+}
+
+/// A pending tile, ordered so the heap pops the one whose center is
+/// closest to the focus first, then the shallowest, then the costliest,
+/// then the lowest task_id.
+#[derive(Clone, Copy)]
+struct Queued {
+    distance_sq: f64,
+    // From `estimate_tile_cost` with cost_balance, else 0
+    cost: u64,
+    job: Job,
+}
+
+impl Queued {
+    fn new(job: Job, focus: Option<(f64, f64)>, cost: u64) -> Self {
+        let (_, _, x, y, w, h) = job;
+        let distance_sq = focus.map_or(0.0, |(fx, fy)| {
+            let dx = x as f64 + w as f64 / 2.0 - fx;
+            let dy = y as f64 + h as f64 / 2.0 - fy;
+            dx * dx + dy * dy
+        });
+        Self {
+            distance_sq,
+            cost,
+            job,
+        }
+    }
+
+    fn key(&self) -> (f64, u32, Reverse<u64>, usize) {
+        (self.distance_sq, self.job.1, Reverse(self.cost), self.job.0)
+    }
+}
+
+impl Ord for Queued {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        let (a, b) = (self.key(), other.key());
+        // Reversed, since BinaryHeap is a max-heap
+        b.0.total_cmp(&a.0)
+            .then(b.1.cmp(&a.1))
+            .then(b.2.cmp(&a.2))
+            .then(b.3.cmp(&a.3))
+    }
+}
+
+impl PartialOrd for Queued {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Queued {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for Queued {}
+
+/// Counters of a `Renderer`'s current, or else last, frame that other
+/// Python threads can poll while it renders. They're read with relaxed
+/// ordering, so a read is a recent value rather than an exact one.
+#[derive(Debug)]
+pub(crate) struct LiveProgress {
+    epoch: Instant,
+    tiles_completed: AtomicUsize,
+    // Nanoseconds after `epoch` that the frame started and ended at; the
+    // end is 0 while it's running
+    started_ns: AtomicU64,
+    ended_ns: AtomicU64,
+    // The frame's cancel flag, so `cancel` can reach it from outside
+    cancelled: Arc<AtomicBool>,
+}
+
+impl LiveProgress {
+    pub(crate) fn new() -> Self {
+        Self {
+            epoch: Instant::now(),
+            tiles_completed: AtomicUsize::new(0),
+            started_ns: AtomicU64::new(0),
+            ended_ns: AtomicU64::new(0),
+            cancelled: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Finished tiles, cache hits and sub-tiles included.
+    pub(crate) fn tiles_completed(&self) -> usize {
+        self.tiles_completed.load(Ordering::Relaxed)
+    }
+
+    /// Time the frame has been running for, or took once it's over.
+    pub(crate) fn elapsed_ms(&self) -> u64 {
+        let started = self.started_ns.load(Ordering::Relaxed);
+        let ended = match self.ended_ns.load(Ordering::Relaxed) {
+            0 => self.now_ns(),
+            ended => ended,
+        };
+        ended.saturating_sub(started) / 1_000_000
+    }
+
+    /// Stops the running frame, in-flight tiles included.
+    pub(crate) fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether the last frame was cancelled; a new frame clears it.
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    fn now_ns(&self) -> u64 {
+        // Never 0, which marks a frame still running
+        (self.epoch.elapsed().as_nanos() as u64).max(1)
+    }
+
+    // Resets the counters for a new frame, which ends when the guard drops
+    fn begin(&self) -> LiveFrameGuard<'_> {
+        self.tiles_completed.store(0, Ordering::Relaxed);
+        self.cancelled.store(false, Ordering::Relaxed);
+        self.ended_ns.store(0, Ordering::Relaxed);
+        self.started_ns.store(self.now_ns(), Ordering::Relaxed);
+        LiveFrameGuard(self)
+    }
+
+    fn add_tiles(&self, tiles: usize) {
+        self.tiles_completed.fetch_add(tiles, Ordering::Relaxed);
+    }
+}
+
+struct LiveFrameGuard<'a>(&'a LiveProgress);
+
+impl Drop for LiveFrameGuard<'_> {
+    fn drop(&mut self) {
+        self.0.ended_ns.store(self.0.now_ns(), Ordering::Relaxed);
+    }
+}
+
+/// State shared between the pool workers rendering one frame.
+struct Frame {
+    params: RenderParams,
+    // Set for deep zooms, see `render_tile_perturbed`
+    orbit: Option<ReferenceOrbit>,
+    coloring: Option<Coloring>,
+    contiguous: bool,
+    encoding: Encoding,
+    final_z: bool,
+    skip_uniform: bool,
+    overall_start: Instant,
+    time_limit: Duration,
+    subdivide_after: Option<Duration>,
+    simulate_load: bool,
+    // Work queue. Workers take the best pending tile as soon as they are
+    // free, so a thread stuck on slow interior tiles never holds up the
+    // others, and slow tiles can be split and pushed back.
+    pending: Mutex<BinaryHeap<Queued>>,
+    // Pixel position that tiles are prioritized by distance to, if any
+    focus: Option<(f64, f64)>,
+    // Tiles taken off the queue but not finished yet; they may still
+    // push sub-tiles, so an empty queue alone doesn't mean we're done
+    in_flight: AtomicUsize,
+    // With subdivision, idle workers wait on this for sub-tiles or for the
+    // last tile in flight to finish
+    queue_cv: Condvar,
+    time_exceeded: AtomicBool,
+    // Iterations finished tiles were charged, see `BudgetLedger`
+    iteration_budget: Option<u64>,
+    budget: Mutex<BudgetLedger>,
+    budget_cv: Condvar,
+    // Set by the main thread when should_cancel fires
+    // Also checked between rows, so a cancel cuts in-flight tiles short
+    cancelled: Arc<AtomicBool>,
+    // Finished pixels, for on_progress; subdivision makes tile counts move
+    pixels_done: AtomicUsize,
+    // Orbits of the coarse first pass, by task_id, for the full pass to resume
+    coarse_states: Mutex<HashMap<usize, Vec<OrbitState>>>,
+    // Finished tiles, with their data unless a worker emitted it already
+    records: Mutex<Vec<(TaskRecord, Option<Emitted>)>>,
+    // Tiles the time limit cut short, likewise
+    partials: Mutex<Vec<TileUpdate>>,
+    // With max_pending_tiles, workers wait while this many finished tiles
+    // are buffered, until the main thread drains and emits them. The default
+    // of None holds every tile until the frame is done, as before.
+    max_pending: Option<usize>,
+    drained_cv: Condvar,
+    // Whether there is an emit_tile to hand tiles to at all
+    emitting: bool,
+    // Set for emit_during, where workers emit each tile as they finish it
+    live_emit: Option<Py<PyAny>>,
+    batch_size: Option<usize>,
+    with_checksum: bool,
+    // A `Renderer`'s tile cache and the `frame_key` of this frame
+    cache: Option<(Arc<Mutex<TileCache>>, u64)>,
+    // A `Renderer`'s counters for polling mid-frame
+    live: Option<Arc<LiveProgress>>,
+    counts: Option<Mutex<Vec<u32>>>,
+    // Workers that have returned
+    finished: Mutex<usize>,
+    finished_cv: Condvar,
+    // First worker failure, raised once all workers are back
+    failure: Mutex<Option<RenderError>>,
+}
+
+/// Iterations charged against an `iteration_budget`, see
+/// `TileData::iteration_total`, by the order tiles were taken off the
+/// queue. That order doesn't depend on timing, so cutting the frame where
+/// the tiles taken first have spent the budget, as `sequential` does,
+/// leaves out the same tiles on every run and any number of threads.
+#[derive(Debug, Default)]
+struct BudgetLedger {
+    // Charge of each tile taken, once it has finished
+    charges: Vec<Option<u64>>,
+    // How many leading tiles have all finished, and what they spent
+    settled: usize,
+    spent: u64,
+    // Tiles taken from here on start past the budget
+    cut: Option<usize>,
+}
+
+impl BudgetLedger {
+    fn take(&mut self) -> usize {
+        self.charges.push(None);
+        self.charges.len() - 1
+    }
+
+    fn charge(&mut self, seq: usize, iterations: u64, budget: u64) {
+        self.charges[seq] = Some(iterations);
+        while self.cut.is_none()
+            && let Some(&Some(charge)) = self.charges.get(self.settled)
+        {
+            self.spent += charge;
+            self.settled += 1;
+            if self.spent >= budget {
+                self.cut = Some(self.settled);
+            }
+        }
+    }
+
+    // Whether the tile taken at `seq` is within budget, once that's known
+    fn within(&self, seq: usize) -> Option<bool> {
+        if self.cut.is_some_and(|cut| seq >= cut) {
+            Some(false)
+        } else {
+            (seq < self.settled).then_some(true)
+        }
+    }
+}
+
+/// Ways a frame can fail after the workers have started.
+#[derive(Debug)]
+enum RenderError {
+    /// A worker panicked, with its panic message
+    WorkerPanicked(String),
+    /// Shared frame state was left half-updated by a panicking thread
+    Poisoned,
+    /// `emit_tile` raised when a worker called it
+    Callback(PyErr),
+}
+
+impl<T> From<PoisonError<T>> for RenderError {
+    fn from(_: PoisonError<T>) -> Self {
+        RenderError::Poisoned
+    }
+}
+
+impl From<RenderError> for PyErr {
+    fn from(err: RenderError) -> Self {
+        match err {
+            RenderError::WorkerPanicked(message) => {
+                PyRuntimeError::new_err(format!("a render worker panicked: {message}"))
+            }
+            RenderError::Poisoned => {
+                PyRuntimeError::new_err("render state was poisoned by a panicking thread")
+            }
+            RenderError::Callback(err) => err,
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> Result<MutexGuard<'_, T>, RenderError> {
+    Ok(mutex.lock()?)
+}
+
+// For state that stays valid whatever a panicking holder was doing
+fn lock_unpoisoned<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Marks a worker as finished when dropped.
+struct FinishGuard<'a>(&'a Frame);
+
+impl Drop for FinishGuard<'_> {
+    fn drop(&mut self) {
+        *lock_unpoisoned(&self.0.finished) += 1;
+        self.0.finished_cv.notify_all();
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown panic"
+    }
+}
+
+impl Frame {
+    /// Counts a tile taken off the queue as done with. Under the queue's
+    /// lock, so a worker that just found the queue empty can't miss the
+    /// wakeup before it waits.
+    fn finish_tile(&self) -> Result<(), RenderError> {
+        let _pending = lock(&self.pending)?;
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+        self.queue_cv.notify_all();
+        Ok(())
+    }
+
+    /// Charges the tile taken at `seq` against the budget, then waits until
+    /// it's known whether the tile was within it, which takes every tile
+    /// taken before it to finish first.
+    fn settle(&self, seq: usize, iterations: u64, budget: u64) -> Result<bool, RenderError> {
+        let mut ledger = lock(&self.budget)?;
+        ledger.charge(seq, iterations, budget);
+        self.budget_cv.notify_all();
+        loop {
+            if let Some(within) = ledger.within(seq) {
+                return Ok(within);
+            }
+            // Tiles cut short by the time limit or a cancel are never charged
+            if self.time_exceeded.load(Ordering::Relaxed) || self.cancelled.load(Ordering::Relaxed)
+            {
+                return Ok(true);
+            }
+            ledger = self.budget_cv.wait_timeout(ledger, POLL_INTERVAL)?.0;
+        }
+    }
+
+    /// Renders `pass` previews of `jobs`, in order, until they run out or
+    /// the frame is over.
+    fn preview(
+        &self,
+        thread_id: usize,
+        jobs: &[Job],
+        next: &AtomicUsize,
+        pass: FirstPass,
+        sender: &mpsc::Sender<TileUpdate>,
+    ) {
+        let mut scratch = TileData::default();
+        loop {
+            if self.cancelled.load(Ordering::Relaxed)
+                || self.overall_start.elapsed() > self.time_limit
+            {
+                break;
+            }
+            let Some(&(task_id, depth, tx, ty, tw, th)) =
+                jobs.get(next.fetch_add(1, Ordering::Relaxed))
+            else {
+                break;
+            };
+
+            let start = Instant::now();
+            let start_time_ms = self.overall_start.elapsed().as_millis();
+            let stride = match pass {
+                FirstPass::Strided(stride) => {
+                    render_tile_preview(
+                        &self.params,
+                        self.orbit.as_ref(),
+                        tx,
+                        ty,
+                        tw,
+                        th,
+                        stride,
+                        &mut scratch,
+                    );
+                    stride
+                }
+                FirstPass::Coarse(coarse_iter) => {
+                    let mut state = Vec::new();
+                    let params = &self.params;
+                    render_tile_coarse(
+                        params,
+                        tx,
+                        ty,
+                        tw,
+                        th,
+                        coarse_iter,
+                        &mut state,
+                        &mut scratch,
+                    );
+                    lock_unpoisoned(&self.coarse_states).insert(task_id, state);
+                    1
+                }
+            };
+            let (cols, rows) = preview_dims(&self.params, (tx, ty, tw, th), stride);
+            let (data, format) = scratch.to_emitted(
+                self.coloring.as_ref(),
+                self.params.max_iter,
+                self.contiguous,
+                self.encoding,
+            );
+
+            let update = TileUpdate {
+                task_id: task_id as u32,
+                thread_id: thread_id as u32,
+                tile_x: tx as u32,
+                tile_y: ty as u32,
+                tile_w: tw as u32,
+                tile_h: th as u32,
+                data,
+                rows: rows as u32,
+                cols: cols as u32,
+                dtype: format.dtype(),
+                layout: TileLayout::new(format, cols, rows),
+                encoding: self.encoding.code(),
+                start_time_ms,
+                duration_ms: start.elapsed().as_millis(),
+                depth,
+                cached: false,
+                preview: true,
+                partial: false,
+                final_z: None,
+                uniform: false,
+            };
+            if sender.send(update).is_err() {
+                break;
+            }
+        }
+    }
+
+    fn work(&self, thread_id: usize) {
+        let _guard = FinishGuard(self);
+        let err = match panic::catch_unwind(AssertUnwindSafe(|| self.render_tiles(thread_id))) {
+            Ok(Ok(())) => return,
+            Ok(Err(err)) => err,
+            Err(payload) => RenderError::WorkerPanicked(panic_message(&*payload).to_owned()),
+        };
+        // Stop the other workers too; the frame is lost either way
+        self.cancelled.store(true, Ordering::Relaxed);
+        // Poisoning is only fallout of a panic, whose message says more
+        let mut failure = lock_unpoisoned(&self.failure);
+        if matches!(*failure, None | Some(RenderError::Poisoned)) {
+            *failure = Some(err);
+        }
+    }
+
+    fn render_tiles(&self, thread_id: usize) -> Result<(), RenderError> {
+        let params = &self.params;
 
-                    // Check TL
-                    if overall_start.elapsed() > time_limit {
-                        time_exceeded.store(true, Ordering::Relaxed);
+        // Thread-local histogram, merged once the worker is done
+        let mut local_counts = self
+            .counts
+            .as_ref()
+            .map(|_| vec![0u32; params.max_iter as usize + 1]);
+        // Every tile this worker takes is rendered into the same buffer
+        let mut scratch = TileData::default();
+        let mut emitter = self
+            .live_emit
+            .as_ref()
+            .map(|emit_tile| Emitter::new(Some(emit_tile), self.batch_size))
+            .transpose()
+            .map_err(RenderError::Callback)?;
+        // For the worker_done event
+        let (mut tiles_done, mut busy) = (0u32, Duration::ZERO);
+
+        loop {
+            // Check if TLE or cancelled
+            if self.time_exceeded.load(Ordering::Relaxed) || self.cancelled.load(Ordering::Relaxed)
+            {
+                break;
+            }
+
+            // Check TL
+            if self.overall_start.elapsed() > self.time_limit {
+                self.time_exceeded.store(true, Ordering::Relaxed);
+                trace_event!("time_exceeded", thread = thread_id);
+                break;
+            }
+            if self.iteration_budget.is_some() && lock(&self.budget)?.cut.is_some() {
+                break;
+            }
+
+            let job = {
+                let mut pending = lock(&self.pending)?;
+                let job = pending.pop().map(|queued| queued.job);
+                if job.is_some() {
+                    self.in_flight.fetch_add(1, Ordering::SeqCst);
+                }
+                // Numbered while the queue is locked, so in the order taken
+                let seq = match (job, self.iteration_budget) {
+                    (Some(_), Some(_)) => lock(&self.budget)?.take(),
+                    _ => 0,
+                };
+                job.map(|job| (seq, job))
+            };
+            let Some((seq, (task_id, depth, tx, ty, tw, th))) = job else {
+                // Without subdivision, tiles in flight can't queue any more
+                if self.subdivide_after.is_none() {
+                    break;
+                }
+                let pending = lock(&self.pending)?;
+                if pending.is_empty() {
+                    if self.in_flight.load(Ordering::SeqCst) == 0 {
                         break;
                     }
+                    drop(self.queue_cv.wait_timeout(pending, POLL_INTERVAL)?);
+                }
+                continue;
+            };
+            #[cfg(debug_assertions)]
+            if task_id == PANIC_AT_TASK.load(Ordering::Relaxed) {
+                panic!("injected at task {task_id}");
+            }
 
-                    let start = Instant::now();
-                    let start_time_ms = overall_start.elapsed().as_millis();
+            let start = Instant::now();
+            let start_time_ms = self.overall_start.elapsed().as_millis();
+            let tile_in_flight = TileInFlight::start();
 
-                    let data = render_tile(width, height, tx, ty, tile_w, tile_h, max_iter);
-                    let duration_ms = start.elapsed().as_millis();
+            // Queued tiles are already clipped to the image. The time limit
+            // is checked every row too, so one slow tile can't run far past it.
+            let frame_deadline = self.overall_start.checked_add(self.time_limit);
+            let deadline = match self.subdivide_after {
+                Some(budget) if tw >= 2 * MIN_SUBTILE && th >= 2 * MIN_SUBTILE => {
+                    let subdivide_at = start + budget;
+                    Some(frame_deadline.map_or(subdivide_at, |limit| limit.min(subdivide_at)))
+                }
+                _ => frame_deadline,
+            };
+            let out = &mut scratch;
+            // A cancel stops the tile at its next row too
+            let cutoff = Cutoff {
+                deadline,
+                cancel: Some(&self.cancelled),
+            };
+            // Only grid tiles had a coarse pass; sub-tiles start over
+            let coarse = match depth {
+                0 => lock_unpoisoned(&self.coarse_states).remove(&task_id),
+                _ => None,
+            };
+            let stats = match (&self.orbit, coarse) {
+                (_, Some(mut state)) => {
+                    resume_tile(params, tx, ty, tw, th, &mut state, cutoff, out)
+                }
+                (Some(orbit), None) => {
+                    render_tile_perturbed_until(params, orbit, tx, ty, tw, th, cutoff, out)
+                }
+                (None, None) => render_tile_until(params, tx, ty, tw, th, cutoff, out),
+            };
+            if self.simulate_load {
+                model::simulate_load(start);
+            }
+            let duration_ms = start.elapsed().as_millis();
+            busy += start.elapsed();
 
-                    // Store the result
-                    let record = TaskRecord {
+            // Out of time or cancelled: hand on the rows that made it and stop
+            let cut_short = scratch.len() < tw * th;
+            let cancelled = cut_short && self.cancelled.load(Ordering::Relaxed);
+            if cancelled || cut_short && frame_deadline.is_some_and(|limit| Instant::now() >= limit)
+            {
+                if !cancelled {
+                    self.time_exceeded.store(true, Ordering::Relaxed);
+                    trace_event!("time_exceeded", thread = thread_id);
+                }
+                if self.emitting && !scratch.is_empty() {
+                    let (data, format) = scratch.to_emitted(
+                        self.coloring.as_ref(),
+                        params.max_iter,
+                        self.contiguous,
+                        self.encoding,
+                    );
+                    let update = TileUpdate {
                         task_id: task_id as u32,
                         thread_id: thread_id as u32,
                         tile_x: tx as u32,
                         tile_y: ty as u32,
-                        tile_w: tile_w as u32,
-                        tile_h: tile_h as u32,
+                        tile_w: tw as u32,
+                        tile_h: th as u32,
+                        data,
+                        rows: (scratch.len() / tw) as u32,
+                        cols: tw as u32,
+                        dtype: format.dtype(),
+                        layout: TileLayout::new(format, tw, scratch.len() / tw),
+                        encoding: self.encoding.code(),
                         start_time_ms,
                         duration_ms,
-                        pixels_computed: (tile_w * tile_h) as u32,
+                        depth,
+                        cached: false,
+                        preview: false,
+                        partial: true,
+                        final_z: self.final_z.then(|| {
+                            let rows = scratch.len() / tw;
+                            final_z_tile(params, (tx, ty, tw, th), rows, self.contiguous)
+                        }),
+                        uniform: false,
                     };
+                    match emitter.as_mut() {
+                        Some(emitter) => Python::attach(|py| emitter.emit(py, update))
+                            .map_err(RenderError::Callback)?,
+                        None => lock(&self.partials)?.push(update),
+                    }
+                }
+                self.finish_tile()?;
+                break;
+            }
 
-                    // Lock and push to shared records
-                    records.lock().unwrap().push((
-                        record,
-                        data,
-                        start_time_ms,
-                        duration_ms,
-                        tx,
-                        ty,
-                        thread_id,
-                    ));
+            // Over budget: drop the partial tile and queue its quadrants
+            if scratch.len() < tw * th {
+                trace_event!(
+                    "subdivide",
+                    thread = thread_id,
+                    task_id = task_id,
+                    depth = depth,
+                    rows_done = scratch.len() / tw,
+                    ms = duration_ms,
+                );
+                // Quadrants queue behind every grid tile, so they go unestimated
+                lock(&self.pending)?.extend(subdivide(task_id, tx, ty, tw, th).map(
+                    |(sub_id, x, y, w, h)| {
+                        Queued::new((sub_id, depth + 1, x, y, w, h), self.focus, 0)
+                    },
+                ));
+                self.queue_cv.notify_all();
+                // The quadrants are charged as tiles of their own
+                if let Some(budget) = self.iteration_budget {
+                    self.settle(seq, 0, budget)?;
                 }
+                self.finish_tile()?;
+                continue;
+            }
+
+            let iterations = scratch.iteration_total(params.max_iter);
+            tile_in_flight.finish(scratch.len(), || iterations);
+            if let Some(budget) = self.iteration_budget
+                && !self.settle(seq, iterations, budget)?
+            {
+                // `sequential` would have stopped before starting it
+                self.finish_tile()?;
+                continue;
+            }
+            if let Some(local_counts) = local_counts.as_mut() {
+                scratch.add_to_histogram(local_counts);
+            }
+
+            // Store the result
+            let (min_iter, max_iter_reached) = scratch.iteration_range(params.max_iter);
+            // Cached tiles keep theirs for later hits that ask for it
+            let caching = depth == 0 && self.cache.is_some();
+            let checksum = (self.with_checksum || caching).then(|| scratch.checksum());
+            let uniform_value = self.skip_uniform.then(|| scratch.uniform_value()).flatten();
+            // Coloring happens here so it is spread over the workers too
+            let emitted = self.emitting.then(|| {
+                let values = if uniform_value.is_some() {
+                    &scratch.first_pixel()
+                } else {
+                    &scratch
+                };
+                let (data, format) = values.to_emitted(
+                    self.coloring.as_ref(),
+                    params.max_iter,
+                    self.contiguous,
+                    self.encoding,
+                );
+                let final_z = self
+                    .final_z
+                    .then(|| final_z_tile(params, (tx, ty, tw, th), th, self.contiguous));
+                (data, format, final_z)
             });
+            // Sub-tiles don't line up with the next frame's grid
+            if depth == 0
+                && let (Some((cache, frame_key)), Some(checksum)) = (&self.cache, checksum)
+            {
+                let tile = CachedTile {
+                    values: scratch.clone(),
+                    min_iter,
+                    max_iter_reached,
+                    checksum,
+                };
+                lock_unpoisoned(cache).insert(tile_key(*frame_key, params, tx, ty, tw, th), tile);
+            }
+            let record = TaskRecord {
+                task_id: task_id as u32,
+                thread_id: thread_id as u32,
+                tile_x: tx as u32,
+                tile_y: ty as u32,
+                tile_w: tw as u32,
+                tile_h: th as u32,
+                start_time_ms,
+                duration_ms,
+                pixels_computed: scratch.len() as u32,
+                depth,
+                min_iter,
+                max_iter_reached,
+                cached: false,
+                supersampled: stats.supersampled as u32,
+                guessed: stats.guessed as u32,
+                checksum: checksum.filter(|_| self.with_checksum),
+                uniform_value,
+            };
+
+            // Lock and push to shared records
+            self.pixels_done.fetch_add(scratch.len(), Ordering::Relaxed);
+            // Tiles nobody asked for are dropped here
+            let tile = match (emitter.as_mut(), emitted) {
+                (Some(emitter), Some(emitted)) => {
+                    Python::attach(|py| {
+                        emitter.emit(py, tile_update(&record, emitted, self.encoding))
+                    })
+                    .map_err(RenderError::Callback)?;
+                    None
+                }
+                (_, emitted) => emitted.filter(|_| self.emitting),
+            };
+            let mut records = lock(&self.records)?;
+            if let Some(max_pending) = self.max_pending
+                && tile.is_some()
+            {
+                while records.len() >= max_pending && !self.cancelled.load(Ordering::Relaxed) {
+                    // Wake the main thread to drain now rather than next poll
+                    self.finished_cv.notify_all();
+                    records = self.drained_cv.wait_timeout(records, POLL_INTERVAL)?.0;
+                }
+            }
+            records.push((record, tile));
+            drop(records);
+            if let Some(live) = &self.live {
+                live.add_tiles(1);
+            }
+            self.finish_tile()?;
+            tiles_done += 1;
+        }
+        trace_event!(
+            "worker_done",
+            thread = thread_id,
+            tiles = tiles_done,
+            busy_ms = busy.as_millis(),
+            wall_ms = self.overall_start.elapsed().as_millis(),
+        );
+        if let Some(emitter) = emitter.as_mut() {
+            Python::attach(|py| emitter.flush(py)).map_err(RenderError::Callback)?;
         }
-    });
 
-    // Now emit all tiles to Python (via main thread)
-    let mut results = Arc::try_unwrap(records)
-        .expect("Failed to unwrap Arc")
-        .into_inner()
-        .unwrap();
+        if let (Some(counts), Some(local_counts)) = (&self.counts, local_counts) {
+            let mut counts = lock(counts)?;
+            for (total, local) in counts.iter_mut().zip(local_counts) {
+                *total += local;
+            }
+        }
+        Ok(())
+    }
+}
 
-    // For parallel visualization, sort by start_time_ms or don't sort at all
+fn tile_update(
+    record: &TaskRecord,
+    (data, format, final_z): Emitted,
+    encoding: Encoding,
+) -> TileUpdate {
+    TileUpdate {
+        task_id: record.task_id,
+        thread_id: record.thread_id,
+        tile_x: record.tile_x,
+        tile_y: record.tile_y,
+        tile_w: record.tile_w,
+        tile_h: record.tile_h,
+        data,
+        rows: record.tile_h,
+        cols: record.tile_w,
+        dtype: format.dtype(),
+        layout: match record.uniform_value {
+            Some(_) => TileLayout::new(format, 1, 1),
+            None => TileLayout::new(format, record.tile_w as usize, record.tile_h as usize),
+        },
+        encoding: encoding.code(),
+        start_time_ms: record.start_time_ms,
+        duration_ms: record.duration_ms,
+        depth: record.depth,
+        cached: record.cached,
+        preview: false,
+        partial: false,
+        final_z,
+        uniform: record.uniform_value.is_some(),
+    }
+}
 
-    // Sort by start_time to show completion order (shows true parallelism)
-    results.sort_by_key(|(record, _, start_time_ms, _, _, _, _)| *start_time_ms);
+// Emits finished tiles in the order they started, keeping their records
+fn emit_finished(
+    py: Python<'_>,
+    emitter: &mut Emitter<'_>,
+    mut finished: Vec<(TaskRecord, Option<Emitted>)>,
+    encoding: Encoding,
+    deterministic: bool,
+    records: &mut Vec<TaskRecord>,
+) -> PyResult<()> {
+    if deterministic {
+        finished.sort_by_key(|(record, _)| (record.depth, record.task_id));
+    } else {
+        // Sort by start_time to show completion order (shows true parallelism)
+        finished.sort_by_key(|(record, _)| record.start_time_ms);
+    }
 
+    for (record, tile) in finished {
+        if let Some(emitted) = tile {
+            emitter.emit(py, tile_update(&record, emitted, encoding))?;
+        }
+        records.push(record);
+    }
+    Ok(())
+}
+
+/// Renders one frame on `pool` and emits its tiles, as `concurrent` does.
+/// Without a pool, a single worker renders them on the calling thread,
+/// which can't poll callbacks meanwhile.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn render_on_pool(
+    py: Python<'_>,
+    pool: Option<&ThreadPool>,
+    params: RenderParams,
+    orbit: Option<ReferenceOrbit>,
+    coloring: Option<Coloring>,
+    tile_w: usize,
+    tile_h: usize,
+    time_limit_ms: u64,
+    iteration_budget: Option<u64>,
+    subdivide_after_ms: Option<u64>,
+    histogram: bool,
+    summary: bool,
+    simulate_load: bool,
+    skip_task_ids: Option<&HashSet<u32>>,
+    dirty: Option<PixelRect>,
+    focus: Option<(f64, f64)>,
+    batch_size: Option<usize>,
+    emit_during: bool,
+    max_pending_tiles: Option<usize>,
+    preview_stride: Option<usize>,
+    coarse_iter: Option<u32>,
+    cost_balance: bool,
+    deterministic: bool,
+    final_z: bool,
+    skip_uniform: bool,
+    with_checksum: bool,
+    cache: Option<&Arc<Mutex<TileCache>>>,
+    live: Option<&Arc<LiveProgress>>,
+    contiguous: bool,
+    encoding: Encoding,
+    emit_tile: Option<&Py<PyAny>>,
+    should_cancel: Option<&Py<PyAny>>,
+    on_progress: Option<&Py<PyAny>>,
+) -> PyResult<RenderOutput> {
+    if tile_w == 0 || tile_h == 0 {
+        return Err(PyValueError::new_err("tile dimensions must be positive"));
+    }
+    if max_pending_tiles == Some(0) {
+        return Err(PyValueError::new_err("max_pending_tiles must be positive"));
+    }
+    if preview_stride.is_some_and(|stride| stride < 2) {
+        return Err(PyValueError::new_err("preview_stride must be at least 2"));
+    }
+    check_iteration_budget(&params, iteration_budget)?;
+    check_coarse_iter(&params, coarse_iter, orbit.is_some())?;
+    check_final_z(&params, final_z, orbit.is_some())?;
+    // These hinge on timing or on which worker gets where first
+    if deterministic
+        && (subdivide_after_ms.is_some()
+            || emit_during
+            || max_pending_tiles.is_some()
+            || preview_stride.is_some()
+            || coarse_iter.is_some())
+    {
+        return Err(PyValueError::new_err(
+            "deterministic renders can't use subdivide_after_ms, emit_during, \
+             max_pending_tiles, preview_stride or coarse_iter",
+        ));
+    }
+    let first_pass = match (preview_stride, coarse_iter) {
+        (Some(stride), None) => Some(FirstPass::Strided(stride)),
+        (None, Some(coarse_iter)) => Some(FirstPass::Coarse(coarse_iter)),
+        (None, None) => None,
+        (Some(_), Some(_)) => {
+            return Err(PyValueError::new_err(
+                "preview_stride and coarse_iter are both first passes; pass one",
+            ));
+        }
+    };
+    let mut emitter = Emitter::new(emit_tile, batch_size)?;
+    let (width, height) = (params.width, params.height);
+    let _live_frame = live.map(|live| live.begin());
+
+    // Collect all tile coordinates first; task_ids match `sequential`.
+    // Tiles finished by an earlier, time-limited call are left out, as are
+    // tiles outside the dirty rectangle.
+    let mut pending: Vec<Job> = tiles_for(width, height, tile_w, tile_h)
+        .into_iter()
+        .enumerate()
+        .filter(|&(task_id, _)| skip_task_ids.is_none_or(|skip| !skip.contains(&(task_id as u32))))
+        .filter(|&(_, tile)| dirty.is_none_or(|rect| overlaps(tile, rect)))
+        .map(|(task_id, (tx, ty, tw, th))| (task_id, 0, tx, ty, tw, th))
+        .collect();
+    let tiles_total = pending.len();
+
+    // Grid tiles found in the cache are emitted up front instead of being
+    // queued. A histogram needs every tile's counts and final_z every
+    // tile's orbits, so both bypass it.
+    let cache = cache.filter(|_| !histogram && !final_z).map(|cache| {
+        let key = frame_key(&params, orbit.as_ref());
+        (Arc::clone(cache), key)
+    });
     let mut final_records = Vec::new();
-    for (record, data, start_time_ms, duration_ms, tx, ty, thread_id) in results {
-        emit_tile.call1(
+    let mut cache_misses = 0;
+    if let Some((cache, key)) = &cache {
+        let mut hits = Vec::new();
+        {
+            let mut cache = lock_unpoisoned(cache);
+            pending.retain(|&(task_id, _, x, y, w, h)| {
+                let key = tile_key(*key, &params, x, y, w, h);
+                let Some(tile) = cache.get(key) else {
+                    return true;
+                };
+                let record = TaskRecord {
+                    task_id: task_id as u32,
+                    thread_id: 0,
+                    tile_x: x as u32,
+                    tile_y: y as u32,
+                    tile_w: w as u32,
+                    tile_h: h as u32,
+                    start_time_ms: 0,
+                    duration_ms: 0,
+                    pixels_computed: 0,
+                    depth: 0,
+                    min_iter: tile.min_iter,
+                    max_iter_reached: tile.max_iter_reached,
+                    cached: true,
+                    supersampled: 0,
+                    guessed: 0,
+                    checksum: with_checksum.then_some(tile.checksum),
+                    uniform_value: skip_uniform.then(|| tile.values.uniform_value()).flatten(),
+                };
+                let data = emitter.is_active().then(|| {
+                    let values = if record.uniform_value.is_some() {
+                        &tile.values.first_pixel()
+                    } else {
+                        &tile.values
+                    };
+                    let (data, format) =
+                        values.to_emitted(coloring.as_ref(), params.max_iter, contiguous, encoding);
+                    (data, format, None)
+                });
+                hits.push((record, data));
+                false
+            });
+        }
+        cache_misses = pending.len() as u32;
+        if let Some(live) = live {
+            live.add_tiles(hits.len());
+        }
+        emit_finished(
             py,
-            (TileUpdate {
-                task_id: record.task_id,
-                thread_id: thread_id as u32,
-                tile_x: tx as u32,
-                tile_y: ty as u32,
-                tile_w: tile_w as u32,
-                tile_h: tile_h as u32,
-                data,
-                start_time_ms,
-                duration_ms,
-            },),
+            &mut emitter,
+            hits,
+            encoding,
+            deterministic,
+            &mut final_records,
         )?;
-        final_records.push(record);
     }
+    let pixels_total = pending.iter().map(|&(_, _, _, _, w, h)| w * h).sum();
+    let num_workers = pool.map_or(1, ThreadPool::size).min(pending.len());
+    // The estimates are cheap next to the tiles, so they run up front
+    let queued: Vec<Queued> = pending
+        .into_iter()
+        .map(|job @ (_, _, x, y, w, h)| {
+            let cost = if cost_balance {
+                estimate_tile_cost(&params, x, y, w, h)
+            } else {
+                0
+            };
+            Queued::new(job, focus, cost)
+        })
+        .collect();
+    // Previews go in the order the full tiles will be taken
+    let mut previews = match first_pass {
+        Some(_) => queued.clone(),
+        None => Vec::new(),
+    };
+    previews.sort_by(|a, b| b.cmp(a));
+
+    let frame = Arc::new(Frame {
+        params,
+        orbit,
+        coloring,
+        contiguous,
+        encoding,
+        final_z,
+        skip_uniform,
+        overall_start: Instant::now(),
+        time_limit: Duration::from_millis(time_limit_ms),
+        subdivide_after: subdivide_after_ms.map(Duration::from_millis),
+        simulate_load,
+        pending: Mutex::new(queued.into()),
+        focus,
+        in_flight: AtomicUsize::new(0),
+        queue_cv: Condvar::new(),
+        time_exceeded: AtomicBool::new(false),
+        iteration_budget,
+        budget: Mutex::new(BudgetLedger::default()),
+        budget_cv: Condvar::new(),
+        cancelled: live.map_or_else(
+            || Arc::new(AtomicBool::new(false)),
+            |live| Arc::clone(&live.cancelled),
+        ),
+        pixels_done: AtomicUsize::new(0),
+        coarse_states: Mutex::new(HashMap::new()),
+        records: Mutex::new(Vec::new()),
+        partials: Mutex::new(Vec::new()),
+        max_pending: max_pending_tiles.filter(|_| !emit_during),
+        drained_cv: Condvar::new(),
+        emitting: emitter.is_active(),
+        live_emit: emit_tile
+            .filter(|_| emit_during)
+            .map(|emit_tile| emit_tile.clone_ref(py)),
+        batch_size,
+        with_checksum,
+        cache,
+        live: live.cloned(),
+        counts: histogram.then(|| Mutex::new(vec![0u32; params.max_iter as usize + 1])),
+        finished: Mutex::new(0),
+        finished_cv: Condvar::new(),
+        failure: Mutex::new(None),
+    });
+    let mut progress = Progress::new(pixels_total);
+    let mut cancel_err = None;
+    trace_event!(
+        "frame_start",
+        tiles = tiles_total,
+        pixels = pixels_total,
+        cached = final_records.len(),
+        workers = num_workers,
+        inline = pool.is_none(),
+    );
+
+    // With a first pass, the workers first race through it over every
+    // queued tile, emitted as the previews come in
+    if let Some(pass) = first_pass.filter(|_| emitter.is_active()) {
+        let jobs: Arc<Vec<Job>> = Arc::new(previews.into_iter().map(|queued| queued.job).collect());
+        let next = Arc::new(AtomicUsize::new(0));
+        let (sender, receiver) = mpsc::channel();
+        for thread_id in 0..num_workers {
+            let (frame, jobs, next) = (Arc::clone(&frame), Arc::clone(&jobs), Arc::clone(&next));
+            let sender = sender.clone();
+            match pool {
+                Some(pool) => {
+                    pool.execute(move || frame.preview(thread_id, &jobs, &next, pass, &sender))
+                }
+                None => py.detach(|| frame.preview(thread_id, &jobs, &next, pass, &sender)),
+            }
+        }
+        drop(sender);
+
+        // Ends once every worker has dropped its sender
+        let receiver = Mutex::new(receiver);
+        while let Some(update) = py.detach(|| lock_unpoisoned(&receiver).recv().ok()) {
+            let polled = emitter
+                .emit(py, update)
+                .and_then(|()| cancel_requested(py, should_cancel));
+            match polled {
+                Ok(false) => {}
+                Ok(true) => {
+                    // The full pass below then ends straight away
+                    frame.cancelled.store(true, Ordering::Relaxed);
+                    break;
+                }
+                Err(err) => {
+                    frame.cancelled.store(true, Ordering::Relaxed);
+                    return Err(err);
+                }
+            }
+        }
+        emitter.flush(py)?;
+    }
+
+    for thread_id in 0..num_workers {
+        let frame = Arc::clone(&frame);
+        match pool {
+            Some(pool) => pool.execute(move || frame.work(thread_id)),
+            None => py.detach(|| frame.work(thread_id)),
+        }
+    }
+
+    // Workers only touch Python to emit_during, so let them and other Python
+    // threads run while we wait, polling the Python callbacks from this
+    // thread if there are any, and draining tiles if they are bounded
+    py.detach(|| {
+        let draining = frame.max_pending.is_some();
+        let mut polling = should_cancel.is_some() || on_progress.is_some() || draining;
+        let mut finished = lock_unpoisoned(&frame.finished);
+        while *finished < num_workers {
+            if !polling {
+                finished = frame
+                    .finished_cv
+                    .wait(finished)
+                    .unwrap_or_else(PoisonError::into_inner);
+                continue;
+            }
+            finished = frame
+                .finished_cv
+                .wait_timeout(finished, POLL_INTERVAL)
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+            let poll = Python::attach(|py| {
+                if draining {
+                    let drained = std::mem::take(&mut *lock(&frame.records)?);
+                    frame.drained_cv.notify_all();
+                    emit_finished(
+                        py,
+                        &mut emitter,
+                        drained,
+                        encoding,
+                        false,
+                        &mut final_records,
+                    )?;
+                }
+                let done = frame.pixels_done.load(Ordering::Relaxed);
+                progress.update(py, on_progress, done)?;
+                cancel_requested(py, should_cancel)
+            });
+            match poll {
+                Ok(false) => {}
+                Ok(true) => {
+                    frame.cancelled.store(true, Ordering::Relaxed);
+                    polling = false;
+                }
+                Err(err) => {
+                    cancel_err = Some(err);
+                    frame.cancelled.store(true, Ordering::Relaxed);
+                    polling = false;
+                }
+            }
+        }
+    });
+
+    if let Some(err) = lock_unpoisoned(&frame.failure).take() {
+        return Err(err.into());
+    }
+    if let Some(err) = cancel_err {
+        return Err(err);
+    }
+    progress.update(py, on_progress, frame.pixels_done.load(Ordering::Relaxed))?;
+
+    // Now emit the remaining tiles to Python (via main thread)
+    let results = std::mem::take(&mut *lock(&frame.records)?);
+    emit_finished(
+        py,
+        &mut emitter,
+        results,
+        encoding,
+        deterministic,
+        &mut final_records,
+    )?;
+    let mut partials = std::mem::take(&mut *lock(&frame.partials)?);
+    if deterministic {
+        partials.sort_by_key(|update| (update.depth, update.task_id));
+    }
+    for update in partials {
+        emitter.emit(py, update)?;
+    }
+    emitter.flush(py)?;
 
-    Ok(final_records)
+    // Emission follows start time, but records line up with `sequential`
+    final_records.sort_by_key(|record| (record.depth, record.task_id));
+    trace_event!(
+        "frame_done",
+        records = final_records.len(),
+        wall_ms = frame.overall_start.elapsed().as_millis(),
+    );
+
+    let counts = frame
+        .counts
+        .as_ref()
+        .map(|counts| lock(counts).map(|mut counts| std::mem::take(&mut *counts)))
+        .transpose()?;
+
+    let summary = summary.then(|| RenderSummary {
+        cache_misses,
+        ..RenderSummary::new(
+            &final_records,
+            tiles_total,
+            frame.overall_start.elapsed().as_millis(),
+        )
+    });
+
+    Ok(RenderOutput::new(final_records, counts, summary))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Viewport;
+
+    fn poisoned(value: u32) -> Mutex<u32> {
+        let mutex = Mutex::new(value);
+        std::thread::scope(|scope| {
+            let holder = scope.spawn(|| {
+                let _guard = mutex.lock().unwrap();
+                panic!("poisoning the mutex");
+            });
+            assert!(holder.join().is_err());
+        });
+        mutex
+    }
+
+    #[test]
+    fn poisoned_lock_is_a_render_error() {
+        let mutex = poisoned(7);
+        assert!(matches!(lock(&mutex), Err(RenderError::Poisoned)));
+        // State that stays valid is still reachable
+        assert_eq!(*lock_unpoisoned(&mutex), 7);
+
+        Python::initialize();
+        Python::attach(|py| {
+            let err = PyErr::from(lock(&mutex).unwrap_err());
+            assert!(err.is_instance_of::<PyRuntimeError>(py));
+            assert!(err.to_string().contains("poisoned"));
+        });
+    }
+
+    #[test]
+    fn budget_cut_ignores_finishing_order() {
+        let charges = [40, 25, 10, 30, 5, 50];
+        let cut = |order: &[usize]| {
+            let mut ledger = BudgetLedger::default();
+            charges.iter().for_each(|_| {
+                ledger.take();
+            });
+            for &seq in order {
+                assert_ne!(ledger.within(seq), Some(true), "decided before it finished");
+                ledger.charge(seq, charges[seq], 70);
+            }
+            (0..charges.len())
+                .map(|seq| ledger.within(seq))
+                .collect::<Vec<_>>()
+        };
+        // 40 + 25 is under 70, and 40 + 25 + 10 is not
+        let expected = [
+            Some(true),
+            Some(true),
+            Some(true),
+            Some(false),
+            Some(false),
+            Some(false),
+        ];
+        assert_eq!(cut(&[0, 1, 2, 3, 4, 5]), expected);
+        assert_eq!(cut(&[5, 3, 2, 0, 4, 1]), expected);
+        assert_eq!(cut(&[2, 1, 0]), expected);
+    }
+
+    // Iterations `mandelbrot` actually runs over the tile's pixels
+    fn tile_work(params: &RenderParams, (x0, y0, w, h): (usize, usize, usize, usize)) -> u64 {
+        let mut work = 0;
+        for y in y0..y0 + h {
+            for x in x0..x0 + w {
+                let (re, im) = params.to_complex(x as f64, y as f64);
+                let count = model::mandelbrot(re, im, params.max_iter, 4.0);
+                work += model::mandelbrot_iterations(re, im, count, params.max_iter, 4.0) as u64;
+            }
+        }
+        work
+    }
+
+    // Per-worker totals when each job in turn goes to the worker free first
+    fn schedule(work: impl IntoIterator<Item = u64>, workers: usize) -> Vec<u64> {
+        let mut totals = vec![0; workers];
+        for work in work {
+            *totals.iter_mut().min().unwrap() += work;
+        }
+        totals
+    }
+
+    #[test]
+    fn cost_balance_evens_out_an_interior_heavy_view() {
+        // Around the main body, where bulbs and the boundary are the slow parts
+        let params = RenderParams {
+            viewport: Viewport {
+                center_re: -0.6,
+                center_im: 0.0,
+                scale: 2.8 / 128.0,
+            },
+            ..RenderParams::plain_mandelbrot(128, 128, 1000, Viewport::default_for(128, 128))
+        };
+        let tiles = tiles_for(128, 128, 16, 16);
+        let work: Vec<u64> = tiles.iter().map(|&tile| tile_work(&params, tile)).collect();
+        let spread = |totals: Vec<u64>| {
+            let (min, max) = (totals.iter().min().unwrap(), totals.iter().max().unwrap());
+            max - min
+        };
+
+        let chunked = work
+            .chunks(tiles.len() / 4)
+            .map(|chunk| chunk.iter().sum())
+            .collect();
+        let mut queue: BinaryHeap<Queued> = tiles
+            .iter()
+            .enumerate()
+            .map(|(task_id, &(x, y, w, h))| {
+                let cost = estimate_tile_cost(&params, x, y, w, h);
+                Queued::new((task_id, 0, x, y, w, h), None, cost)
+            })
+            .collect();
+        let by_cost = std::iter::from_fn(|| queue.pop()).map(|queued| work[queued.job.0]);
+
+        let (chunked, raster, by_cost) = (
+            spread(chunked),
+            spread(schedule(work.iter().copied(), 4)),
+            spread(schedule(by_cost, 4)),
+        );
+        assert!(
+            by_cost < raster && raster < chunked,
+            "{by_cost} {raster} {chunked}"
+        );
+        assert!(by_cost * 10 < chunked);
+    }
+
+    #[test]
+    fn shared_queue_balances_a_center_heavy_view() {
+        // Interior rows send every pixel to max_iter, so the middle of the
+        // image costs far more than its top and bottom bands
+        let params = RenderParams {
+            viewport: Viewport {
+                center_re: -0.6,
+                center_im: 0.0,
+                scale: 2.8 / 128.0,
+            },
+            ..RenderParams::plain_mandelbrot(128, 128, 1000, Viewport::default_for(128, 128))
+        };
+        let tiles = tiles_for(128, 128, 16, 16);
+        let work: Vec<u64> = tiles.iter().map(|&tile| tile_work(&params, tile)).collect();
+        let ratio = |totals: Vec<u64>| {
+            let (min, max) = (totals.iter().min().unwrap(), totals.iter().max().unwrap());
+            *max as f64 / *min as f64
+        };
+
+        // Without cost_balance or focus, tiles leave the queue in raster order
+        let mut queue: BinaryHeap<Queued> = tiles
+            .iter()
+            .enumerate()
+            .map(|(task_id, &(x, y, w, h))| Queued::new((task_id, 0, x, y, w, h), None, 0))
+            .collect();
+        let taken: Vec<usize> = std::iter::from_fn(|| queue.pop())
+            .map(|queued| queued.job.0)
+            .collect();
+        assert_eq!(taken, (0..tiles.len()).collect::<Vec<_>>());
+
+        let chunked = work
+            .chunks(tiles.len() / 4)
+            .map(|chunk| chunk.iter().sum())
+            .collect();
+        let (chunked, shared) = (
+            ratio(chunked),
+            ratio(schedule(taken.iter().map(|&i| work[i]), 4)),
+        );
+        assert!(shared < 2.0, "{shared}");
+        assert!(shared < chunked, "{shared} {chunked}");
+    }
 }
@@ -0,0 +1,12 @@
+use crate::delta;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+/// Escape counts back from the data of a tile emitted with
+/// `encoding=ENCODING_DELTA_VARINT`, in row-major order; the format is
+/// described at `delta::encode_counts`.
+#[pyfunction]
+pub fn decode_counts(data: &[u8]) -> PyResult<Vec<u32>> {
+    delta::decode_counts(data)
+        .ok_or_else(|| PyValueError::new_err("data is not delta-encoded counts"))
+}
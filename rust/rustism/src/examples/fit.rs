@@ -0,0 +1,94 @@
+use crate::model::{
+    FRACTAL_MANDELBROT, FractalKind, RenderParams, TileData, Viewport, render_tile,
+};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+// Every set these render lies inside |c| <= 2, or |z| <= 2 for Julia sets
+const SEARCH_RADIUS: f64 = 2.0;
+
+/// `(center_re, center_im, scale)` of a `width` by `height` view framing
+/// the fractal, to pass on to a render. A `samples` by `samples` grid over
+/// the square within radius 2 of the origin is rendered to `max_iter`, and
+/// the view fits the box around every sample that took at least `min_iter`
+/// iterations, widened by `margin` of its size on each side. That box hugs
+/// the set, though filaments thinner than the grid, like the Mandelbrot
+/// set's antenna toward -2, can fall outside it. A Julia set too sparse to
+/// catch any sample gets the whole square.
+#[pyfunction]
+#[pyo3(signature = (
+    width,
+    height,
+    max_iter = 256,
+    margin = 0.05,
+    fractal_kind = FRACTAL_MANDELBROT,
+    julia_re = 0.0,
+    julia_im = 0.0,
+    power = 2,
+    samples = 128,
+    min_iter = 16,
+))]
+#[allow(clippy::too_many_arguments)]
+pub fn auto_fit(
+    py: Python<'_>,
+    width: usize,
+    height: usize,
+    max_iter: u32,
+    margin: f64,
+    fractal_kind: u8,
+    julia_re: f64,
+    julia_im: f64,
+    power: u32,
+    samples: usize,
+    min_iter: u32,
+) -> PyResult<(f64, f64, f64)> {
+    if width == 0 || height == 0 {
+        return Err(PyValueError::new_err("width and height must be positive"));
+    }
+    if samples < 2 {
+        return Err(PyValueError::new_err("samples must be at least 2"));
+    }
+    if !(margin >= 0.0 && margin.is_finite()) {
+        return Err(PyValueError::new_err("margin must not be negative"));
+    }
+    let viewport = Viewport {
+        center_re: 0.0,
+        center_im: 0.0,
+        scale: 2.0 * SEARCH_RADIUS / samples as f64,
+    };
+    let params = RenderParams {
+        kind: FractalKind::from_code(fractal_kind, julia_re, julia_im)?,
+        power,
+        pixel_center: true,
+        ..RenderParams::plain_mandelbrot(samples, samples, max_iter, viewport)
+    };
+    params.validate()?;
+
+    let mut out = TileData::default();
+    py.detach(|| render_tile(&params, 0, 0, samples, samples, &mut out));
+    let TileData::Iterations(counts) = &out else {
+        unreachable!("iteration output always renders counts")
+    };
+
+    // (min_x, min_y, max_x, max_y) of the samples, in whole-sample edges
+    let threshold = min_iter.min(max_iter);
+    let found = counts
+        .iter()
+        .enumerate()
+        .filter(|&(_, &count)| count >= threshold)
+        .fold(None::<(usize, usize, usize, usize)>, |bounds, (i, _)| {
+            let (x, y) = (i % samples, i / samples);
+            Some(match bounds {
+                None => (x, y, x + 1, y + 1),
+                Some((x0, y0, x1, y1)) => (x0.min(x), y0.min(y), x1.max(x + 1), y1.max(y + 1)),
+            })
+        });
+    let (x0, y0, x1, y1) = found.unwrap_or((0, 0, samples, samples));
+
+    // Sample positions are centers, so pull the edges back half a sample
+    let (re0, im0) = params.to_complex(x0 as f64 - 0.5, y0 as f64 - 0.5);
+    let (re1, im1) = params.to_complex(x1 as f64 - 0.5, y1 as f64 - 0.5);
+    let (box_w, box_h) = ((re1 - re0).abs(), (im1 - im0).abs());
+    let scale = (box_w / width as f64).max(box_h / height as f64) * (1.0 + 2.0 * margin);
+    Ok(((re0 + re1) / 2.0, (im0 + im1) / 2.0, scale))
+}
@@ -0,0 +1,217 @@
+use crate::metrics::TileInFlight;
+use crate::model::{
+    self, DEFAULT_CENTER_IM, DEFAULT_CENTER_RE, RenderParams, TileData, Viewport, default_scale,
+    iteration_dtype, pack_counts, render_tile, tiles_for,
+};
+use crate::pool::{ThreadPool, available_threads};
+use pyo3::buffer::PyBuffer;
+use pyo3::exceptions::{PyRuntimeError, PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use std::sync::{
+    Arc,
+    atomic::{AtomicUsize, Ordering},
+    mpsc,
+};
+
+// (tile_x, tile_y, tile_w, escape counts)
+type FinishedTile = (usize, usize, usize, Vec<u32>);
+
+/// Renders the escape counts of the whole Mandelbrot view on `num_threads`
+/// threads and returns them as one image, without calling back into
+/// Python. The result is `height * width` native-endian counts in row-major
+/// order and their dtype, as picked by `iteration_dtype`; read it with
+/// `numpy.frombuffer(data, dtype).reshape(height, width)`. Tiles only split
+/// up the work and don't show in the result. With `mirror`, a view centered
+/// on the real axis only renders the rows down to it and copies the rest,
+/// which comes out identical in about half the time.
+#[pyfunction]
+#[pyo3(signature = (
+    width,
+    height,
+    max_iter,
+    center_re = DEFAULT_CENTER_RE,
+    center_im = DEFAULT_CENTER_IM,
+    scale = None,
+    num_threads = 0,
+    tile_w = 64,
+    tile_h = 64,
+    mirror = true,
+))]
+#[allow(clippy::too_many_arguments)]
+pub fn render_image(
+    py: Python<'_>,
+    width: usize,
+    height: usize,
+    max_iter: u32,
+    center_re: f64,
+    center_im: f64,
+    scale: Option<f64>,
+    num_threads: usize,
+    tile_w: usize,
+    tile_h: usize,
+    mirror: bool,
+) -> PyResult<(Vec<u8>, &'static str)> {
+    if tile_w == 0 || tile_h == 0 {
+        return Err(PyValueError::new_err("tile dimensions must be positive"));
+    }
+    // 0 means "one thread per available core", as in `concurrent`
+    let num_threads = match num_threads {
+        0 => available_threads(),
+        n => n,
+    };
+
+    let params = plain_params(width, height, max_iter, center_re, center_im, scale)?;
+    // Rows past the first with a mirror above it are all copies
+    let rows = if mirror {
+        (0..height)
+            .take_while(|&y| params.mirror_row(y).is_none_or(|source| source >= y))
+            .count()
+    } else {
+        height
+    };
+    let tiles = Arc::new(tiles_for(width, rows, tile_w, tile_h));
+    let next = Arc::new(AtomicUsize::new(0));
+    let (sender, receiver) = mpsc::channel::<FinishedTile>();
+
+    let pool = ThreadPool::new(num_threads);
+    for _ in 0..pool.size().min(tiles.len()) {
+        let (tiles, next, sender) = (Arc::clone(&tiles), Arc::clone(&next), sender.clone());
+        pool.execute(move || {
+            let mut scratch = TileData::default();
+            loop {
+                let task_id = next.fetch_add(1, Ordering::Relaxed);
+                let Some(&(tx, ty, tw, th)) = tiles.get(task_id) else {
+                    break;
+                };
+                let tile_in_flight = TileInFlight::start();
+                render_tile(&params, tx, ty, tw, th, &mut scratch);
+                tile_in_flight.finish(scratch.len(), || scratch.iteration_total(max_iter));
+                let TileData::Iterations(counts) = &scratch else {
+                    unreachable!("iteration output always renders counts")
+                };
+                if sender.send((tx, ty, tw, counts.clone())).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+    drop(sender);
+
+    // Each tile's rows go into their slice of the image as they come in
+    let (image, placed) = py.detach(move || {
+        let mut image = vec![0; width * height];
+        let mut placed = 0;
+        for (tx, ty, tw, counts) in receiver {
+            for (row, counts) in counts.chunks_exact(tw).enumerate() {
+                let at = (ty + row) * width + tx;
+                image[at..at + tw].copy_from_slice(counts);
+            }
+            placed += 1;
+        }
+        for y in rows..height {
+            let source = params
+                .mirror_row(y)
+                .expect("rows past the rendered ones have a mirror among them");
+            image.copy_within(source * width..(source + 1) * width, y * width);
+        }
+        (image, placed)
+    });
+    if placed < tiles.len() {
+        return Err(PyRuntimeError::new_err("a render worker panicked"));
+    }
+
+    Ok((pack_counts(&image, max_iter), iteration_dtype(max_iter)))
+}
+
+/// Renders the escape counts of one tile of the Mandelbrot view straight
+/// into `buffer`, a writable C-contiguous uint16 buffer such as a numpy
+/// framebuffer, without a copy of the tile in between. Pixel `(dx, dy)` of
+/// the tile goes to element `offset + dy * stride + dx`. `stride` defaults
+/// to `width` and `offset` to the tile's own position in the image, so
+/// tiles rendered in turn into one `height * width` buffer assemble the
+/// image in place. The GIL stays held while the tile renders.
+#[pyfunction]
+#[pyo3(signature = (
+    buffer,
+    width,
+    height,
+    max_iter,
+    tile_x,
+    tile_y,
+    tile_w,
+    tile_h,
+    center_re = DEFAULT_CENTER_RE,
+    center_im = DEFAULT_CENTER_IM,
+    scale = None,
+    stride = None,
+    offset = None,
+))]
+#[allow(clippy::too_many_arguments)]
+pub fn render_tile_into(
+    py: Python<'_>,
+    buffer: PyBuffer<u16>,
+    width: usize,
+    height: usize,
+    max_iter: u32,
+    tile_x: usize,
+    tile_y: usize,
+    tile_w: usize,
+    tile_h: usize,
+    center_re: f64,
+    center_im: f64,
+    scale: Option<f64>,
+    stride: Option<usize>,
+    offset: Option<usize>,
+) -> PyResult<()> {
+    let params = plain_params(width, height, max_iter, center_re, center_im, scale)?;
+    model::check_tile_into(&params)?;
+    let stride = stride.unwrap_or(width);
+    let offset = offset.unwrap_or(tile_y * stride + tile_x);
+    let tile = (tile_x, tile_y, tile_w, tile_h);
+
+    let Some(cells) = buffer.as_mut_slice(py) else {
+        return Err(PyTypeError::new_err(
+            "buffer must be writable and C-contiguous",
+        ));
+    };
+    match model::tile_into_len(&params, tile, offset, stride) {
+        Some(len) if len <= cells.len() => {}
+        Some(_) => {
+            return Err(PyValueError::new_err(
+                "buffer is too small for the tile at this offset and stride",
+            ));
+        }
+        None => {
+            return Err(PyValueError::new_err(
+                "stride must be at least the tile's width",
+            ));
+        }
+    }
+    model::write_tile(&params, tile, offset, stride, |i, count| {
+        cells[i].set(count)
+    });
+    Ok(())
+}
+
+// The plain f64 Mandelbrot view these functions render
+fn plain_params(
+    width: usize,
+    height: usize,
+    max_iter: u32,
+    center_re: f64,
+    center_im: f64,
+    scale: Option<f64>,
+) -> PyResult<RenderParams> {
+    let params = RenderParams::plain_mandelbrot(
+        width,
+        height,
+        max_iter,
+        Viewport {
+            center_re,
+            center_im,
+            scale: scale.unwrap_or_else(|| default_scale(width, height)),
+        },
+    );
+    params.validate()?;
+    Ok(params)
+}
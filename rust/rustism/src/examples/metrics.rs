@@ -0,0 +1,34 @@
+use crate::metrics::{IN_FLIGHT, ITERATIONS, PIXELS, RENDER_MICROS, TILES};
+use pyo3::IntoPyObject;
+use pyo3::prelude::*;
+use std::sync::atomic::Ordering::Relaxed;
+
+/// Render counters of the whole process, returned as a dict. Names and
+/// units follow Prometheus conventions, so each maps onto a metric as is.
+#[derive(Debug, IntoPyObject)]
+pub struct MetricsSnapshot {
+    // Finished tiles, and the pixels and iterations in them, across every
+    // render since the extension was loaded
+    pub tiles_rendered_total: u64,
+    pub pixels_rendered_total: u64,
+    pub iterations_total: u64,
+    // Summed over the tiles, so it grows faster than wall time on many
+    // threads
+    pub render_seconds_total: f64,
+    // Tiles being rendered right now
+    pub tiles_in_flight: u64,
+}
+
+/// Current values of the process-wide render counters that sequential,
+/// concurrent, `Renderer` and render_image update as they finish tiles.
+/// Needs the `metrics` feature.
+#[pyfunction]
+pub fn metrics_snapshot() -> MetricsSnapshot {
+    MetricsSnapshot {
+        tiles_rendered_total: TILES.load(Relaxed),
+        pixels_rendered_total: PIXELS.load(Relaxed),
+        iterations_total: ITERATIONS.load(Relaxed),
+        render_seconds_total: RENDER_MICROS.load(Relaxed) as f64 / 1e6,
+        tiles_in_flight: IN_FLIGHT.load(Relaxed),
+    }
+}
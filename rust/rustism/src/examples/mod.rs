@@ -1,2 +1,15 @@
+pub mod bench;
+pub mod capabilities;
+pub mod colormap;
 pub mod concurrent;
+pub mod decode;
+pub mod fit;
+pub mod image;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod orbit;
+pub mod plan;
+pub mod png;
+pub mod renderer;
+pub mod sample;
 pub mod sequential;
@@ -0,0 +1,19 @@
+use crate::model::{CoordinateArg, ReferenceOrbit, check_max_iter};
+use pyo3::prelude::*;
+
+/// Reference orbit of a deep zoom centered on `(center_re, center_im)`,
+/// good for renders of up to `max_iter` iterations. Pass it to
+/// `sequential`, `concurrent` or `Renderer.render` as `reference_orbit` to
+/// skip iterating the center again; those renders are centered on it.
+/// Decimal strings keep the digits a deep center needs, as elsewhere.
+#[pyfunction]
+pub fn compute_reference_orbit(
+    py: Python<'_>,
+    center_re: CoordinateArg,
+    center_im: CoordinateArg,
+    max_iter: u32,
+) -> PyResult<ReferenceOrbit> {
+    check_max_iter(max_iter)?;
+    let (re, im) = (center_re.into_decimal(), center_im.into_decimal());
+    py.detach(|| ReferenceOrbit::compute(&re, &im, max_iter))
+}
@@ -0,0 +1,55 @@
+use crate::model::tiles_for;
+use crate::pool::available_threads;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+// (task_id, tile_x, tile_y, tile_w, tile_h, assigned_thread)
+type PlannedTile = (u32, u32, u32, u32, u32, u32);
+
+/// Number of threads `num_threads = 0` uses: what the OS reports the
+/// machine can run at once, so hyperthreads count as separate threads.
+#[pyfunction]
+pub fn recommended_threads() -> usize {
+    available_threads()
+}
+
+/// Tile schedule of a `concurrent` render, without rendering anything:
+/// `(task_id, tile_x, tile_y, tile_w, tile_h, assigned_thread)` per tile,
+/// with edge tiles clipped to the image.
+///
+/// Workers pull tiles off a shared queue in task_id order, so the real
+/// assignment depends on how long each tile takes. `assigned_thread` is the
+/// round-robin split they end up with when every tile costs the same.
+#[pyfunction]
+pub fn plan(
+    width: usize,
+    height: usize,
+    tile_w: usize,
+    tile_h: usize,
+    num_threads: usize,
+) -> PyResult<Vec<PlannedTile>> {
+    if tile_w == 0 || tile_h == 0 {
+        return Err(PyValueError::new_err("tile dimensions must be positive"));
+    }
+
+    // 0 means "one thread per available core", as in `concurrent`
+    let num_threads = match num_threads {
+        0 => available_threads(),
+        n => n,
+    };
+
+    Ok(tiles_for(width, height, tile_w, tile_h)
+        .into_iter()
+        .enumerate()
+        .map(|(task_id, (tx, ty, tw, th))| {
+            (
+                task_id as u32,
+                tx as u32,
+                ty as u32,
+                tw as u32,
+                th as u32,
+                (task_id % num_threads) as u32,
+            )
+        })
+        .collect())
+}
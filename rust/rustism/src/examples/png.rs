@@ -0,0 +1,147 @@
+use crate::color::{Coloring, PALETTE_CLASSIC, PaletteArg, Tone};
+use crate::model::{
+    DEFAULT_CENTER_IM, DEFAULT_CENTER_RE, Encoding, RenderParams, TileData, Viewport,
+    default_scale, render_tile, tiles_for,
+};
+use crate::png::encode_rgba;
+use pyo3::exceptions::{PyOSError, PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use std::path::PathBuf;
+
+/// Renders the whole Mandelbrot view tile by tile, colors it with `palette`
+/// and writes it to `path` as a PNG, without calling back into Python.
+/// Returns the number of bytes written.
+#[pyfunction]
+#[pyo3(signature = (
+    path,
+    width,
+    height,
+    tile_w,
+    tile_h,
+    max_iter,
+    center_re = DEFAULT_CENTER_RE,
+    center_im = DEFAULT_CENTER_IM,
+    scale = None,
+    palette = PaletteArg::Code(PALETTE_CLASSIC),
+    exposure = 1.0,
+    gamma = 1.0,
+    palette_offset = 0.0,
+))]
+#[allow(clippy::too_many_arguments)]
+pub fn render_to_png(
+    py: Python<'_>,
+    path: PathBuf,
+    width: usize,
+    height: usize,
+    tile_w: usize,
+    tile_h: usize,
+    max_iter: u32,
+    center_re: f64,
+    center_im: f64,
+    scale: Option<f64>,
+    palette: PaletteArg,
+    exposure: f64,
+    gamma: f64,
+    palette_offset: f32,
+) -> PyResult<usize> {
+    if tile_w == 0 || tile_h == 0 {
+        return Err(PyValueError::new_err("tile dimensions must be positive"));
+    }
+    // PNG has no empty images
+    if width == 0 || height == 0 || width > u32::MAX as usize || height > u32::MAX as usize {
+        return Err(PyValueError::new_err(
+            "image dimensions must be between 1 and 2^32 - 1",
+        ));
+    }
+
+    let params = RenderParams::plain_mandelbrot(
+        width,
+        height,
+        max_iter,
+        Viewport {
+            center_re,
+            center_im,
+            scale: scale.unwrap_or_else(|| default_scale(width, height)),
+        },
+    );
+    params.validate()?;
+    let tone = Tone::new(exposure, gamma)?;
+    let Some(coloring) = Coloring::new(
+        py,
+        Some(palette),
+        tone,
+        palette_offset,
+        params.output,
+        max_iter,
+    )?
+    else {
+        unreachable!("a palette was passed")
+    };
+
+    let png = py
+        .detach(|| {
+            let rgba = render_rgba(&params, &coloring, tile_w, tile_h);
+            encode_rgba(width as u32, height as u32, &rgba)
+        })
+        .map_err(|err| PyRuntimeError::new_err(format!("encoding the PNG: {err}")))?;
+
+    std::fs::write(&path, &png)
+        .map_err(|err| PyOSError::new_err(format!("writing {}: {err}", path.display())))?;
+    Ok(png.len())
+}
+
+/// The colored view, row-major and 4 bytes per pixel, rendered tile by tile.
+fn render_rgba(
+    params: &RenderParams,
+    coloring: &Coloring,
+    tile_w: usize,
+    tile_h: usize,
+) -> Vec<u8> {
+    let (width, height) = (params.width, params.height);
+    let mut rgba = vec![0u8; width * height * 4];
+    let mut scratch = TileData::default();
+    for (tx, ty, tw, th) in tiles_for(width, height, tile_w, tile_h) {
+        render_tile(params, tx, ty, tw, th, &mut scratch);
+        // Colored and packed, the tile is its RGBA rows back to back
+        let (TileData::Bytes(tile), _) =
+            scratch.to_emitted(Some(coloring), params.max_iter, true, Encoding::Raw)
+        else {
+            unreachable!("contiguous tiles are bytes")
+        };
+        for (row, pixels) in tile.chunks_exact(tw * 4).enumerate() {
+            let at = ((ty + row) * width + tx) * 4;
+            rgba[at..at + pixels.len()].copy_from_slice(pixels);
+        }
+    }
+    rgba
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::{Gradient, Palette};
+
+    #[test]
+    fn golden_render() {
+        let params = RenderParams::plain_mandelbrot(96, 64, 200, Viewport::default_for(96, 64));
+        let coloring = Coloring {
+            gradient: Gradient::Builtin(Palette::Classic),
+            tone: Tone::new(1.0, 1.0).unwrap(),
+            offset: 0.0,
+        };
+        // Same pixels whatever size the tiles are
+        let rgba = render_rgba(&params, &coloring, 32, 32);
+        assert_eq!(render_rgba(&params, &coloring, 96, 64), rgba);
+        assert_eq!(render_rgba(&params, &coloring, 7, 5), rgba);
+
+        let png = encode_rgba(96, 64, &rgba).unwrap();
+        let mut reader = ::png::Decoder::new(std::io::Cursor::new(&png))
+            .read_info()
+            .unwrap();
+        let mut decoded = vec![0; reader.output_buffer_size().unwrap()];
+        reader.next_frame(&mut decoded).unwrap();
+        assert_eq!(decoded, rgba);
+        // Only changes if some pixel of the render does
+        assert_eq!(TileData::Bytes(rgba).checksum(), 0x02ba_d605_9380_d23b);
+    }
+}
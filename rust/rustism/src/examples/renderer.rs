@@ -0,0 +1,576 @@
+use crate::cache::TileCache;
+use crate::color::PaletteArg;
+use crate::examples::concurrent::{LiveProgress, render_on_pool};
+use crate::model::{
+    CoordinateArg, DEFAULT_CENTER_IM, DEFAULT_CENTER_RE, ENCODING_RAW, FRACTAL_JULIA,
+    FRACTAL_MANDELBROT, OUTPUT_ITERATIONS, PRECISION_F64, ReferenceOrbit, RenderArgs, RenderOutput,
+    RenderRequest, SAMPLES_GRID, TRAP_POINT, TRAVERSAL_ROW_MAJOR, cancel_requested, default_scale,
+};
+use crate::pool::{ThreadPool, available_threads};
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::sync::MutexExt;
+use pyo3::types::{PyCFunction, PyDict, PyList, PyTuple};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Renders like `concurrent`, but keeps its worker threads alive between
+/// calls. Calls from several Python threads take turns on the one pool.
+///
+/// With a `cache_size`, the last that many grid tiles are kept, and a
+/// render emits any tile it would repeat from there instead, marked
+/// `cached`. Tiles are matched by their place on the plane, so panning by
+/// whole tiles reuses everything still in view. Tiles are kept before
+/// coloring, so a frame that only changes the palette, tone or
+/// `palette_offset` is colored from the cache without iterating again.
+/// Renders with `histogram` skip the cache.
+///
+/// `tiles_completed` and `elapsed_ms` can be read from another thread
+/// while a render runs, e.g. for a live tiles-per-second readout. They are
+/// relaxed reads, so approximate mid-frame, and exact once it's done.
+/// `cancel` likewise stops a running render from another thread.
+#[pyclass]
+pub struct Renderer {
+    pool: Mutex<ThreadPool>,
+    #[pyo3(get)]
+    num_threads: usize,
+    cache: Option<Arc<Mutex<TileCache>>>,
+    #[pyo3(get)]
+    cache_size: usize,
+    live: Arc<LiveProgress>,
+}
+
+#[pymethods]
+impl Renderer {
+    #[new]
+    #[pyo3(signature = (num_threads = 0, cache_size = 0))]
+    fn new(num_threads: usize, cache_size: usize) -> Self {
+        // 0 means "one thread per available core"
+        let num_threads = match num_threads {
+            0 => available_threads(),
+            n => n,
+        };
+        Self {
+            pool: Mutex::new(ThreadPool::new(num_threads)),
+            num_threads,
+            cache: (cache_size > 0).then(|| Arc::new(Mutex::new(TileCache::new(cache_size)))),
+            cache_size,
+            live: Arc::new(LiveProgress::new()),
+        }
+    }
+
+    /// Tiles the current, or else last, render has finished so far.
+    #[getter]
+    fn tiles_completed(&self) -> usize {
+        self.live.tiles_completed()
+    }
+
+    /// How long the current render has been running, or the last one took.
+    #[getter]
+    fn elapsed_ms(&self) -> u64 {
+        self.live.elapsed_ms()
+    }
+
+    /// Stops the current render as `should_cancel` would, but without
+    /// waiting for in-flight tiles: they stop at their next row and go out
+    /// as partials. The render returns what it finished. Does nothing when
+    /// no render is running.
+    fn cancel(&self) {
+        self.live.cancel();
+    }
+
+    /// Tiles currently cached.
+    #[getter]
+    fn cached_tiles(&self) -> usize {
+        self.cache.as_ref().map_or(0, |cache| {
+            cache.lock().unwrap_or_else(PoisonError::into_inner).len()
+        })
+    }
+
+    fn clear_cache(&self) {
+        if let Some(cache) = &self.cache {
+            cache.lock().unwrap_or_else(PoisonError::into_inner).clear();
+        }
+    }
+
+    #[pyo3(signature = (
+        width,
+        height,
+        tile_w,
+        tile_h,
+        max_iter,
+        emit_tile,
+        time_limit_ms,
+        center_re = CoordinateArg::Float(DEFAULT_CENTER_RE),
+        center_im = CoordinateArg::Float(DEFAULT_CENTER_IM),
+        scale = None,
+        fractal_kind = FRACTAL_MANDELBROT,
+        julia_re = 0.0,
+        julia_im = 0.0,
+        smooth = false,
+        contiguous = false,
+        should_cancel = None,
+        on_progress = None,
+        precision = PRECISION_F64,
+        histogram = false,
+        power = 2,
+        subdivide_after_ms = None,
+        output_mode = OUTPUT_ITERATIONS,
+        samples_per_axis = 1,
+        deep_center = None,
+        escape_radius = 2.0,
+        palette = None,
+        interior = false,
+        trap = TRAP_POINT,
+        trap_re = 0.0,
+        trap_im = 0.0,
+        summary = false,
+        simulate_load = false,
+        skip_task_ids = None,
+        batch_size = None,
+        periodicity = false,
+        emit_during = false,
+        max_pending_tiles = None,
+        focus_x = None,
+        focus_y = None,
+        light_angle = 45.0,
+        light_height = 1.5,
+        preview_stride = None,
+        flip_y = false,
+        with_checksum = false,
+        dirty_x = None,
+        dirty_y = None,
+        dirty_w = None,
+        dirty_h = None,
+        exposure = 1.0,
+        gamma = 1.0,
+        aa_threshold = None,
+        solid_guess = false,
+        sample_pattern = SAMPLES_GRID,
+        check_every = 1,
+        palette_offset = 0.0,
+        iteration_budget = None,
+        pixel_center = false,
+        coarse_iter = None,
+        encoding = ENCODING_RAW,
+        cost_balance = false,
+        reference_orbit = None,
+        deterministic = false,
+        final_z = false,
+        stripe_freq = 5.0,
+        skip_uniform = false,
+        stats_only = false,
+        traversal = TRAVERSAL_ROW_MAJOR,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn render(
+        &self,
+        py: Python<'_>,
+        width: usize,
+        height: usize,
+        tile_w: usize,
+        tile_h: usize,
+        max_iter: u32,
+        emit_tile: Option<Py<PyAny>>,
+        time_limit_ms: u64,
+        center_re: CoordinateArg,
+        center_im: CoordinateArg,
+        scale: Option<f64>,
+        fractal_kind: u8,
+        julia_re: f64,
+        julia_im: f64,
+        smooth: bool,
+        contiguous: bool,
+        should_cancel: Option<Py<PyAny>>,
+        on_progress: Option<Py<PyAny>>,
+        precision: u8,
+        histogram: bool,
+        power: u32,
+        subdivide_after_ms: Option<u64>,
+        output_mode: u8,
+        samples_per_axis: u32,
+        deep_center: Option<(String, String)>,
+        escape_radius: f64,
+        palette: Option<PaletteArg>,
+        interior: bool,
+        trap: u8,
+        trap_re: f64,
+        trap_im: f64,
+        summary: bool,
+        simulate_load: bool,
+        skip_task_ids: Option<HashSet<u32>>,
+        batch_size: Option<usize>,
+        periodicity: bool,
+        emit_during: bool,
+        max_pending_tiles: Option<usize>,
+        focus_x: Option<f64>,
+        focus_y: Option<f64>,
+        light_angle: f64,
+        light_height: f64,
+        preview_stride: Option<usize>,
+        flip_y: bool,
+        with_checksum: bool,
+        dirty_x: Option<usize>,
+        dirty_y: Option<usize>,
+        dirty_w: Option<usize>,
+        dirty_h: Option<usize>,
+        exposure: f64,
+        gamma: f64,
+        aa_threshold: Option<f64>,
+        solid_guess: bool,
+        sample_pattern: u8,
+        check_every: u16,
+        palette_offset: f32,
+        iteration_budget: Option<u64>,
+        pixel_center: bool,
+        coarse_iter: Option<u32>,
+        encoding: u8,
+        cost_balance: bool,
+        reference_orbit: Option<PyRef<'_, ReferenceOrbit>>,
+        deterministic: bool,
+        final_z: bool,
+        stripe_freq: f64,
+        skip_uniform: bool,
+        stats_only: bool,
+        traversal: u8,
+    ) -> PyResult<RenderOutput> {
+        let RenderRequest {
+            params,
+            orbit,
+            coloring,
+            encoding,
+            histogram,
+            summary,
+            dirty,
+            focus,
+        } = RenderRequest::from_args(
+            py,
+            RenderArgs {
+                width,
+                height,
+                max_iter,
+                emit_tile: emit_tile.as_ref(),
+                center_re,
+                center_im,
+                scale,
+                fractal_kind,
+                julia_re,
+                julia_im,
+                smooth,
+                precision,
+                histogram,
+                summary,
+                stats_only,
+                power,
+                output_mode,
+                samples_per_axis,
+                sample_pattern,
+                deep_center,
+                reference_orbit: reference_orbit.as_deref(),
+                escape_radius,
+                palette,
+                exposure,
+                gamma,
+                palette_offset,
+                interior,
+                trap,
+                trap_re,
+                trap_im,
+                light_angle,
+                light_height,
+                stripe_freq,
+                periodicity,
+                flip_y,
+                aa_threshold,
+                solid_guess,
+                check_every,
+                pixel_center,
+                traversal,
+                encoding,
+                dirty_x,
+                dirty_y,
+                dirty_w,
+                dirty_h,
+                focus_x,
+                focus_y,
+            },
+        )?;
+
+        // Waiting for the lock releases the GIL, so a render in progress on
+        // another Python thread can still call back into Python. A render
+        // that failed midway leaves the pool itself intact.
+        let pool = self
+            .pool
+            .lock_py_attached(py)
+            .unwrap_or_else(PoisonError::into_inner);
+        render_on_pool(
+            py,
+            Some(&pool),
+            params,
+            orbit,
+            coloring,
+            tile_w,
+            tile_h,
+            time_limit_ms,
+            iteration_budget,
+            subdivide_after_ms,
+            histogram,
+            summary,
+            simulate_load,
+            skip_task_ids.as_ref(),
+            dirty,
+            focus,
+            batch_size,
+            emit_during,
+            max_pending_tiles,
+            preview_stride,
+            coarse_iter,
+            cost_balance,
+            deterministic,
+            final_z,
+            skip_uniform,
+            with_checksum,
+            self.cache.as_ref(),
+            Some(&self.live),
+            contiguous,
+            encoding,
+            emit_tile.as_ref(),
+            should_cancel.as_ref(),
+            on_progress.as_ref(),
+        )
+    }
+
+    /// Takes the same arguments as `render`, but returns an asyncio future
+    /// on the running loop instead of blocking it. `emit_tile` and the
+    /// result are handed back to the loop, while `should_cancel` and
+    /// `on_progress` still run on the render thread. Cancelling the future
+    /// stops the render.
+    #[pyo3(signature = (*args, **kwargs))]
+    fn render_async<'py>(
+        slf: Bound<'py, Self>,
+        args: Bound<'py, PyTuple>,
+        kwargs: Option<Bound<'py, PyDict>>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let py = slf.py();
+        let event_loop = py.import("asyncio")?.call_method0("get_running_loop")?;
+        let future = event_loop.call_method0("create_future")?;
+
+        let kwargs = match kwargs {
+            Some(kwargs) => kwargs.copy()?,
+            None => PyDict::new(py),
+        };
+        let mut args: Vec<Bound<'py, PyAny>> = args.iter().collect();
+
+        // emit_tile is the sixth positional argument
+        let emit_tile = if let Some(emit_tile) = kwargs.get_item("emit_tile")? {
+            Some(emit_tile)
+        } else {
+            args.get(5).cloned()
+        }
+        .filter(|emit_tile| !emit_tile.is_none());
+        if let Some(emit_tile) = emit_tile {
+            let (event_loop, emit_tile) = (event_loop.clone().unbind(), emit_tile.unbind());
+            let on_loop = PyCFunction::new_closure(py, None, None, move |args, _| {
+                let py = args.py();
+                let mut call = vec![emit_tile.clone_ref(py)];
+                call.extend(args.iter().map(Bound::unbind));
+                event_loop
+                    .bind(py)
+                    .call_method1("call_soon_threadsafe", PyTuple::new(py, call)?)
+                    .map(Bound::unbind)
+            })?;
+            if kwargs.contains("emit_tile")? {
+                kwargs.set_item("emit_tile", on_loop)?;
+            } else {
+                args[5] = on_loop.into_any();
+            }
+        }
+
+        let should_cancel = kwargs
+            .get_item("should_cancel")?
+            .filter(|should_cancel| !should_cancel.is_none())
+            .map(Bound::unbind);
+        let cancel_future = future.clone().unbind();
+        let cancel = PyCFunction::new_closure(py, None, None, move |args, _| {
+            let py = args.py();
+            if cancel_future
+                .bind(py)
+                .call_method0("cancelled")?
+                .is_truthy()?
+            {
+                return Ok(true);
+            }
+            match &should_cancel {
+                Some(should_cancel) => should_cancel.call0(py)?.bind(py).is_truthy(),
+                None => Ok(false),
+            }
+        })?;
+        kwargs.set_item("should_cancel", cancel)?;
+
+        let render = slf.getattr("render")?.unbind();
+        let args = PyTuple::new(py, args)?.unbind();
+        let kwargs = kwargs.unbind();
+        let (event_loop, result_future) = (event_loop.unbind(), future.clone().unbind());
+        std::thread::spawn(move || {
+            Python::attach(|py| {
+                let (value, is_error) = match render.call(py, args.bind(py), Some(kwargs.bind(py)))
+                {
+                    Ok(value) => (value, false),
+                    Err(err) => (err.into_value(py).into_any(), true),
+                };
+                let settle =
+                    PyCFunction::new_closure(py, None, None, move |args, _| -> PyResult<()> {
+                        let py = args.py();
+                        let future = result_future.bind(py);
+                        // The awaiting task may have been cancelled meanwhile
+                        if future.call_method0("done")?.is_truthy()? {
+                            return Ok(());
+                        }
+                        let method = if is_error {
+                            "set_exception"
+                        } else {
+                            "set_result"
+                        };
+                        future.call_method1(method, (value.clone_ref(py),))?;
+                        Ok(())
+                    });
+                // A closed loop has nobody left to tell
+                if let Ok(settle) = settle {
+                    let _ = event_loop
+                        .bind(py)
+                        .call_method1("call_soon_threadsafe", (settle,));
+                }
+            });
+        });
+
+        Ok(future)
+    }
+
+    /// Renders `num_frames` frames of a zoom toward the view center, each
+    /// `zoom_factor` times closer than the one before, on this renderer's
+    /// pool. Keyword arguments are those of `render`, with `scale` that of
+    /// the first frame (by default the whole-set view). Tiles go to
+    /// `emit_tile` tagged with their frame's `frame_index`, and the result
+    /// is a list of each frame's `render` result. `should_cancel` or
+    /// `cancel` also stops the frames still to come.
+    #[pyo3(signature = (num_frames, zoom_factor, **kwargs))]
+    fn render_zoom_sequence<'py>(
+        slf: Bound<'py, Self>,
+        num_frames: usize,
+        zoom_factor: f64,
+        kwargs: Option<Bound<'py, PyDict>>,
+    ) -> PyResult<Vec<Bound<'py, PyAny>>> {
+        if !(zoom_factor.is_finite() && zoom_factor > 0.0) {
+            return Err(PyValueError::new_err(
+                "zoom_factor must be positive and finite",
+            ));
+        }
+        let py = slf.py();
+        let kwargs = match kwargs {
+            Some(kwargs) => kwargs.copy()?,
+            None => PyDict::new(py),
+        };
+        let start_scale = match kwargs.get_item("scale")?.filter(|scale| !scale.is_none()) {
+            Some(scale) => scale.extract()?,
+            None => {
+                let dimension = |name| -> PyResult<usize> {
+                    kwargs
+                        .get_item(name)?
+                        .ok_or_else(|| {
+                            PyTypeError::new_err(format!("missing required argument '{name}'"))
+                        })?
+                        .extract()
+                };
+                default_scale(dimension("width")?, dimension("height")?)
+            }
+        };
+        render_frames(&slf, kwargs, num_frames, |kwargs, frame_index| {
+            let scale = start_scale / zoom_factor.powi(frame_index as i32);
+            kwargs.set_item("scale", scale)
+        })
+    }
+
+    /// Renders `num_frames` frames of a Julia set morphing from constant
+    /// `c_start` to `c_end`, both `(re, im)`, on this renderer's pool. The
+    /// constant moves linearly between them, so the first frame uses
+    /// `c_start` and the last exactly `c_end`. Keyword arguments are those
+    /// of `render` for the view, which stays put; `fractal_kind`, `julia_re`
+    /// and `julia_im` are set for each frame. Tiles and the result are as
+    /// from `render_zoom_sequence`.
+    #[pyo3(signature = (num_frames, c_start, c_end, **kwargs))]
+    fn render_julia_sequence<'py>(
+        slf: Bound<'py, Self>,
+        num_frames: usize,
+        c_start: (f64, f64),
+        c_end: (f64, f64),
+        kwargs: Option<Bound<'py, PyDict>>,
+    ) -> PyResult<Vec<Bound<'py, PyAny>>> {
+        let kwargs = match kwargs {
+            Some(kwargs) => kwargs.copy()?,
+            None => PyDict::new(slf.py()),
+        };
+        kwargs.set_item("fractal_kind", FRACTAL_JULIA)?;
+        render_frames(&slf, kwargs, num_frames, |kwargs, frame_index| {
+            // Written as a blend so the ends land exactly on c_start and c_end
+            let t = match num_frames {
+                0 | 1 => 0.0,
+                n => frame_index as f64 / (n - 1) as f64,
+            };
+            let lerp = |start: f64, end: f64| start * (1.0 - t) + end * t;
+            kwargs.set_item("julia_re", lerp(c_start.0, c_end.0))?;
+            kwargs.set_item("julia_im", lerp(c_start.1, c_end.1))
+        })
+    }
+}
+
+/// Calls `renderer.render` with `kwargs` once per frame, after `set_frame`
+/// has updated them for it, and returns every frame's result. Tiles go to
+/// `emit_tile` with their frame's `frame_index` set, and `should_cancel` or
+/// `cancel` stops the frames still to come.
+fn render_frames<'py>(
+    renderer: &Bound<'py, Renderer>,
+    kwargs: Bound<'py, PyDict>,
+    num_frames: usize,
+    mut set_frame: impl FnMut(&Bound<'py, PyDict>, usize) -> PyResult<()>,
+) -> PyResult<Vec<Bound<'py, PyAny>>> {
+    let py = renderer.py();
+    let emit_tile = kwargs
+        .get_item("emit_tile")?
+        .filter(|emit_tile| !emit_tile.is_none())
+        .map(Bound::unbind);
+    let should_cancel = kwargs
+        .get_item("should_cancel")?
+        .filter(|should_cancel| !should_cancel.is_none())
+        .map(Bound::unbind);
+
+    let render = renderer.getattr("render")?;
+    let mut frames = Vec::with_capacity(num_frames);
+    for frame_index in 0..num_frames {
+        if cancel_requested(py, should_cancel.as_ref())? {
+            break;
+        }
+        set_frame(&kwargs, frame_index)?;
+        if let Some(emit_tile) = &emit_tile {
+            let emit_tile = emit_tile.clone_ref(py);
+            // batch_size hands over a list of tiles instead of one
+            let tagged = PyCFunction::new_closure(py, None, None, move |args, _| {
+                let py = args.py();
+                let tile = args.get_item(0)?;
+                let tiles = match tile.cast::<PyList>() {
+                    Ok(batch) => batch.iter().collect(),
+                    Err(_) => vec![tile.clone()],
+                };
+                for tile in tiles {
+                    tile.set_item("frame_index", frame_index)?;
+                }
+                emit_tile.call1(py, (tile,))
+            })?;
+            kwargs.set_item("emit_tile", tagged)?;
+        }
+        frames.push(render.call((), Some(&kwargs))?);
+        if renderer.borrow().live.is_cancelled() {
+            break;
+        }
+    }
+    Ok(frames)
+}
@@ -0,0 +1,37 @@
+use crate::model::{
+    SMOOTH_ESCAPE_RADIUS_SQ, check_max_iter, in_main_body, mandelbrot, smooth_escape,
+};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+/// Integer and smooth escape values of the Mandelbrot set at one point, as
+/// a render with the same `max_iter` and `escape_radius` would give its
+/// pixel. Points in the main cardioid or period-2 bulb are answered without
+/// iterating.
+#[pyfunction]
+#[pyo3(signature = (c_re, c_im, max_iter, escape_radius = 2.0))]
+pub fn sample_point(
+    c_re: f64,
+    c_im: f64,
+    max_iter: u32,
+    escape_radius: f64,
+) -> PyResult<(u32, f32)> {
+    if !(c_re.is_finite() && c_im.is_finite()) {
+        return Err(PyValueError::new_err("c_re and c_im must be finite"));
+    }
+    if !(escape_radius > 0.0 && escape_radius.is_finite()) {
+        return Err(PyValueError::new_err("escape_radius must be positive"));
+    }
+    check_max_iter(max_iter)?;
+    let radius_sq = escape_radius * escape_radius;
+
+    let iterations = mandelbrot(c_re, c_im, max_iter, radius_sq);
+    let smooth = if in_main_body(c_re, c_im) {
+        max_iter as f32
+    } else {
+        let radius_sq = radius_sq.max(SMOOTH_ESCAPE_RADIUS_SQ);
+        smooth_escape(0.0, 0.0, c_re, c_im, max_iter, 2, false, radius_sq) as f32
+    };
+
+    Ok((iterations, smooth))
+}
@@ -1,73 +1,523 @@
-use crate::model::{TaskRecord, TileUpdate, render_tile};
+use crate::color::PaletteArg;
+use crate::metrics::TileInFlight;
+use crate::model::{
+    self, CoordinateArg, Cutoff, DEFAULT_CENTER_IM, DEFAULT_CENTER_RE, ENCODING_RAW, Emitter,
+    FRACTAL_MANDELBROT, ORDER_RASTER, OUTPUT_ITERATIONS, PRECISION_F64, Progress, ReferenceOrbit,
+    RenderArgs, RenderOutput, RenderRequest, RenderSummary, SAMPLES_GRID, TRAP_POINT,
+    TRAVERSAL_ROW_MAJOR, TaskRecord, TileData, TileLayout, TileOrder, TileUpdate, cancel_requested,
+    overlaps, preview_dims, render_tile_coarse, render_tile_perturbed_until, render_tile_preview,
+    render_tile_until, resume_tile, tiles_for,
+};
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
-use std::time::{Duration, Instant};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        Condvar, Mutex, PoisonError,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+// How often should_cancel is asked while a tile renders
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
 
 #[pyfunction]
+#[pyo3(signature = (
+    width,
+    height,
+    tile_w,
+    tile_h,
+    max_iter,
+    emit_tile,
+    time_limit_ms,
+    center_re = CoordinateArg::Float(DEFAULT_CENTER_RE),
+    center_im = CoordinateArg::Float(DEFAULT_CENTER_IM),
+    scale = None,
+    fractal_kind = FRACTAL_MANDELBROT,
+    julia_re = 0.0,
+    julia_im = 0.0,
+    smooth = false,
+    contiguous = false,
+    should_cancel = None,
+    on_progress = None,
+    precision = PRECISION_F64,
+    histogram = false,
+    power = 2,
+    output_mode = OUTPUT_ITERATIONS,
+    samples_per_axis = 1,
+    deep_center = None,
+    escape_radius = 2.0,
+    palette = None,
+    interior = false,
+    trap = TRAP_POINT,
+    trap_re = 0.0,
+    trap_im = 0.0,
+    emit_order = ORDER_RASTER,
+    seed = 0,
+    summary = false,
+    simulate_load = false,
+    skip_task_ids = None,
+    batch_size = None,
+    periodicity = false,
+    light_angle = 45.0,
+    light_height = 1.5,
+    preview_stride = None,
+    flip_y = false,
+    with_checksum = false,
+    dirty_x = None,
+    dirty_y = None,
+    dirty_w = None,
+    dirty_h = None,
+    exposure = 1.0,
+    gamma = 1.0,
+    aa_threshold = None,
+    solid_guess = false,
+    sample_pattern = SAMPLES_GRID,
+    check_every = 1,
+    palette_offset = 0.0,
+    iteration_budget = None,
+    pixel_center = false,
+    coarse_iter = None,
+    encoding = ENCODING_RAW,
+    reference_orbit = None,
+    final_z = false,
+    stripe_freq = 5.0,
+    skip_uniform = false,
+    stats_only = false,
+    traversal = TRAVERSAL_ROW_MAJOR,
+))]
+#[allow(clippy::too_many_arguments)]
 pub fn sequential(
     py: Python<'_>,
     width: usize,
     height: usize,
     tile_w: usize,
     tile_h: usize,
-    max_iter: u16,
-    emit_tile: PyObject,
+    max_iter: u32,
+    emit_tile: Option<Py<PyAny>>,
     time_limit_ms: u64,
-) -> PyResult<Vec<TaskRecord>> {
+    center_re: CoordinateArg,
+    center_im: CoordinateArg,
+    scale: Option<f64>,
+    fractal_kind: u8,
+    julia_re: f64,
+    julia_im: f64,
+    smooth: bool,
+    contiguous: bool,
+    should_cancel: Option<Py<PyAny>>,
+    on_progress: Option<Py<PyAny>>,
+    precision: u8,
+    histogram: bool,
+    power: u32,
+    output_mode: u8,
+    samples_per_axis: u32,
+    deep_center: Option<(String, String)>,
+    escape_radius: f64,
+    palette: Option<PaletteArg>,
+    interior: bool,
+    trap: u8,
+    trap_re: f64,
+    trap_im: f64,
+    emit_order: u8,
+    seed: u64,
+    summary: bool,
+    simulate_load: bool,
+    skip_task_ids: Option<HashSet<u32>>,
+    batch_size: Option<usize>,
+    periodicity: bool,
+    light_angle: f64,
+    light_height: f64,
+    preview_stride: Option<usize>,
+    flip_y: bool,
+    with_checksum: bool,
+    dirty_x: Option<usize>,
+    dirty_y: Option<usize>,
+    dirty_w: Option<usize>,
+    dirty_h: Option<usize>,
+    exposure: f64,
+    gamma: f64,
+    aa_threshold: Option<f64>,
+    solid_guess: bool,
+    sample_pattern: u8,
+    check_every: u16,
+    palette_offset: f32,
+    iteration_budget: Option<u64>,
+    pixel_center: bool,
+    coarse_iter: Option<u32>,
+    encoding: u8,
+    reference_orbit: Option<PyRef<'_, ReferenceOrbit>>,
+    final_z: bool,
+    stripe_freq: f64,
+    skip_uniform: bool,
+    stats_only: bool,
+    traversal: u8,
+) -> PyResult<RenderOutput> {
+    if tile_w == 0 || tile_h == 0 {
+        return Err(PyValueError::new_err("tile dimensions must be positive"));
+    }
+    let RenderRequest {
+        params,
+        orbit,
+        coloring,
+        encoding,
+        histogram,
+        summary,
+        dirty,
+        ..
+    } = RenderRequest::from_args(
+        py,
+        RenderArgs {
+            width,
+            height,
+            max_iter,
+            emit_tile: emit_tile.as_ref(),
+            center_re,
+            center_im,
+            scale,
+            fractal_kind,
+            julia_re,
+            julia_im,
+            smooth,
+            precision,
+            histogram,
+            summary,
+            stats_only,
+            power,
+            output_mode,
+            samples_per_axis,
+            sample_pattern,
+            deep_center,
+            reference_orbit: reference_orbit.as_deref(),
+            escape_radius,
+            palette,
+            exposure,
+            gamma,
+            palette_offset,
+            interior,
+            trap,
+            trap_re,
+            trap_im,
+            light_angle,
+            light_height,
+            stripe_freq,
+            periodicity,
+            flip_y,
+            aa_threshold,
+            solid_guess,
+            check_every,
+            pixel_center,
+            traversal,
+            encoding,
+            dirty_x,
+            dirty_y,
+            dirty_w,
+            dirty_h,
+            focus_x: None,
+            focus_y: None,
+        },
+    )?;
+    model::check_iteration_budget(&params, iteration_budget)?;
+    let mut emitter = Emitter::new(emit_tile.as_ref(), batch_size)?;
+    if preview_stride.is_some_and(|stride| stride < 2) {
+        return Err(PyValueError::new_err("preview_stride must be at least 2"));
+    }
+    model::check_coarse_iter(&params, coarse_iter, orbit.is_some())?;
+    model::check_final_z(&params, final_z, orbit.is_some())?;
+    if preview_stride.is_some() && coarse_iter.is_some() {
+        return Err(PyValueError::new_err(
+            "preview_stride and coarse_iter are both first passes; pass one",
+        ));
+    }
+
+    let tiles = tiles_for(width, height, tile_w, tile_h);
+    let mut order = TileOrder::from_code(emit_order, seed)?.arrange(&tiles, width, height);
+    // Tiles finished by an earlier, time-limited call
+    if let Some(skip) = &skip_task_ids {
+        order.retain(|&task_id| !skip.contains(&(task_id as u32)));
+    }
+    if let Some(rect) = dirty {
+        order.retain(|&task_id| overlaps(tiles[task_id], rect));
+    }
     let mut records = Vec::new();
-    let mut task_id = 0;
+    let mut counts = histogram.then(|| vec![0u32; max_iter as usize + 1]);
+    let tiles_total = order.len();
+    // Counted in pixels, as concurrent does
+    let pixels_total = order
+        .iter()
+        .map(|&task_id| tiles[task_id])
+        .map(|(_, _, w, h)| w * h)
+        .sum();
+    let mut progress = Progress::new(pixels_total);
+    let mut pixels_done = 0;
 
+    // Reused for every tile; only the emitted copy is allocated per tile
+    let mut scratch = TileData::default();
     let overall_start = Instant::now();
     let time_limit = Duration::from_millis(time_limit_ms);
+    // Also checked every row, so one slow tile can't run far past the limit
+    let deadline = overall_start.checked_add(time_limit);
+    let mut iterations_spent = 0u64;
 
-    for ty in (0..height).step_by(tile_h) {
-        for tx in (0..width).step_by(tile_w) {
-            // Check if we've exceeded the time limit
-            if overall_start.elapsed() >= time_limit {
+    // With preview_stride, a strided pass over every tile goes out first,
+    // and with coarse_iter a shallow one whose orbits the full pass resumes
+    let mut coarse_states = HashMap::new();
+    let first_pass = preview_stride
+        .map(|stride| (stride, None))
+        .or(coarse_iter.map(|coarse_iter| (1, Some(coarse_iter))));
+    if let Some((stride, coarse_iter)) = first_pass.filter(|_| emitter.is_active()) {
+        for &task_id in &order {
+            let (tx, ty, tw, th) = tiles[task_id];
+            if overall_start.elapsed() >= time_limit
+                || cancel_requested(py, should_cancel.as_ref())?
+            {
                 break;
             }
 
             let start = Instant::now();
             let start_time_ms = overall_start.elapsed().as_millis();
+            py.detach(|| match coarse_iter {
+                Some(coarse_iter) => {
+                    let mut state = Vec::new();
+                    render_tile_coarse(
+                        &params,
+                        tx,
+                        ty,
+                        tw,
+                        th,
+                        coarse_iter,
+                        &mut state,
+                        &mut scratch,
+                    );
+                    coarse_states.insert(task_id, state);
+                }
+                None => {
+                    render_tile_preview(
+                        &params,
+                        orbit.as_ref(),
+                        tx,
+                        ty,
+                        tw,
+                        th,
+                        stride,
+                        &mut scratch,
+                    );
+                }
+            });
+            let (cols, rows) = preview_dims(&params, tiles[task_id], stride);
+            let (data, format) =
+                scratch.to_emitted(coloring.as_ref(), max_iter, contiguous, encoding);
+
+            emitter.emit(
+                py,
+                TileUpdate {
+                    task_id: task_id as u32,
+                    thread_id: 0,
+                    tile_x: tx as u32,
+                    tile_y: ty as u32,
+                    tile_w: tw as u32,
+                    tile_h: th as u32,
+                    data,
+                    rows: rows as u32,
+                    cols: cols as u32,
+                    dtype: format.dtype(),
+                    layout: TileLayout::new(format, cols, rows),
+                    encoding: encoding.code(),
+                    start_time_ms,
+                    duration_ms: start.elapsed().as_millis(),
+                    depth: 0,
+                    cached: false,
+                    preview: true,
+                    partial: false,
+                    final_z: None,
+                    uniform: false,
+                },
+            )?;
+        }
+        emitter.flush(py)?;
+    }
+
+    for task_id in order {
+        let (tx, ty, tw, th) = tiles[task_id];
+        // Check if we've exceeded the time limit
+        if overall_start.elapsed() >= time_limit {
+            break;
+        }
+
+        if cancel_requested(py, should_cancel.as_ref())? {
+            break;
+        }
+        if iteration_budget.is_some_and(|budget| iterations_spent >= budget) {
+            break;
+        }
+
+        let start = Instant::now();
+        let start_time_ms = overall_start.elapsed().as_millis();
+        let tile_in_flight = TileInFlight::start();
 
-            let data = render_tile(width, height, tx, ty, tile_w, tile_h, max_iter);
+        // Release the GIL for the math; it is only needed for emit_tile and
+        // should_cancel, which a watcher keeps asking so a cancel stops the
+        // tile at its next row
+        let cancelled = AtomicBool::new(false);
+        let tile_done = (Mutex::new(false), Condvar::new());
+        let stats = py.detach(|| {
+            std::thread::scope(|scope| {
+                let watcher = should_cancel.as_ref().map(|should_cancel| {
+                    scope.spawn(|| watch_cancel(should_cancel, &tile_done, &cancelled))
+                });
+                let out = &mut scratch;
+                let cutoff = Cutoff {
+                    deadline,
+                    cancel: watcher.is_some().then_some(&cancelled),
+                };
+                let stats = match (&orbit, coarse_states.remove(&task_id)) {
+                    (_, Some(mut state)) => {
+                        resume_tile(&params, tx, ty, tw, th, &mut state, cutoff, out)
+                    }
+                    (Some(orbit), None) => {
+                        render_tile_perturbed_until(&params, orbit, tx, ty, tw, th, cutoff, out)
+                    }
+                    (None, None) => render_tile_until(&params, tx, ty, tw, th, cutoff, out),
+                };
+                if simulate_load {
+                    model::simulate_load(start);
+                }
 
-            let duration_ms = start.elapsed().as_millis();
+                *tile_done.0.lock().unwrap_or_else(PoisonError::into_inner) = true;
+                tile_done.1.notify_one();
+                match watcher.map(|watcher| watcher.join()) {
+                    Some(Ok(polled)) => polled.map(|()| stats),
+                    Some(Err(panic)) => std::panic::resume_unwind(panic),
+                    None => Ok(stats),
+                }
+            })
+        })?;
 
-            emit_tile.call1(
+        let duration_ms = start.elapsed().as_millis();
+        // Out of time partway through: emit the rows that made it and stop
+        let partial = scratch.len() < tw * th;
+        if partial && scratch.is_empty() {
+            break;
+        }
+
+        if !partial && let Some(counts) = counts.as_mut() {
+            scratch.add_to_histogram(counts);
+        }
+        let iterations = scratch.iteration_total(max_iter);
+        iterations_spent += iterations;
+        if !partial {
+            tile_in_flight.finish(scratch.len(), || iterations);
+        }
+
+        let (min_iter, max_iter_reached) = scratch.iteration_range(max_iter);
+        let pixels_computed = scratch.len() as u32;
+        let checksum = with_checksum.then(|| scratch.checksum());
+        let uniform_value = (skip_uniform && !partial)
+            .then(|| scratch.uniform_value())
+            .flatten();
+
+        if emitter.is_active() {
+            let values = if uniform_value.is_some() {
+                &scratch.first_pixel()
+            } else {
+                &scratch
+            };
+            let (data, format) =
+                values.to_emitted(coloring.as_ref(), max_iter, contiguous, encoding);
+            let rows = pixels_computed as usize / tw;
+            let (layout_w, layout_h) = if uniform_value.is_some() {
+                (1, 1)
+            } else {
+                (tw, rows)
+            };
+            let final_z =
+                final_z.then(|| model::final_z_tile(&params, (tx, ty, tw, th), rows, contiguous));
+            emitter.emit(
                 py,
-                (TileUpdate {
-                    task_id,
+                TileUpdate {
+                    task_id: task_id as u32,
                     thread_id: 0,
                     tile_x: tx as u32,
                     tile_y: ty as u32,
-                    tile_w: tile_w as u32,
-                    tile_h: tile_h as u32,
+                    tile_w: tw as u32,
+                    tile_h: th as u32,
                     data,
+                    rows: rows as u32,
+                    cols: tw as u32,
+                    dtype: format.dtype(),
+                    layout: TileLayout::new(format, layout_w, layout_h),
+                    encoding: encoding.code(),
                     start_time_ms,
                     duration_ms,
-                },),
+                    depth: 0,
+                    cached: false,
+                    preview: false,
+                    partial,
+                    final_z,
+                    uniform: uniform_value.is_some(),
+                },
             )?;
+        }
+        if partial {
+            break;
+        }
 
-            records.push(TaskRecord {
-                task_id,
-                thread_id: 0,
-                tile_x: tx as u32,
-                tile_y: ty as u32,
-                tile_w: tile_w as u32,
-                tile_h: tile_h as u32,
-                start_time_ms,
-                duration_ms,
-                pixels_computed: (tile_w * tile_h) as u32,
-            });
+        records.push(TaskRecord {
+            task_id: task_id as u32,
+            thread_id: 0,
+            tile_x: tx as u32,
+            tile_y: ty as u32,
+            tile_w: tw as u32,
+            tile_h: th as u32,
+            start_time_ms,
+            duration_ms,
+            pixels_computed,
+            depth: 0,
+            min_iter,
+            max_iter_reached,
+            cached: false,
+            supersampled: stats.supersampled as u32,
+            guessed: stats.guessed as u32,
+            checksum,
+            uniform_value,
+        });
 
-            task_id += 1;
+        pixels_done += tw * th;
+        progress.update(py, on_progress.as_ref(), pixels_done)?;
+    }
+    emitter.flush(py)?;
 
-            // Also check at the end of each row
-            if overall_start.elapsed() >= time_limit {
-                break;
-            }
+    // Emission follows emit_order, but records stay in raster order
+    records.sort_by_key(|record| record.task_id);
+
+    let summary = summary
+        .then(|| RenderSummary::new(&records, tiles_total, overall_start.elapsed().as_millis()));
+
+    Ok(RenderOutput::new(records, counts, summary))
+}
+
+// Asks should_cancel every POLL_INTERVAL until the tile is done, setting
+// `cancelled` for its cutoff once it says stop or raises
+fn watch_cancel(
+    should_cancel: &Py<PyAny>,
+    tile_done: &(Mutex<bool>, Condvar),
+    cancelled: &AtomicBool,
+) -> PyResult<()> {
+    loop {
+        let done = tile_done.0.lock().unwrap_or_else(PoisonError::into_inner);
+        let done = tile_done
+            .1
+            .wait_timeout_while(done, POLL_INTERVAL, |done| !*done)
+            .unwrap_or_else(PoisonError::into_inner)
+            .0;
+        if *done {
+            return Ok(());
+        }
+        drop(done);
+        let polled = Python::attach(|py| cancel_requested(py, Some(should_cancel)));
+        if !matches!(polled, Ok(false)) {
+            cancelled.store(true, Ordering::Relaxed);
+            return polled.map(drop);
         }
     }
-
-    Ok(records)
 }
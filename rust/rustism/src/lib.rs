@@ -1,13 +1,89 @@
+pub mod cache;
+pub mod color;
+pub mod dd;
+pub mod delta;
+#[cfg(feature = "double-double")]
+pub mod double_double;
 pub mod examples;
+mod metrics;
 pub mod model;
+pub mod png;
+pub mod pool;
+#[cfg(feature = "simd")]
+pub mod simd;
+mod trace;
 
 use pyo3::prelude::*;
 
+/// How much slower every tile gets with `simulate_load`, see `model::simulate_load`.
 pub const TIME_MULTIPLIER: u128 = 5;
 
 #[pymodule]
 fn rustism(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(examples::sequential::sequential, m)?)?;
     m.add_function(wrap_pyfunction!(examples::concurrent::concurrent, m)?)?;
+    m.add_function(wrap_pyfunction!(examples::plan::plan, m)?)?;
+    m.add_function(wrap_pyfunction!(examples::plan::recommended_threads, m)?)?;
+    m.add_function(wrap_pyfunction!(examples::sample::sample_point, m)?)?;
+    m.add_function(wrap_pyfunction!(examples::bench::bench, m)?)?;
+    m.add_function(wrap_pyfunction!(examples::png::render_to_png, m)?)?;
+    m.add_function(wrap_pyfunction!(examples::image::render_image, m)?)?;
+    m.add_function(wrap_pyfunction!(examples::image::render_tile_into, m)?)?;
+    m.add_function(wrap_pyfunction!(examples::capabilities::capabilities, m)?)?;
+    m.add_function(wrap_pyfunction!(examples::decode::decode_counts, m)?)?;
+    m.add_function(wrap_pyfunction!(examples::colormap::colormap, m)?)?;
+    m.add_function(wrap_pyfunction!(examples::fit::auto_fit, m)?)?;
+    #[cfg(feature = "metrics")]
+    m.add_function(wrap_pyfunction!(examples::metrics::metrics_snapshot, m)?)?;
+    #[cfg(debug_assertions)]
+    m.add_function(wrap_pyfunction!(examples::concurrent::_panic_at_task, m)?)?;
+    #[cfg(debug_assertions)]
+    m.add_function(wrap_pyfunction!(examples::concurrent::_threads_spawned, m)?)?;
+    m.add_function(wrap_pyfunction!(
+        examples::orbit::compute_reference_orbit,
+        m
+    )?)?;
+    m.add_class::<examples::renderer::Renderer>()?;
+    m.add_class::<model::RenderSummary>()?;
+    m.add_class::<model::ReferenceOrbit>()?;
+    m.add("FRACTAL_MANDELBROT", model::FRACTAL_MANDELBROT)?;
+    m.add("FRACTAL_JULIA", model::FRACTAL_JULIA)?;
+    m.add("FRACTAL_BURNING_SHIP", model::FRACTAL_BURNING_SHIP)?;
+    m.add("OUTPUT_ITERATIONS", model::OUTPUT_ITERATIONS)?;
+    m.add("OUTPUT_SMOOTH", model::OUTPUT_SMOOTH)?;
+    m.add("OUTPUT_DISTANCE", model::OUTPUT_DISTANCE)?;
+    m.add("OUTPUT_TRAP", model::OUTPUT_TRAP)?;
+    m.add("OUTPUT_SHADED", model::OUTPUT_SHADED)?;
+    m.add("OUTPUT_DOUBLE_SMOOTH", model::OUTPUT_DOUBLE_SMOOTH)?;
+    m.add("OUTPUT_ANGLE", model::OUTPUT_ANGLE)?;
+    m.add("OUTPUT_STRIPE", model::OUTPUT_STRIPE)?;
+    m.add("FORMAT_UINT8", model::FORMAT_UINT8)?;
+    m.add("FORMAT_UINT16", model::FORMAT_UINT16)?;
+    m.add("FORMAT_UINT32", model::FORMAT_UINT32)?;
+    m.add("FORMAT_FLOAT32", model::FORMAT_FLOAT32)?;
+    m.add("FORMAT_FLOAT64", model::FORMAT_FLOAT64)?;
+    m.add("FORMAT_RGBA8", model::FORMAT_RGBA8)?;
+    m.add("ENCODING_RAW", model::ENCODING_RAW)?;
+    m.add("ENCODING_DELTA_VARINT", model::ENCODING_DELTA_VARINT)?;
+    m.add("ENCODING_NORMALIZED", model::ENCODING_NORMALIZED)?;
+    m.add("TRAP_POINT", model::TRAP_POINT)?;
+    m.add("TRAP_HORIZONTAL", model::TRAP_HORIZONTAL)?;
+    m.add("TRAP_VERTICAL", model::TRAP_VERTICAL)?;
+    m.add("ORDER_RASTER", model::ORDER_RASTER)?;
+    m.add("ORDER_BOUSTROPHEDON", model::ORDER_BOUSTROPHEDON)?;
+    m.add("ORDER_CENTER_OUT", model::ORDER_CENTER_OUT)?;
+    m.add("ORDER_SHUFFLED", model::ORDER_SHUFFLED)?;
+    m.add("SAMPLES_GRID", model::SAMPLES_GRID)?;
+    m.add("SAMPLES_ROTATED_GRID", model::SAMPLES_ROTATED_GRID)?;
+    m.add("SAMPLES_HALTON", model::SAMPLES_HALTON)?;
+    m.add("TRAVERSAL_ROW_MAJOR", model::TRAVERSAL_ROW_MAJOR)?;
+    m.add("TRAVERSAL_Z_ORDER", model::TRAVERSAL_Z_ORDER)?;
+    m.add("PRECISION_F64", model::PRECISION_F64)?;
+    m.add("PRECISION_F32", model::PRECISION_F32)?;
+    m.add("PRECISION_DOUBLE_DOUBLE", model::PRECISION_DOUBLE_DOUBLE)?;
+    m.add("PALETTE_GRAYSCALE", color::PALETTE_GRAYSCALE)?;
+    m.add("PALETTE_FIRE", color::PALETTE_FIRE)?;
+    m.add("PALETTE_OCEAN", color::PALETTE_OCEAN)?;
+    m.add("PALETTE_CLASSIC", color::PALETTE_CLASSIC)?;
     Ok(())
 }
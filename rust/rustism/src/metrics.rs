@@ -0,0 +1,56 @@
+#[cfg(feature = "metrics")]
+use std::sync::atomic::{AtomicU64, Ordering::Relaxed};
+use std::time::{Duration, Instant};
+
+// Totals since the extension was loaded. Relaxed atomics, so updating them
+// costs a few uncontended adds a tile and a read is a recent value.
+#[cfg(feature = "metrics")]
+pub(crate) static TILES: AtomicU64 = AtomicU64::new(0);
+#[cfg(feature = "metrics")]
+pub(crate) static PIXELS: AtomicU64 = AtomicU64::new(0);
+#[cfg(feature = "metrics")]
+pub(crate) static ITERATIONS: AtomicU64 = AtomicU64::new(0);
+#[cfg(feature = "metrics")]
+pub(crate) static RENDER_MICROS: AtomicU64 = AtomicU64::new(0);
+#[cfg(feature = "metrics")]
+pub(crate) static IN_FLIGHT: AtomicU64 = AtomicU64::new(0);
+
+/// A tile being rendered, counted as in flight until dropped. Only tiles
+/// that `finish` count as rendered; previews, partial tiles and ones cut
+/// short for subdivision don't. Without the `metrics` feature it does
+/// nothing.
+pub(crate) struct TileInFlight {
+    start: Instant,
+}
+
+impl TileInFlight {
+    pub(crate) fn start() -> Self {
+        #[cfg(feature = "metrics")]
+        IN_FLIGHT.fetch_add(1, Relaxed);
+        Self {
+            start: Instant::now(),
+        }
+    }
+
+    /// Counts the tile as rendered. `iterations` is only called with the
+    /// feature, so it can hold the tile's whole `iteration_total`.
+    pub(crate) fn finish(self, pixels: usize, iterations: impl FnOnce() -> u64) {
+        let elapsed: Duration = self.start.elapsed();
+        #[cfg(feature = "metrics")]
+        {
+            TILES.fetch_add(1, Relaxed);
+            PIXELS.fetch_add(pixels as u64, Relaxed);
+            ITERATIONS.fetch_add(iterations(), Relaxed);
+            RENDER_MICROS.fetch_add(elapsed.as_micros() as u64, Relaxed);
+        }
+        #[cfg(not(feature = "metrics"))]
+        let _ = (pixels, iterations, elapsed);
+    }
+}
+
+impl Drop for TileInFlight {
+    fn drop(&mut self) {
+        #[cfg(feature = "metrics")]
+        IN_FLIGHT.fetch_sub(1, Relaxed);
+    }
+}
@@ -1,5 +1,11 @@
+use crate::color::{Coloring, PaletteArg, Tone};
+use crate::dd::DoubleDouble;
+use crate::delta;
 use pyo3::IntoPyObject;
+use pyo3::exceptions::{PyUserWarning, PyValueError};
 use pyo3::prelude::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
 
 #[derive(Debug, IntoPyObject)]
 pub struct TaskRecord {
@@ -11,7 +17,29 @@ pub struct TaskRecord {
     pub tile_h: u32,
     pub start_time_ms: u128,
     pub duration_ms: u128,
+    // Values actually rendered, i.e. the length of the tile's buffer, or 0
+    // for a cached tile
     pub pixels_computed: u32,
+    // 0 for grid tiles; subdivided tiles sit one level below their parent
+    pub depth: u32,
+    // Range of the tile's escape counts (floored for float output); both
+    // are max_iter for a fully interior tile
+    pub min_iter: u32,
+    pub max_iter_reached: u32,
+    // Served from a `Renderer`'s tile cache instead of being rendered
+    pub cached: bool,
+    // Pixels that got more than one sample: all of them with
+    // samples_per_axis above 1, or with aa_threshold just those by an edge
+    pub supersampled: u32,
+    // Pixels solid_guess filled in from their rectangle's border without
+    // iterating them
+    pub guessed: u32,
+    // With with_checksum, `TileData::checksum` of the rendered values,
+    // before any palette or contiguous packing
+    pub checksum: Option<u64>,
+    // With skip_uniform, the value every pixel of the tile holds, before
+    // any palette, if they all hold the same one; see `TileUpdate::uniform`
+    pub uniform_value: Option<f64>,
 }
 
 #[derive(IntoPyObject)]
@@ -22,54 +50,3328 @@ pub struct TileUpdate {
     pub tile_y: u32,
     pub tile_w: u32,
     pub tile_h: u32,
-    pub data: Vec<u16>,
+    pub data: TileData,
+    // Actual rendered shape of `data` (row-major), which is smaller than
+    // tile_h x tile_w for tiles clipped by the image edge
+    pub rows: u32,
+    pub cols: u32,
+    // Of the values in `data`, once decoded if it's encoded
+    pub dtype: &'static str,
+    pub layout: TileLayout,
+    // One of the ENCODING_ codes, see `Encoding`
+    pub encoding: u8,
     pub start_time_ms: u128,
     pub duration_ms: u128,
+    pub depth: u32,
+    pub cached: bool,
+    // A strided preview, with rows and cols to match, see `render_tile_preview`
+    pub preview: bool,
+    // Cut short by the time limit, so only its first `rows` rows are there.
+    // Partial tiles get no record, so a resumed render redoes them.
+    pub partial: bool,
+    // With final_z, `final_z_tile` of the tile, packed like `data`; None
+    // otherwise and for previews
+    pub final_z: Option<TileData>,
+    // With skip_uniform, every pixel holds the record's uniform_value, so
+    // `data` is just the first one, colored and packed as usual, and its
+    // layout 1 x 1. Fill the tile's rows x cols with it.
+    pub uniform: bool,
 }
 
+/// Per-pixel tile payload: escape counts, or an f32 value per pixel for the
+/// smooth and distance output modes, or their colors once a palette is applied.
+/// Counts are emitted in the width `iteration_dtype` picks.
+#[derive(Debug, Clone, IntoPyObject)]
+pub enum TileData {
+    Iterations(Vec<u32>),
+    Values(Vec<f32>),
+    /// f64 smooth values, see `OutputMode::DoubleSmooth`
+    Doubles(Vec<f64>),
+    /// RGBA pixels, see `Palette::rgba`
+    Rgba(Vec<u32>),
+    /// Native-endian bytes of one of the above, for `numpy.frombuffer`.
+    Bytes(Vec<u8>),
+}
+
+pub const FORMAT_UINT8: u8 = 0;
+pub const FORMAT_UINT16: u8 = 1;
+pub const FORMAT_UINT32: u8 = 2;
+pub const FORMAT_FLOAT32: u8 = 3;
+pub const FORMAT_FLOAT64: u8 = 4;
+pub const FORMAT_RGBA8: u8 = 5;
+
+/// Element type of an emitted tile. Unlike its dtype, this tells colors
+/// apart from 32-bit escape counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TileFormat {
+    Uint8,
+    Uint16,
+    Uint32,
+    Float32,
+    Float64,
+    /// Colored pixels, R, G, B, A bytes in memory
+    Rgba8,
+}
+
+impl TileFormat {
+    /// Narrowest unsigned format that holds every escape count up to
+    /// `max_iter`.
+    pub fn for_counts(max_iter: u32) -> Self {
+        if max_iter <= u8::MAX as u32 {
+            TileFormat::Uint8
+        } else if max_iter <= u16::MAX as u32 {
+            TileFormat::Uint16
+        } else {
+            TileFormat::Uint32
+        }
+    }
+
+    pub fn code(self) -> u8 {
+        match self {
+            TileFormat::Uint8 => FORMAT_UINT8,
+            TileFormat::Uint16 => FORMAT_UINT16,
+            TileFormat::Uint32 => FORMAT_UINT32,
+            TileFormat::Float32 => FORMAT_FLOAT32,
+            TileFormat::Float64 => FORMAT_FLOAT64,
+            TileFormat::Rgba8 => FORMAT_RGBA8,
+        }
+    }
+
+    /// Numpy dtype name of the elements; an RGBA pixel reads as one uint32.
+    pub fn dtype(self) -> &'static str {
+        match self {
+            TileFormat::Uint8 => "uint8",
+            TileFormat::Uint16 => "uint16",
+            TileFormat::Uint32 | TileFormat::Rgba8 => "uint32",
+            TileFormat::Float32 => "float32",
+            TileFormat::Float64 => "float64",
+        }
+    }
+
+    pub fn bytes_per_pixel(self) -> u32 {
+        match self {
+            TileFormat::Uint8 => 1,
+            TileFormat::Uint16 => 2,
+            TileFormat::Uint32 | TileFormat::Float32 | TileFormat::Rgba8 => 4,
+            TileFormat::Float64 => 8,
+        }
+    }
+}
+
+/// Where an emitted tile's pixels sit in its data once packed by
+/// `contiguous`, so an uploader can copy it without knowing the output
+/// mode. Rows are back to back from byte 0, `stride` bytes apart.
+#[derive(Debug, Clone, Copy, IntoPyObject)]
+pub struct TileLayout {
+    // One of the FORMAT_ codes, see `TileFormat`
+    pub format: u8,
+    pub bytes_per_pixel: u32,
+    pub width: u32,
+    pub height: u32,
+    pub stride: u32,
+}
+
+impl TileLayout {
+    pub fn new(format: TileFormat, width: usize, height: usize) -> Self {
+        let bytes_per_pixel = format.bytes_per_pixel();
+        Self {
+            format: format.code(),
+            bytes_per_pixel,
+            width: width as u32,
+            height: height as u32,
+            stride: width as u32 * bytes_per_pixel,
+        }
+    }
+}
+
+pub const ENCODING_RAW: u8 = 0;
+pub const ENCODING_DELTA_VARINT: u8 = 1;
+pub const ENCODING_NORMALIZED: u8 = 2;
+
+/// How an emitted tile's data is coded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// As the output mode and `contiguous` leave it
+    Raw,
+    /// Escape counts as bytes from `delta::encode_counts`, much smaller on
+    /// the wire; `rustism.decode_counts` gives the counts back.
+    DeltaVarint,
+    /// Smooth values divided by `max_iter`, in `[0, 1]` with interior
+    /// pixels at exactly 1.0, so a palette over them holds still across
+    /// frames with different `max_iter`.
+    Normalized,
+}
+
+impl Encoding {
+    pub fn from_code(code: u8, output: OutputMode, colored: bool) -> PyResult<Self> {
+        match code {
+            ENCODING_RAW => Ok(Encoding::Raw),
+            ENCODING_DELTA_VARINT if output == OutputMode::Iterations && !colored => {
+                Ok(Encoding::DeltaVarint)
+            }
+            ENCODING_DELTA_VARINT => Err(PyValueError::new_err(
+                "delta encoding needs uncolored iteration output",
+            )),
+            ENCODING_NORMALIZED
+                if matches!(output, OutputMode::Smooth | OutputMode::DoubleSmooth) && !colored =>
+            {
+                Ok(Encoding::Normalized)
+            }
+            ENCODING_NORMALIZED => Err(PyValueError::new_err(
+                "normalized encoding needs uncolored smooth output",
+            )),
+            _ => Err(PyValueError::new_err(format!("unknown encoding {code}"))),
+        }
+    }
+
+    pub fn code(self) -> u8 {
+        match self {
+            Encoding::Raw => ENCODING_RAW,
+            Encoding::DeltaVarint => ENCODING_DELTA_VARINT,
+            Encoding::Normalized => ENCODING_NORMALIZED,
+        }
+    }
+}
+
+impl Default for TileData {
+    fn default() -> Self {
+        TileData::Iterations(Vec::new())
+    }
+}
+
+impl TileData {
+    /// Format of the elements.
+    pub fn format(&self) -> TileFormat {
+        match self {
+            TileData::Iterations(_) => TileFormat::Uint32,
+            TileData::Values(_) => TileFormat::Float32,
+            TileData::Doubles(_) => TileFormat::Float64,
+            TileData::Rgba(_) => TileFormat::Rgba8,
+            TileData::Bytes(_) => TileFormat::Uint8,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            TileData::Iterations(v) => v.len(),
+            TileData::Values(v) => v.len(),
+            TileData::Doubles(v) => v.len(),
+            TileData::Rgba(v) => v.len(),
+            TileData::Bytes(v) => v.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 64-bit FNV-1a hash of the values' little-endian bytes, so the same
+    /// pixels give the same checksum on any platform.
+    pub fn checksum(&self) -> u64 {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let mut feed = |bytes: &[u8]| {
+            for &byte in bytes {
+                hash = (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3);
+            }
+        };
+        match self {
+            TileData::Iterations(v) => v.iter().for_each(|value| feed(&value.to_le_bytes())),
+            TileData::Values(v) => v.iter().for_each(|value| feed(&value.to_le_bytes())),
+            TileData::Doubles(v) => v.iter().for_each(|value| feed(&value.to_le_bytes())),
+            // Already R, G, B, A in memory
+            TileData::Rgba(v) => v.iter().for_each(|value| feed(&value.to_ne_bytes())),
+            TileData::Bytes(v) => feed(v),
+        }
+        hash
+    }
+
+    /// Iterations the tile is charged against an `iteration_budget`: each
+    /// pixel's escape count, interior ones `max_iter`, whether or not a
+    /// shortcut skipped them, so the total is the same on any machine.
+    pub fn iteration_total(&self, max_iter: u32) -> u64 {
+        let charge = |value: f64| {
+            if value < 0.0 {
+                max_iter as u64
+            } else {
+                value.min(max_iter as f64) as u64
+            }
+        };
+        match self {
+            TileData::Iterations(v) => v.iter().map(|&i| i as u64).sum(),
+            TileData::Values(v) => v.iter().map(|&value| charge(value as f64)).sum(),
+            TileData::Doubles(v) => v.iter().map(|&value| charge(value)).sum(),
+            TileData::Rgba(_) | TileData::Bytes(_) => 0,
+        }
+    }
+
+    /// Empties the tile as an escape-count buffer, keeping its allocation
+    /// if it already was one.
+    pub(crate) fn iterations_mut(&mut self) -> &mut Vec<u32> {
+        if !matches!(self, TileData::Iterations(_)) {
+            *self = TileData::Iterations(Vec::new());
+        }
+        let TileData::Iterations(v) = self else {
+            unreachable!()
+        };
+        v.clear();
+        v
+    }
+
+    /// `iterations_mut` for float values.
+    fn values_mut(&mut self) -> &mut Vec<f32> {
+        if !matches!(self, TileData::Values(_)) {
+            *self = TileData::Values(Vec::new());
+        }
+        let TileData::Values(v) = self else {
+            unreachable!()
+        };
+        v.clear();
+        v
+    }
+
+    /// `iterations_mut` for f64 values.
+    fn doubles_mut(&mut self) -> &mut Vec<f64> {
+        if !matches!(self, TileData::Doubles(_)) {
+            *self = TileData::Doubles(Vec::new());
+        }
+        let TileData::Doubles(v) = self else {
+            unreachable!()
+        };
+        v.clear();
+        v
+    }
+
+    /// Counts each pixel into `histogram[escape iteration]`; interior pixels
+    /// land in the last bucket. Float values are floored, and negative ones
+    /// are interior, see `interior_value`.
+    pub fn add_to_histogram(&self, histogram: &mut [u32]) {
+        let last = histogram.len() - 1;
+        match self {
+            TileData::Iterations(v) => {
+                for &i in v {
+                    histogram[(i as usize).min(last)] += 1;
+                }
+            }
+            TileData::Values(v) => {
+                for &i in v {
+                    let bucket = if i < 0.0 { last } else { i as usize };
+                    histogram[bucket.min(last)] += 1;
+                }
+            }
+            TileData::Doubles(v) => {
+                for &i in v {
+                    let bucket = if i < 0.0 { last } else { i as usize };
+                    histogram[bucket.min(last)] += 1;
+                }
+            }
+            TileData::Rgba(_) | TileData::Bytes(_) => {}
+        }
+    }
+
+    /// Smallest and largest escape count in the tile, flooring float values
+    /// and counting negative (interior) ones as `max_iter`. `(0, 0)` for an
+    /// empty or already packed tile.
+    pub fn iteration_range(&self, max_iter: u32) -> (u32, u32) {
+        let range = match self {
+            TileData::Iterations(v) => v.iter().fold(None, |range, &i| widen(range, i)),
+            TileData::Values(v) => v.iter().fold(None, |range, &i| {
+                widen(range, if i < 0.0 { max_iter } else { i as u32 })
+            }),
+            TileData::Doubles(v) => v.iter().fold(None, |range, &i| {
+                widen(range, if i < 0.0 { max_iter } else { i as u32 })
+            }),
+            TileData::Rgba(_) | TileData::Bytes(_) => None,
+        };
+        range.unwrap_or((0, 0))
+    }
+
+    /// The value every pixel holds, if the tile has pixels and they all
+    /// hold the same one. Floats compare by their bits, so an all-NaN tile
+    /// counts. `None` for a colored or packed tile.
+    pub fn uniform_value(&self) -> Option<f64> {
+        fn same<T: Copy>(v: &[T], bits: impl Fn(T) -> u64) -> Option<T> {
+            let (&first, rest) = v.split_first()?;
+            rest.iter()
+                .all(|&x| bits(x) == bits(first))
+                .then_some(first)
+        }
+        match self {
+            TileData::Iterations(v) => same(v, |i| i as u64).map(f64::from),
+            TileData::Values(v) => same(v, |x| x.to_bits() as u64).map(f64::from),
+            TileData::Doubles(v) => same(v, f64::to_bits),
+            TileData::Rgba(_) | TileData::Bytes(_) => None,
+        }
+    }
+
+    /// The tile cut down to its first pixel, which stands for all of them
+    /// in a uniform tile.
+    pub fn first_pixel(&self) -> Self {
+        match self {
+            TileData::Iterations(v) => TileData::Iterations(v.iter().take(1).copied().collect()),
+            TileData::Values(v) => TileData::Values(v.iter().take(1).copied().collect()),
+            TileData::Doubles(v) => TileData::Doubles(v.iter().take(1).copied().collect()),
+            TileData::Rgba(v) => TileData::Rgba(v.iter().take(1).copied().collect()),
+            TileData::Bytes(v) => TileData::Bytes(v.iter().take(1).copied().collect()),
+        }
+    }
+
+    /// Copies a rendered tile out of its scratch buffer in the form it is
+    /// emitted in: escape values replaced with their colors if `coloring`
+    /// is set, then packed into one contiguous byte buffer if `contiguous`.
+    /// Read the bytes back with `numpy.frombuffer(data, dtype).reshape(rows,
+    /// cols)`, using the dtype of the format returned alongside.
+    pub fn to_emitted(
+        &self,
+        coloring: Option<&Coloring>,
+        max_iter: u32,
+        contiguous: bool,
+        encoding: Encoding,
+    ) -> (Self, TileFormat) {
+        match (self, coloring) {
+            (TileData::Iterations(v), None) if encoding == Encoding::DeltaVarint => (
+                TileData::Bytes(delta::encode_counts(v)),
+                TileFormat::for_counts(max_iter),
+            ),
+            (TileData::Iterations(v), Some(coloring)) => (
+                colorize(v, coloring, max_iter, contiguous),
+                TileFormat::Rgba8,
+            ),
+            (TileData::Iterations(v), None) if contiguous => (
+                TileData::Bytes(pack_counts(v, max_iter)),
+                TileFormat::for_counts(max_iter),
+            ),
+            (TileData::Iterations(_), None) => (self.clone(), TileFormat::for_counts(max_iter)),
+            (TileData::Values(_) | TileData::Doubles(_), None)
+                if encoding == Encoding::Normalized =>
+            {
+                self.normalized(max_iter)
+                    .to_emitted(None, max_iter, contiguous, Encoding::Raw)
+            }
+            (TileData::Values(v), Some(coloring)) => (
+                colorize(v, coloring, max_iter, contiguous),
+                TileFormat::Rgba8,
+            ),
+            (TileData::Doubles(v), Some(coloring)) => (
+                colorize(v, coloring, max_iter, contiguous),
+                TileFormat::Rgba8,
+            ),
+            (data, _) if contiguous => (data.to_bytes(), data.format()),
+            (data, _) => (data.clone(), data.format()),
+        }
+    }
+
+    /// Float values over `max_iter`, clamped into `[0, 1]`, for
+    /// `Encoding::Normalized`. Interior pixels, at `max_iter` or negative
+    /// with `interior`, come out exactly 1.0.
+    fn normalized(&self, max_iter: u32) -> Self {
+        match self {
+            TileData::Values(v) => {
+                let max = max_iter as f32;
+                let normalize = |x: f32| if x < 0.0 { 1.0 } else { (x / max).min(1.0) };
+                TileData::Values(v.iter().map(|&x| normalize(x)).collect())
+            }
+            TileData::Doubles(v) => {
+                let max = max_iter as f64;
+                let normalize = |x: f64| if x < 0.0 { 1.0 } else { (x / max).min(1.0) };
+                TileData::Doubles(v.iter().map(|&x| normalize(x)).collect())
+            }
+            _ => self.clone(),
+        }
+    }
+
+    fn to_bytes(&self) -> Self {
+        match self {
+            TileData::Iterations(v) => {
+                TileData::Bytes(v.iter().flat_map(|x| x.to_ne_bytes()).collect())
+            }
+            TileData::Values(v) => {
+                TileData::Bytes(v.iter().flat_map(|x| x.to_ne_bytes()).collect())
+            }
+            TileData::Doubles(v) => {
+                TileData::Bytes(v.iter().flat_map(|x| x.to_ne_bytes()).collect())
+            }
+            TileData::Rgba(v) => TileData::Bytes(v.iter().flat_map(|x| x.to_ne_bytes()).collect()),
+            TileData::Bytes(v) => TileData::Bytes(v.clone()),
+        }
+    }
+}
+
+/// Narrowest unsigned dtype that holds every escape count up to `max_iter`.
+pub fn iteration_dtype(max_iter: u32) -> &'static str {
+    TileFormat::for_counts(max_iter).dtype()
+}
+
+/// Native-endian bytes of escape counts, each in the `iteration_dtype` width.
+pub fn pack_counts(counts: &[u32], max_iter: u32) -> Vec<u8> {
+    match TileFormat::for_counts(max_iter) {
+        TileFormat::Uint8 => counts.iter().map(|&i| i as u8).collect(),
+        TileFormat::Uint16 => counts
+            .iter()
+            .flat_map(|&i| (i as u16).to_ne_bytes())
+            .collect(),
+        _ => counts.iter().flat_map(|i| i.to_ne_bytes()).collect(),
+    }
+}
+
+fn colorize<T: Copy + Into<f64>>(
+    values: &[T],
+    coloring: &Coloring,
+    max_iter: u32,
+    contiguous: bool,
+) -> TileData {
+    let colors = values.iter().map(|&i| coloring.rgba(i.into(), max_iter));
+    if contiguous {
+        TileData::Bytes(colors.flat_map(u32::to_ne_bytes).collect())
+    } else {
+        TileData::Rgba(colors.collect())
+    }
+}
+
+fn widen(range: Option<(u32, u32)>, i: u32) -> Option<(u32, u32)> {
+    Some(match range {
+        Some((lo, hi)) => (lo.min(i), hi.max(i)),
+        None => (i, i),
+    })
+}
+
+/// What a renderer hands back to Python: the records alone, or a tuple of
+/// the records followed by the histogram and/or summary that were requested.
+#[derive(IntoPyObject)]
+pub enum RenderOutput {
+    Records(Vec<TaskRecord>),
+    WithHistogram(Vec<TaskRecord>, Vec<u32>),
+    WithSummary(Vec<TaskRecord>, RenderSummary),
+    WithBoth(Vec<TaskRecord>, Vec<u32>, RenderSummary),
+}
+
+impl RenderOutput {
+    pub fn new(
+        records: Vec<TaskRecord>,
+        histogram: Option<Vec<u32>>,
+        mut summary: Option<RenderSummary>,
+    ) -> Self {
+        if let (Some(histogram), Some(summary)) = (&histogram, &mut summary) {
+            let total: u64 = histogram.iter().map(|&n| n as u64).sum();
+            let interior = histogram.last().copied().unwrap_or(0);
+            if total > 0 {
+                summary.interior_fraction = interior as f64 / total as f64;
+            }
+        }
+        match (histogram, summary) {
+            (None, None) => RenderOutput::Records(records),
+            (Some(histogram), None) => RenderOutput::WithHistogram(records, histogram),
+            (None, Some(summary)) => RenderOutput::WithSummary(records, summary),
+            (Some(histogram), Some(summary)) => RenderOutput::WithBoth(records, histogram, summary),
+        }
+    }
+}
+
+/// Totals and tile-duration statistics of one render.
+#[pyclass(get_all)]
+#[derive(Debug, Clone)]
+pub struct RenderSummary {
+    pub wall_time_ms: u128,
+    pub total_pixels: u64,
+    pub tiles_rendered: u32,
+    // Grid tiles that got no pixels before the time limit or a cancel
+    pub tiles_skipped: u32,
+    pub min_tile_ms: u128,
+    pub mean_tile_ms: f64,
+    pub p50_tile_ms: u128,
+    pub p95_tile_ms: u128,
+    pub max_tile_ms: u128,
+    // Range of the escape counts over every finished tile, as in TaskRecord;
+    // both 0 when none finished
+    pub min_iter: u32,
+    pub max_iter_reached: u32,
+    // Share of the histogram's pixels that are interior; 0 without one
+    pub interior_fraction: f64,
+    // Grid tiles served from and missing from a `Renderer`'s tile cache,
+    // which tiles_rendered leaves out; always 0 without one
+    pub cache_hits: u32,
+    pub cache_misses: u32,
+    // Grid and sub-tiles each thread finished, by thread_id
+    pub tiles_per_thread: Vec<u32>,
+    // Throughput over the wall time; only `bench` fills these in
+    pub pixels_per_sec: f64,
+    pub iterations_per_sec: f64,
+}
+
+impl RenderSummary {
+    /// `tiles_total` is the size of the grid the records were cut from.
+    pub fn new(records: &[TaskRecord], tiles_total: usize, wall_time_ms: u128) -> Self {
+        // Cached tiles took no time, so they'd only skew the statistics
+        let mut durations: Vec<u128> = records
+            .iter()
+            .filter(|record| !record.cached)
+            .map(|record| record.duration_ms)
+            .collect();
+        durations.sort_unstable();
+
+        // Nearest-rank percentile
+        let percentile = |p: f64| match durations.len() {
+            0 => 0,
+            n => durations[((p / 100.0 * n as f64).ceil() as usize).clamp(1, n) - 1],
+        };
+        let mean_tile_ms = match durations.len() {
+            0 => 0.0,
+            n => durations.iter().sum::<u128>() as f64 / n as f64,
+        };
+
+        // A sub-tile's grid tile is its task_id with one quadrant digit per level dropped
+        let mut started: Vec<u32> = records
+            .iter()
+            .map(|record| record.task_id >> (2 * record.depth))
+            .collect();
+        started.sort_unstable();
+        started.dedup();
+
+        let mut tiles_per_thread = Vec::new();
+        for record in records.iter().filter(|record| !record.cached) {
+            let thread_id = record.thread_id as usize;
+            if tiles_per_thread.len() <= thread_id {
+                tiles_per_thread.resize(thread_id + 1, 0);
+            }
+            tiles_per_thread[thread_id] += 1;
+        }
+
+        Self {
+            wall_time_ms,
+            total_pixels: records
+                .iter()
+                .map(|record| record.pixels_computed as u64)
+                .sum(),
+            tiles_rendered: durations.len() as u32,
+            tiles_skipped: tiles_total.saturating_sub(started.len()) as u32,
+            min_tile_ms: durations.first().copied().unwrap_or(0),
+            mean_tile_ms,
+            p50_tile_ms: percentile(50.0),
+            p95_tile_ms: percentile(95.0),
+            max_tile_ms: durations.last().copied().unwrap_or(0),
+            min_iter: records
+                .iter()
+                .map(|record| record.min_iter)
+                .min()
+                .unwrap_or(0),
+            max_iter_reached: records
+                .iter()
+                .map(|record| record.max_iter_reached)
+                .max()
+                .unwrap_or(0),
+            interior_fraction: 0.0,
+            cache_hits: records.iter().filter(|record| record.cached).count() as u32,
+            cache_misses: 0,
+            tiles_per_thread,
+            pixels_per_sec: 0.0,
+            iterations_per_sec: 0.0,
+        }
+    }
+}
+
+#[pymethods]
+impl RenderSummary {
+    fn __repr__(&self) -> String {
+        format!(
+            "RenderSummary(wall_time_ms={}, total_pixels={}, tiles_rendered={}, \
+             tiles_skipped={}, p50_tile_ms={}, p95_tile_ms={}, min_iter={}, \
+             max_iter_reached={}, interior_fraction={}, cache_hits={}, cache_misses={})",
+            self.wall_time_ms,
+            self.total_pixels,
+            self.tiles_rendered,
+            self.tiles_skipped,
+            self.p50_tile_ms,
+            self.p95_tile_ms,
+            self.min_iter,
+            self.max_iter_reached,
+            self.interior_fraction,
+            self.cache_hits,
+            self.cache_misses,
+        )
+    }
+}
+
+pub const DEFAULT_CENTER_RE: f64 = -0.75;
+pub const DEFAULT_CENTER_IM: f64 = 0.0;
+pub const DEFAULT_VIEW_WIDTH: f64 = 3.5;
+pub const DEFAULT_VIEW_HEIGHT: f64 = 2.0;
+
+/// Maps pixels onto the complex plane: `center` sits at the middle of the
+/// image and every pixel is `scale` units wide on both axes.
+#[derive(Debug, Clone, Copy)]
+pub struct Viewport {
+    pub center_re: f64,
+    pub center_im: f64,
+    pub scale: f64,
+}
+
+impl Viewport {
+    /// The original full-set view, 3.5 by 2 units, fitted into the image.
+    pub fn default_for(width: usize, height: usize) -> Self {
+        Self {
+            center_re: DEFAULT_CENTER_RE,
+            center_im: DEFAULT_CENTER_IM,
+            scale: default_scale(width, height),
+        }
+    }
+}
+
+/// Value of a render's `center_re` or `center_im`: a float, or a decimal
+/// string for deep zooms, where an f64 would already have dropped digits.
+#[derive(Debug, Clone, FromPyObject)]
+pub enum CoordinateArg {
+    Float(f64),
+    Decimal(String),
+}
+
+impl CoordinateArg {
+    pub fn into_decimal(self) -> String {
+        match self {
+            // Shortest form that reads back as the same f64
+            CoordinateArg::Float(value) => format!("{value:e}"),
+            CoordinateArg::Decimal(value) => value,
+        }
+    }
+}
+
+/// `(center_re, center_im)` as decimal strings
+pub type DeepCenter = (String, String);
+
+/// Splits the center arguments into the f64 center of the viewport and the
+/// full-precision center of a reference orbit, if any. A string coordinate
+/// works like passing both as `deep_center`.
+pub fn resolve_center(
+    center_re: CoordinateArg,
+    center_im: CoordinateArg,
+    deep_center: Option<DeepCenter>,
+) -> PyResult<(f64, f64, Option<DeepCenter>)> {
+    if let (CoordinateArg::Float(re), CoordinateArg::Float(im)) = (&center_re, &center_im) {
+        return Ok((*re, *im, deep_center));
+    }
+    if deep_center.is_some() {
+        return Err(PyValueError::new_err(
+            "pass string center coordinates or deep_center, not both",
+        ));
+    }
+
+    let (re, im) = (center_re.into_decimal(), center_im.into_decimal());
+    let (re_f64, im_f64) = (
+        parse_coordinate(&re)?.to_f64(),
+        parse_coordinate(&im)?.to_f64(),
+    );
+    Ok((re_f64, im_f64, Some((re, im))))
+}
+
+/// Hands a resolved deep center to the deep-zoom path `params` asks for:
+/// with double-double precision it becomes `dd_center`, and every pixel is
+/// iterated in full; otherwise it is the center of a perturbation orbit.
+/// A precomputed `reference_orbit` stands in for the deep center, and the
+/// view is centered on it.
+pub fn deep_zoom(
+    params: &mut RenderParams,
+    deep_center: Option<DeepCenter>,
+    reference_orbit: Option<&ReferenceOrbit>,
+) -> PyResult<Option<ReferenceOrbit>> {
+    if let Some(orbit) = reference_orbit {
+        if deep_center.is_some() {
+            return Err(PyValueError::new_err(
+                "pass reference_orbit or a deep center, not both",
+            ));
+        }
+        if params.precision == Precision::DoubleDouble {
+            return Err(PyValueError::new_err(
+                "reference_orbit does not apply to double-double precision",
+            ));
+        }
+        if orbit.max_iter < params.max_iter {
+            return Err(PyValueError::new_err(
+                "reference_orbit was computed for fewer iterations than max_iter",
+            ));
+        }
+        ReferenceOrbit::check_params(params)?;
+        params.viewport.center_re = orbit.center_re.to_f64();
+        params.viewport.center_im = orbit.center_im.to_f64();
+        // The view moved since the caller validated it
+        params.validate()?;
+        return Ok(Some(orbit.clone()));
+    }
+    let Some((re, im)) = deep_center else {
+        return Ok(None);
+    };
+    if params.precision == Precision::DoubleDouble {
+        params.dd_center = Some((parse_coordinate(&re)?, parse_coordinate(&im)?));
+        return Ok(None);
+    }
+    ReferenceOrbit::new(params, &re, &im).map(Some)
+}
+
+/// Pixel rectangle `(x, y, w, h)` in the image, like a tile.
+pub type PixelRect = (usize, usize, usize, usize);
+
+/// Resolves the `dirty_x, dirty_y, dirty_w, dirty_h` arguments, which are
+/// given all together or not at all.
+pub fn dirty_rect(
+    x: Option<usize>,
+    y: Option<usize>,
+    w: Option<usize>,
+    h: Option<usize>,
+) -> PyResult<Option<PixelRect>> {
+    match (x, y, w, h) {
+        (None, None, None, None) => Ok(None),
+        (Some(_), Some(_), Some(0), Some(_)) | (Some(_), Some(_), Some(_), Some(0)) => {
+            Err(PyValueError::new_err("dirty rectangle must not be empty"))
+        }
+        (Some(x), Some(y), Some(w), Some(h)) => Ok(Some((x, y, w, h))),
+        _ => Err(PyValueError::new_err(
+            "pass all of dirty_x, dirty_y, dirty_w and dirty_h, or none",
+        )),
+    }
+}
+
+/// The arguments `sequential`, `concurrent` and `Renderer.render` share,
+/// as they come from Python. `RenderRequest::from_args` resolves them.
+pub struct RenderArgs<'a> {
+    pub width: usize,
+    pub height: usize,
+    pub max_iter: u32,
+    pub emit_tile: Option<&'a Py<PyAny>>,
+    pub center_re: CoordinateArg,
+    pub center_im: CoordinateArg,
+    pub scale: Option<f64>,
+    pub fractal_kind: u8,
+    pub julia_re: f64,
+    pub julia_im: f64,
+    pub smooth: bool,
+    pub precision: u8,
+    pub histogram: bool,
+    pub summary: bool,
+    pub stats_only: bool,
+    pub power: u32,
+    pub output_mode: u8,
+    pub samples_per_axis: u32,
+    pub sample_pattern: u8,
+    pub deep_center: Option<DeepCenter>,
+    pub reference_orbit: Option<&'a ReferenceOrbit>,
+    pub escape_radius: f64,
+    pub palette: Option<PaletteArg>,
+    pub exposure: f64,
+    pub gamma: f64,
+    pub palette_offset: f32,
+    pub interior: bool,
+    pub trap: u8,
+    pub trap_re: f64,
+    pub trap_im: f64,
+    pub light_angle: f64,
+    pub light_height: f64,
+    pub stripe_freq: f64,
+    pub periodicity: bool,
+    pub flip_y: bool,
+    pub aa_threshold: Option<f64>,
+    pub solid_guess: bool,
+    pub check_every: u16,
+    pub pixel_center: bool,
+    pub traversal: u8,
+    pub encoding: u8,
+    pub dirty_x: Option<usize>,
+    pub dirty_y: Option<usize>,
+    pub dirty_w: Option<usize>,
+    pub dirty_h: Option<usize>,
+    pub focus_x: Option<f64>,
+    pub focus_y: Option<f64>,
+}
+
+/// A render's shared arguments, resolved and validated.
+pub struct RenderRequest {
+    pub params: RenderParams,
+    pub orbit: Option<ReferenceOrbit>,
+    pub coloring: Option<Coloring>,
+    pub encoding: Encoding,
+    pub histogram: bool,
+    pub summary: bool,
+    pub dirty: Option<PixelRect>,
+    pub focus: Option<(f64, f64)>,
+}
+
+impl RenderRequest {
+    pub fn from_args(py: Python<'_>, args: RenderArgs<'_>) -> PyResult<Self> {
+        let (histogram, summary) = resolve_stats_only(
+            args.stats_only,
+            args.emit_tile,
+            args.histogram,
+            args.summary,
+        )?;
+        let (center_re, center_im, deep_center) =
+            resolve_center(args.center_re, args.center_im, args.deep_center)?;
+        let (width, height) = (args.width, args.height);
+        let mut params = RenderParams {
+            width,
+            height,
+            max_iter: args.max_iter,
+            viewport: Viewport {
+                center_re,
+                center_im,
+                scale: args.scale.unwrap_or_else(|| default_scale(width, height)),
+            },
+            kind: FractalKind::from_code(args.fractal_kind, args.julia_re, args.julia_im)?,
+            output: OutputMode::from_code(args.output_mode, args.smooth)?,
+            precision: Precision::from_code(args.precision)?,
+            power: args.power,
+            samples_per_axis: args.samples_per_axis,
+            sample_pattern: SamplePattern::from_code(args.sample_pattern)?,
+            escape_radius: args.escape_radius,
+            interior: args.interior,
+            trap: OrbitTrap::from_code(args.trap, args.trap_re, args.trap_im)?,
+            light: Light::new(args.light_angle, args.light_height)?,
+            stripe_freq: args.stripe_freq,
+            periodicity: args.periodicity,
+            flip_y: args.flip_y,
+            aa_threshold: args.aa_threshold,
+            solid_guess: args.solid_guess,
+            dd_center: None,
+            check_every: args.check_every,
+            pixel_center: args.pixel_center,
+            traversal: Traversal::from_code(args.traversal)?,
+        };
+        params.validate()?;
+        if params.solid_guess {
+            warn_solid_guess(py)?;
+        }
+        let orbit = deep_zoom(&mut params, deep_center, args.reference_orbit)?;
+        let coloring = Coloring::new(
+            py,
+            args.palette,
+            Tone::new(args.exposure, args.gamma)?,
+            args.palette_offset,
+            params.output,
+            params.max_iter,
+        )?;
+        let encoding = Encoding::from_code(args.encoding, params.output, coloring.is_some())?;
+        let dirty = dirty_rect(args.dirty_x, args.dirty_y, args.dirty_w, args.dirty_h)?;
+        // Either coordinate alone focuses on that row or column's center
+        let focus = (args.focus_x.is_some() || args.focus_y.is_some()).then(|| {
+            (
+                args.focus_x.unwrap_or(width as f64 / 2.0),
+                args.focus_y.unwrap_or(height as f64 / 2.0),
+            )
+        });
+        Ok(Self {
+            params,
+            orbit,
+            coloring,
+            encoding,
+            histogram,
+            summary,
+            dirty,
+            focus,
+        })
+    }
+}
+
+/// Checks an `iteration_budget` argument, which only escape values can
+/// be charged against.
+pub fn check_iteration_budget(params: &RenderParams, budget: Option<u64>) -> PyResult<()> {
+    if budget.is_some() && !params.output.is_escape_value() {
+        return Err(PyValueError::new_err(
+            "iteration_budget needs iteration or smooth output",
+        ));
+    }
+    Ok(())
+}
+
+/// Whether `tile` shares any pixels with `rect`.
+pub fn overlaps(tile: PixelRect, rect: PixelRect) -> bool {
+    let (tx, ty, tw, th) = tile;
+    let (rx, ry, rw, rh) = rect;
+    tx < rx + rw && rx < tx + tw && ty < ry + rh && ry < ty + th
+}
+
+pub fn parse_coordinate(value: &str) -> PyResult<DoubleDouble> {
+    match value.parse::<DoubleDouble>() {
+        Ok(parsed) if parsed.hi.is_finite() => Ok(parsed),
+        Ok(_) => Err(PyValueError::new_err(format!(
+            "coordinate {value:?} is past the range of f64"
+        ))),
+        Err(_) => Err(PyValueError::new_err(format!(
+            "invalid coordinate {value:?}"
+        ))),
+    }
+}
+
+/// Smallest scale that shows the whole default view. Pixels stay square,
+/// so the other axis just shows a bit more of the plane. An empty image
+/// gets the scale of a 1 pixel wide or high one, so it renders no tiles
+/// rather than failing validation.
+pub fn default_scale(width: usize, height: usize) -> f64 {
+    (DEFAULT_VIEW_WIDTH / width.max(1) as f64).max(DEFAULT_VIEW_HEIGHT / height.max(1) as f64)
+}
+
+pub const FRACTAL_MANDELBROT: u8 = 0;
+pub const FRACTAL_JULIA: u8 = 1;
+pub const FRACTAL_BURNING_SHIP: u8 = 2;
+
+#[derive(Debug, Clone, Copy)]
+pub enum FractalKind {
+    Mandelbrot,
+    /// Iterates the pixel coordinate with a fixed constant `c`.
+    Julia {
+        c_re: f64,
+        c_im: f64,
+    },
+    /// Mandelbrot with `|Re z| + i|Im z|` squared each step.
+    BurningShip,
+}
+
+impl FractalKind {
+    pub fn from_code(code: u8, julia_re: f64, julia_im: f64) -> PyResult<Self> {
+        match code {
+            FRACTAL_MANDELBROT => Ok(FractalKind::Mandelbrot),
+            FRACTAL_JULIA => Ok(FractalKind::Julia {
+                c_re: julia_re,
+                c_im: julia_im,
+            }),
+            FRACTAL_BURNING_SHIP => Ok(FractalKind::BurningShip),
+            _ => Err(PyValueError::new_err(format!(
+                "unknown fractal_kind {code}"
+            ))),
+        }
+    }
+}
+
+pub const PRECISION_F64: u8 = 0;
+pub const PRECISION_F32: u8 = 1;
+pub const PRECISION_DOUBLE_DOUBLE: u8 = 2;
+
+/// Float width of the escape iteration. `F32` is faster and only disagrees
+/// with `F64` on chaotic pixels along the set boundary, as long as the scale
+/// stays above `F32_MIN_SCALE` units per pixel; zoom further and the
+/// mismatches spread into visible noise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precision {
+    F64,
+    F32,
+    /// Power 2 Mandelbrot orbits in `DoubleDouble`, see
+    /// `double_double::render`. Needs the `double-double` feature.
+    DoubleDouble,
+}
+
+pub const F32_MIN_SCALE: f64 = 1e-4;
+
+impl Precision {
+    pub fn from_code(code: u8) -> PyResult<Self> {
+        match code {
+            PRECISION_F64 => Ok(Precision::F64),
+            PRECISION_F32 => Ok(Precision::F32),
+            PRECISION_DOUBLE_DOUBLE if cfg!(feature = "double-double") => {
+                Ok(Precision::DoubleDouble)
+            }
+            PRECISION_DOUBLE_DOUBLE => Err(PyValueError::new_err(
+                "double-double precision needs the double-double feature",
+            )),
+            _ => Err(PyValueError::new_err(format!("unknown precision {code}"))),
+        }
+    }
+}
+
+pub const OUTPUT_ITERATIONS: u8 = 0;
+pub const OUTPUT_SMOOTH: u8 = 1;
+pub const OUTPUT_DISTANCE: u8 = 2;
+pub const OUTPUT_TRAP: u8 = 3;
+pub const OUTPUT_SHADED: u8 = 4;
+pub const OUTPUT_DOUBLE_SMOOTH: u8 = 5;
+pub const OUTPUT_ANGLE: u8 = 6;
+pub const OUTPUT_STRIPE: u8 = 7;
+
+/// What each pixel of a tile holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputMode {
+    /// Integer escape iteration
+    Iterations,
+    /// Fractional escape value, see `smooth_escape`
+    Smooth,
+    /// Exterior distance estimate, see `distance_estimate`. Tracks dz/dc
+    /// alongside z, so roughly twice the cost of the other modes.
+    Distance,
+    /// Closest approach of the orbit to `RenderParams::trap`
+    Trap,
+    /// Brightness in `[0, 1]` of the escape-time surface under
+    /// `RenderParams::light`. Tracks dz/dc like `Distance`.
+    Shaded,
+    /// `Smooth` kept as f64 instead of f32, at 8 bytes a pixel instead of
+    /// 4. f32 only has 24 bits for the whole value, so past a few thousand
+    /// iterations neighbouring pixels round to the same fraction.
+    DoubleSmooth,
+    /// Argument of z at escape, see `angle_value`
+    Angle,
+    /// Stripe average of the orbit, see `stripe_value`
+    Stripe,
+}
+
+impl OutputMode {
+    /// `smooth = true` is shorthand for `OUTPUT_SMOOTH`.
+    pub fn from_code(code: u8, smooth: bool) -> PyResult<Self> {
+        match (code, smooth) {
+            (OUTPUT_ITERATIONS, false) => Ok(OutputMode::Iterations),
+            (OUTPUT_ITERATIONS | OUTPUT_SMOOTH, _) => Ok(OutputMode::Smooth),
+            (OUTPUT_DISTANCE, false) => Ok(OutputMode::Distance),
+            (OUTPUT_TRAP, false) => Ok(OutputMode::Trap),
+            (OUTPUT_SHADED, false) => Ok(OutputMode::Shaded),
+            (OUTPUT_DOUBLE_SMOOTH, _) => Ok(OutputMode::DoubleSmooth),
+            (OUTPUT_ANGLE, false) => Ok(OutputMode::Angle),
+            (OUTPUT_STRIPE, false) => Ok(OutputMode::Stripe),
+            (
+                OUTPUT_DISTANCE | OUTPUT_TRAP | OUTPUT_SHADED | OUTPUT_ANGLE | OUTPUT_STRIPE,
+                true,
+            ) => Err(PyValueError::new_err(
+                "smooth only combines with the iteration output mode",
+            )),
+            _ => Err(PyValueError::new_err(format!("unknown output_mode {code}"))),
+        }
+    }
+
+    /// Whether pixels hold an escape iteration, integer or smooth, as
+    /// opposed to a distance.
+    pub fn is_escape_value(self) -> bool {
+        matches!(
+            self,
+            OutputMode::Iterations | OutputMode::Smooth | OutputMode::DoubleSmooth
+        )
+    }
+}
+
+pub const TRAP_POINT: u8 = 0;
+pub const TRAP_HORIZONTAL: u8 = 1;
+pub const TRAP_VERTICAL: u8 = 2;
+
+/// Shape the orbit-trap output measures distance to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OrbitTrap {
+    Point {
+        re: f64,
+        im: f64,
+    },
+    /// The line `Im z = im`; the real axis by default
+    Horizontal {
+        im: f64,
+    },
+    /// The line `Re z = re`; the imaginary axis by default
+    Vertical {
+        re: f64,
+    },
+}
+
+impl OrbitTrap {
+    pub fn from_code(code: u8, trap_re: f64, trap_im: f64) -> PyResult<Self> {
+        match code {
+            TRAP_POINT => Ok(OrbitTrap::Point {
+                re: trap_re,
+                im: trap_im,
+            }),
+            TRAP_HORIZONTAL => Ok(OrbitTrap::Horizontal { im: trap_im }),
+            TRAP_VERTICAL => Ok(OrbitTrap::Vertical { re: trap_re }),
+            _ => Err(PyValueError::new_err(format!("unknown trap {code}"))),
+        }
+    }
+
+    #[inline(always)]
+    pub fn distance(self, z_re: f64, z_im: f64) -> f64 {
+        match self {
+            OrbitTrap::Point { re, im } => ((z_re - re).powi(2) + (z_im - im).powi(2)).sqrt(),
+            OrbitTrap::Horizontal { im } => (z_im - im).abs(),
+            OrbitTrap::Vertical { re } => (z_re - re).abs(),
+        }
+    }
+}
+
+pub const SAMPLES_GRID: u8 = 0;
+pub const SAMPLES_ROTATED_GRID: u8 = 1;
+pub const SAMPLES_HALTON: u8 = 2;
+
+/// Where in the pixel the N x N samples of a supersampled pixel go. Every
+/// pixel uses the same offsets, so renders are reproducible.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SamplePattern {
+    /// Centers of an N x N grid of cells
+    Grid,
+    /// The grid sheared so that each sample has a row and a column of the
+    /// N^2 x N^2 grid to itself, like the usual 2 x 2 rotated grid
+    RotatedGrid,
+    /// Samples 1 to N^2 of the base 2 and 3 Halton sequence, starting at
+    /// (1/2, 1/3), (1/4, 2/3), (3/4, 1/9), (1/8, 4/9)
+    Halton,
+}
+
+impl SamplePattern {
+    pub fn from_code(code: u8) -> PyResult<Self> {
+        match code {
+            SAMPLES_GRID => Ok(SamplePattern::Grid),
+            SAMPLES_ROTATED_GRID => Ok(SamplePattern::RotatedGrid),
+            SAMPLES_HALTON => Ok(SamplePattern::Halton),
+            _ => Err(PyValueError::new_err(format!(
+                "unknown sample pattern {code}"
+            ))),
+        }
+    }
+
+    /// Offset in `[0, 1)` on both axes of sample `(sx, sy)` of `n` per axis.
+    #[inline(always)]
+    pub fn offset(self, sx: u32, sy: u32, n: u32) -> (f64, f64) {
+        match self {
+            SamplePattern::Grid => {
+                let step = 1.0 / n as f64;
+                ((sx as f64 + 0.5) * step, (sy as f64 + 0.5) * step)
+            }
+            SamplePattern::RotatedGrid => {
+                let step = 1.0 / (n * n) as f64;
+                let col = sx * n + sy;
+                let row = (n - 1 - sy) * n + sx;
+                ((col as f64 + 0.5) * step, (row as f64 + 0.5) * step)
+            }
+            SamplePattern::Halton => {
+                // Index 0 would be the pixel corner on both axes
+                let index = sy * n + sx + 1;
+                (radical_inverse(index, 2), radical_inverse(index, 3))
+            }
+        }
+    }
+}
+
+pub const TRAVERSAL_ROW_MAJOR: u8 = 0;
+pub const TRAVERSAL_Z_ORDER: u8 = 1;
+
+/// Order a tile's pixels are computed in. Tiles come out row-major and
+/// with the same values either way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Traversal {
+    RowMajor,
+    /// Along the Morton curve through square blocks of the tile, so pixels
+    /// computed one after another are close on both axes
+    ZOrder,
+}
+
+impl Traversal {
+    pub fn from_code(code: u8) -> PyResult<Self> {
+        match code {
+            TRAVERSAL_ROW_MAJOR => Ok(Traversal::RowMajor),
+            TRAVERSAL_Z_ORDER => Ok(Traversal::ZOrder),
+            _ => Err(PyValueError::new_err(format!("unknown traversal {code}"))),
+        }
+    }
+}
+
+// `index` written in `base` and mirrored about the radix point
+fn radical_inverse(mut index: u32, base: u32) -> f64 {
+    let mut inverse = 0.0;
+    let mut digit_weight = 1.0 / base as f64;
+    while index > 0 {
+        inverse += (index % base) as f64 * digit_weight;
+        index /= base;
+        digit_weight /= base as f64;
+    }
+    inverse
+}
+
+/// Directional light for the shaded output mode, in the plane of the image
+/// and raised above it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Light {
+    // Unit vector the light comes from
+    pub re: f64,
+    pub im: f64,
+    // How far above the image the light sits. Low values exaggerate the
+    // relief; surfaces facing away from the light only go black below 1.
+    pub height: f64,
+}
+
+impl Light {
+    /// `angle` is in degrees, counterclockwise from the positive real axis.
+    pub fn new(angle: f64, height: f64) -> PyResult<Self> {
+        if !angle.is_finite() {
+            return Err(PyValueError::new_err("light_angle must be finite"));
+        }
+        if !(height >= 0.0 && height.is_finite()) {
+            return Err(PyValueError::new_err("light_height must not be negative"));
+        }
+        let (im, re) = angle.to_radians().sin_cos();
+        Ok(Self { re, im, height })
+    }
+
+    /// The light of the `light_angle` and `light_height` defaults.
+    pub fn default_light() -> Self {
+        let (im, re) = 45f64.to_radians().sin_cos();
+        Self {
+            re,
+            im,
+            height: 1.5,
+        }
+    }
+
+    /// Lambertian brightness of the surface at an escaped `z`, whose normal
+    /// points along `z / dz`.
+    #[inline(always)]
+    pub fn shade(self, z_re: f64, z_im: f64, dz_re: f64, dz_im: f64) -> f32 {
+        // z / dz up to a positive factor, i.e. z * conj(dz)
+        let (u_re, u_im) = (z_re * dz_re + z_im * dz_im, z_im * dz_re - z_re * dz_im);
+        let norm = (u_re * u_re + u_im * u_im).sqrt();
+        if !(norm > 0.0 && norm.is_finite()) {
+            return 0.0;
+        }
+
+        let lit = (u_re * self.re + u_im * self.im) / norm;
+        ((lit + self.height) / (1.0 + self.height)).clamp(0.0, 1.0) as f32
+    }
+}
+
+/// Everything about a render that is shared by all of its tiles.
+#[derive(Debug, Clone, Copy)]
+pub struct RenderParams {
+    pub width: usize,
+    pub height: usize,
+    pub max_iter: u32,
+    pub viewport: Viewport,
+    pub kind: FractalKind,
+    pub output: OutputMode,
+    // Anti-aliasing grid per pixel side; above 1 the tile is always `Values`
+    pub samples_per_axis: u32,
+    // Where those samples sit inside the pixel
+    pub sample_pattern: SamplePattern,
+    // F32 only applies to single-sample escape counts of the power 2
+    // Mandelbrot and Julia sets; `validate` rejects it anywhere else
+    pub precision: Precision,
+    // Exponent d in z = z^d + c; anything but 2 takes the generic multibrot path
+    pub power: u32,
+    // Bailout |z| for escape counts. Anything but 2.0 shifts the counts of
+    // pixels near the boundary; smooth and distance output never go below
+    // their own, much larger, radius.
+    pub escape_radius: f64,
+    // Interior pixels hold a value of their final z, see `interior_value`
+    pub interior: bool,
+    // Only read by the trap output mode
+    pub trap: OrbitTrap,
+    // Only read by the shaded output mode
+    pub light: Light,
+    // Only read by the stripe output mode: how many stripes a full turn of
+    // arg z sweeps through
+    pub stripe_freq: f64,
+    // Stop iterating orbits caught in a cycle, see `quadratic_periodic`.
+    // `validate` rejects it outside f64 escape counts of z^2 + c.
+    pub periodicity: bool,
+    // Imaginary part growing up the image, as in math, rather than down it
+    pub flip_y: bool,
+    // With samples_per_axis above 1, only pixels whose single sample differs
+    // from a 4-neighbor's by more than this get the full grid
+    pub aa_threshold: Option<f64>,
+    // Fill rectangles with a uniform border without iterating their inside,
+    // see `guess_tile`. Faster on plain regions, but not exact.
+    pub solid_guess: bool,
+    // Full-precision view center for double-double precision, set from a
+    // deep center by `deep_zoom`; the viewport's f64 center otherwise
+    pub dd_center: Option<(DoubleDouble, DoubleDouble)>,
+    // Iterations between escape tests, see `mandelbrot_coarse`. Above 1,
+    // counts near the boundary come out up to this many too high, and
+    // `validate` rejects it outside f64 Mandelbrot escape counts without
+    // periodicity.
+    pub check_every: u16,
+    // Single samples are taken at pixel centers rather than corners.
+    // Supersamples already spread over the pixel around its center.
+    pub pixel_center: bool,
+    // Only `map_pixels` honors this; solid_guess, supersampled and SIMD
+    // tiles go row-major. It doesn't change any value, so the cache ignores it.
+    pub traversal: Traversal,
+}
+
+/// What rendering a tile took beyond one plain sample per pixel.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TileStats {
+    // Pixels that got more than one sample
+    pub supersampled: usize,
+    // Pixels `guess_tile` filled in without iterating
+    pub guessed: usize,
+}
+
+impl RenderParams {
+    /// One f64 sample per pixel of the power-2 Mandelbrot escape counts,
+    /// with every other option at its default, for the renders that have no
+    /// arguments for them. Still to be checked with `validate`.
+    pub fn plain_mandelbrot(
+        width: usize,
+        height: usize,
+        max_iter: u32,
+        viewport: Viewport,
+    ) -> Self {
+        Self {
+            width,
+            height,
+            max_iter,
+            viewport,
+            kind: FractalKind::Mandelbrot,
+            output: OutputMode::Iterations,
+            precision: Precision::F64,
+            power: 2,
+            samples_per_axis: 1,
+            sample_pattern: SamplePattern::Grid,
+            escape_radius: 2.0,
+            interior: false,
+            trap: OrbitTrap::Point { re: 0.0, im: 0.0 },
+            light: Light::default_light(),
+            stripe_freq: 5.0,
+            periodicity: false,
+            flip_y: false,
+            aa_threshold: None,
+            solid_guess: false,
+            dd_center: None,
+            check_every: 1,
+            pixel_center: false,
+            traversal: Traversal::RowMajor,
+        }
+    }
+
+    /// Complex coordinate of the (possibly fractional) pixel position `(x, y)`.
+    #[inline(always)]
+    pub fn to_complex(&self, x: f64, y: f64) -> (f64, f64) {
+        let (re, im) = self.pixel_offset(x, y);
+        (self.viewport.center_re + re, self.viewport.center_im + im)
+    }
+
+    /// Offset of the pixel position `(x, y)` from the view center.
+    #[inline(always)]
+    pub fn pixel_offset(&self, x: f64, y: f64) -> (f64, f64) {
+        let scale = self.viewport.scale;
+        let half = if self.pixel_center && self.samples_per_axis == 1 {
+            0.5
+        } else {
+            0.0
+        };
+        (
+            (x + half - self.width as f64 / 2.0) * scale,
+            (y + half - self.height as f64 / 2.0) * scale,
+        )
+    }
+
+    /// Position on the plane of image row `y`, which is `y` itself unless
+    /// `flip_y` swaps the rows top to bottom. Flipping whole rows, before
+    /// any supersampling offsets, keeps the flipped image an exact mirror.
+    #[inline(always)]
+    pub fn row_position(&self, y: usize) -> f64 {
+        if self.flip_y {
+            (self.height - 1 - y) as f64
+        } else {
+            y as f64
+        }
+    }
+
+    /// The image row whose pixels are the complex conjugates of row `y`'s.
+    /// The Mandelbrot set gives conjugates the very same escape values, bit
+    /// for bit, so that row can be copied instead of rendered. `None` unless
+    /// the view is a single-sample f64 or f32 escape-value render of the
+    /// Mandelbrot set centered on the real axis, or if the mirrored row
+    /// falls outside the image.
+    pub fn mirror_row(&self, y: usize) -> Option<usize> {
+        if !matches!(self.kind, FractalKind::Mandelbrot)
+            || self.viewport.center_im != 0.0
+            || self.precision == Precision::DoubleDouble
+            || self.samples_per_axis > 1
+            || !self.output.is_escape_value()
+        {
+            return None;
+        }
+        // Positions p and h - p, or h - 1 - p at pixel centers, are the
+        // same distance either side of the axis
+        let (height, position) = (self.height, self.row_position(y) as usize);
+        let mirrored = height - position - usize::from(self.pixel_center);
+        if mirrored >= height {
+            return None;
+        }
+        Some(if self.flip_y {
+            height - 1 - mirrored
+        } else {
+            mirrored
+        })
+    }
+
+    // Whether pixels are single-sample escape counts of z^2 + c, the one
+    // path that f32 precision, periodicity and check_every change
+    fn quadratic_counts(&self) -> bool {
+        matches!(
+            self.kind,
+            FractalKind::Mandelbrot | FractalKind::Julia { .. }
+        ) && self.power == 2
+            && self.output == OutputMode::Iterations
+            && self.samples_per_axis == 1
+            && !self.interior
+    }
+
+    pub fn escape_radius_sq(&self) -> f64 {
+        self.escape_radius * self.escape_radius
+    }
+
+    /// Bailout for the smooth and distance formulas, which need a large one.
+    pub fn smooth_radius_sq(&self) -> f64 {
+        self.escape_radius_sq().max(SMOOTH_ESCAPE_RADIUS_SQ)
+    }
+
+    /// Rejects parameter combinations that would render garbage.
+    pub fn validate(&self) -> PyResult<()> {
+        check_max_iter(self.max_iter)?;
+        let viewport = &self.viewport;
+        if !(viewport.scale > 0.0 && viewport.scale.is_finite()) {
+            return Err(PyValueError::new_err("scale must be positive"));
+        }
+        if !(viewport.center_re.is_finite() && viewport.center_im.is_finite()) {
+            return Err(PyValueError::new_err(
+                "center_re and center_im must be finite",
+            ));
+        }
+        // Every pixel lies between the corners, so they bound the coordinates
+        let corners = [
+            self.to_complex(0.0, 0.0),
+            self.to_complex(self.width as f64, self.height as f64),
+        ];
+        if corners
+            .iter()
+            .any(|(re, im)| !(re.is_finite() && im.is_finite()))
+        {
+            return Err(PyValueError::new_err(
+                "the view reaches past the range of f64",
+            ));
+        }
+        if let FractalKind::Julia { c_re, c_im } = self.kind
+            && !(c_re.is_finite() && c_im.is_finite())
+        {
+            return Err(PyValueError::new_err(
+                "julia_re and julia_im must be finite",
+            ));
+        }
+        if !(self.escape_radius > 0.0 && self.escape_radius.is_finite()) {
+            return Err(PyValueError::new_err("escape_radius must be positive"));
+        }
+        if self.samples_per_axis == 0 {
+            return Err(PyValueError::new_err("samples_per_axis must be at least 1"));
+        }
+        if self.power < 2 {
+            return Err(PyValueError::new_err("power must be at least 2"));
+        }
+        if self.power != 2 && matches!(self.kind, FractalKind::BurningShip) {
+            return Err(PyValueError::new_err("Burning Ship only supports power 2"));
+        }
+        if self.interior && (!self.output.is_escape_value() || self.samples_per_axis > 1) {
+            return Err(PyValueError::new_err(
+                "interior coloring needs a single sample of iteration or smooth output",
+            ));
+        }
+        if matches!(self.output, OutputMode::Distance | OutputMode::Shaded)
+            && (self.power != 2 || matches!(self.kind, FractalKind::BurningShip))
+        {
+            return Err(PyValueError::new_err(
+                "distance estimation and shading need Mandelbrot or Julia with power 2",
+            ));
+        }
+        if !self.stripe_freq.is_finite() {
+            return Err(PyValueError::new_err("stripe_freq must be finite"));
+        }
+        // Angles either side of the negative real axis would average to 0
+        if self.output == OutputMode::Angle && self.samples_per_axis > 1 {
+            return Err(PyValueError::new_err(
+                "angle output needs samples_per_axis of 1",
+            ));
+        }
+        if let Some(threshold) = self.aa_threshold {
+            if !(threshold >= 0.0 && threshold.is_finite()) {
+                return Err(PyValueError::new_err("aa_threshold must not be negative"));
+            }
+            if self.samples_per_axis < 2 {
+                return Err(PyValueError::new_err(
+                    "aa_threshold needs samples_per_axis of at least 2",
+                ));
+            }
+        }
+        if self.precision == Precision::DoubleDouble
+            && (!matches!(self.kind, FractalKind::Mandelbrot)
+                || self.power != 2
+                || !self.output.is_escape_value()
+                || self.interior)
+        {
+            return Err(PyValueError::new_err(
+                "double-double precision only supports escape values of the power 2 Mandelbrot set",
+            ));
+        }
+        if self.precision == Precision::F32 && !self.quadratic_counts() {
+            return Err(PyValueError::new_err(
+                "f32 precision only supports single-sample escape counts of the power 2 \
+                 Mandelbrot or Julia set",
+            ));
+        }
+        if self.periodicity && (self.precision != Precision::F64 || !self.quadratic_counts()) {
+            return Err(PyValueError::new_err(
+                "periodicity only applies to single-sample f64 escape counts of the power 2 \
+                 Mandelbrot or Julia set",
+            ));
+        }
+        if self.check_every == 0 {
+            return Err(PyValueError::new_err("check_every must be at least 1"));
+        }
+        if self.check_every > 1
+            && (!matches!(self.kind, FractalKind::Mandelbrot)
+                || self.precision != Precision::F64
+                || self.periodicity
+                || !self.quadratic_counts())
+        {
+            return Err(PyValueError::new_err(
+                "check_every above 1 only applies to single-sample f64 escape counts of the \
+                 power 2 Mandelbrot set, without periodicity",
+            ));
+        }
+        if self.solid_guess
+            && (self.output != OutputMode::Iterations || self.samples_per_axis > 1 || self.interior)
+        {
+            return Err(PyValueError::new_err(
+                "solid_guess needs a single sample of iteration output",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// When a tile render stops early: at a deadline, once a cancel flag is
+/// raised, or at whichever comes first. It's checked before every row,
+/// with a relaxed load of the flag, so checking costs next to nothing.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Cutoff<'a> {
+    pub deadline: Option<Instant>,
+    pub cancel: Option<&'a AtomicBool>,
+}
+
+impl Cutoff<'_> {
+    pub fn is_set(&self) -> bool {
+        self.deadline.is_some() || self.cancel.is_some()
+    }
+
+    #[inline(always)]
+    pub fn reached(&self) -> bool {
+        self.cancel
+            .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+            || self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+}
+
+/// Writes the escape counts of a tile straight into a framebuffer: pixel
+/// `(dx, dy)` of the tile lands at `buf[offset + dy * stride + dx]`, so no
+/// tile buffer is copied in between. Edge tiles write only the pixels inside
+/// the image. Needs params that pass `check_tile_into`, and `buf` large
+/// enough, see `tile_into_len`.
+pub fn render_tile_into(
+    params: &RenderParams,
+    tile: (usize, usize, usize, usize),
+    buf: &mut [u16],
+    offset: usize,
+    stride: usize,
+) {
+    write_tile(params, tile, offset, stride, |i, count| buf[i] = count);
+}
+
+/// `render_tile_into` for any writer, handed each index and count.
+pub(crate) fn write_tile(
+    params: &RenderParams,
+    (tile_x, tile_y, tile_w, tile_h): (usize, usize, usize, usize),
+    offset: usize,
+    stride: usize,
+    mut put: impl FnMut(usize, u16),
+) {
+    let cols = tile_w.min(params.width.saturating_sub(tile_x));
+    let rows = tile_h.min(params.height.saturating_sub(tile_y));
+    for dy in 0..rows {
+        let y = params.row_position(tile_y + dy);
+        for dx in 0..cols {
+            let (re, im) = params.to_complex((tile_x + dx) as f64, y);
+            put(
+                offset + dy * stride + dx,
+                escape_count(params, re, im) as u16,
+            );
+        }
+    }
+}
+
+/// Length a framebuffer needs for `render_tile_into` to write the tile at
+/// `offset` and `stride`, or `None` if its rows would overlap.
+pub fn tile_into_len(
+    params: &RenderParams,
+    (tile_x, tile_y, tile_w, tile_h): (usize, usize, usize, usize),
+    offset: usize,
+    stride: usize,
+) -> Option<usize> {
+    let cols = tile_w.min(params.width.saturating_sub(tile_x));
+    let rows = tile_h.min(params.height.saturating_sub(tile_y));
+    if rows == 0 || cols == 0 {
+        return Some(0);
+    }
+    (stride >= cols).then(|| offset + (rows - 1) * stride + cols)
+}
+
+/// Errors unless `render_tile_into` gives the counts `render_tile` would:
+/// single-sample escape counts that fit in a u16.
+pub fn check_tile_into(params: &RenderParams) -> PyResult<()> {
+    if params.output != OutputMode::Iterations
+        || params.samples_per_axis > 1
+        || params.interior
+        || params.precision == Precision::DoubleDouble
+    {
+        return Err(PyValueError::new_err(
+            "rendering into a buffer needs a single sample of iteration output",
+        ));
+    }
+    if params.max_iter > u16::MAX as u32 {
+        return Err(PyValueError::new_err(
+            "rendering into a buffer needs max_iter of at most 65535",
+        ));
+    }
+    Ok(())
+}
+
+/// Renders a tile into `out`, replacing its contents. Reusing one `out`
+/// per thread saves an allocation per tile; edge tiles just come out short.
 pub fn render_tile(
+    params: &RenderParams,
+    tile_x: usize,
+    tile_y: usize,
+    tile_w: usize,
+    tile_h: usize,
+    out: &mut TileData,
+) -> TileStats {
+    let cutoff = Cutoff::default();
+    render(params, None, tile_x, tile_y, tile_w, tile_h, 1, cutoff, out)
+}
+
+/// Like `render_tile`, but stops at the first row that starts once
+/// `cutoff` is reached, so `out` may be left short.
+pub fn render_tile_until(
+    params: &RenderParams,
+    tile_x: usize,
+    tile_y: usize,
+    tile_w: usize,
+    tile_h: usize,
+    cutoff: Cutoff<'_>,
+    out: &mut TileData,
+) -> TileStats {
+    render(params, None, tile_x, tile_y, tile_w, tile_h, 1, cutoff, out)
+}
+
+/// Like `render_tile`, but iterates each pixel as an f64 offset from
+/// `orbit`, which stays accurate far beyond the zoom where `to_complex`
+/// runs out of bits. The viewport center is ignored in favor of the orbit's.
+pub fn render_tile_perturbed(
+    params: &RenderParams,
+    orbit: &ReferenceOrbit,
+    tile_x: usize,
+    tile_y: usize,
+    tile_w: usize,
+    tile_h: usize,
+    out: &mut TileData,
+) -> TileStats {
+    render(
+        params,
+        Some(orbit),
+        tile_x,
+        tile_y,
+        tile_w,
+        tile_h,
+        1,
+        Cutoff::default(),
+        out,
+    )
+}
+
+/// `render_tile_perturbed` with the cutoff of `render_tile_until`.
+#[allow(clippy::too_many_arguments)]
+pub fn render_tile_perturbed_until(
+    params: &RenderParams,
+    orbit: &ReferenceOrbit,
+    tile_x: usize,
+    tile_y: usize,
+    tile_w: usize,
+    tile_h: usize,
+    cutoff: Cutoff<'_>,
+    out: &mut TileData,
+) -> TileStats {
+    render(
+        params,
+        Some(orbit),
+        tile_x,
+        tile_y,
+        tile_w,
+        tile_h,
+        1,
+        cutoff,
+        out,
+    )
+}
+
+/// Like `render_tile`, or `render_tile_perturbed` with an `orbit`, but only
+/// renders every `stride`-th pixel of every `stride`-th row, for a quick
+/// preview of the tile that is `preview_dims` in size.
+#[allow(clippy::too_many_arguments)]
+pub fn render_tile_preview(
+    params: &RenderParams,
+    orbit: Option<&ReferenceOrbit>,
+    tile_x: usize,
+    tile_y: usize,
+    tile_w: usize,
+    tile_h: usize,
+    stride: usize,
+    out: &mut TileData,
+) -> TileStats {
+    let cutoff = Cutoff::default();
+    render(
+        params, orbit, tile_x, tile_y, tile_w, tile_h, stride, cutoff, out,
+    )
+}
+
+/// Escape counts of the tile as `render_tile` would give them with only
+/// `coarse_iter` iterations, except that pixels still bounded by then come
+/// out as `max_iter`. Each pixel's orbit is left in `state` for
+/// `resume_tile` to carry on from.
+#[allow(clippy::too_many_arguments)]
+pub fn render_tile_coarse(
+    params: &RenderParams,
+    tile_x: usize,
+    tile_y: usize,
+    tile_w: usize,
+    tile_h: usize,
+    coarse_iter: u32,
+    state: &mut Vec<OrbitState>,
+    out: &mut TileData,
+) {
+    let tile = (tile_x, tile_y, tile_w, tile_h);
+    state.clear();
+    map_pixels(params, tile, 1, Cutoff::default(), state, |x, y| {
+        let (re, im) = params.to_complex(x, y);
+        let mut orbit = OrbitState::start(params, re, im);
+        orbit.advance(params, re, im, coarse_iter);
+        orbit
+    });
+    let max_iter = params.max_iter;
+    let out = out.iterations_mut();
+    out.extend(state.iter().map(|orbit| orbit.count(max_iter)));
+}
+
+/// Carries the orbits a `render_tile_coarse` of the same tile left in
+/// `state` on to `max_iter`, for the counts `render_tile` would give.
+/// Stops at the first row that starts once `cutoff` is reached.
+#[allow(clippy::too_many_arguments)]
+pub fn resume_tile(
+    params: &RenderParams,
+    tile_x: usize,
+    tile_y: usize,
+    tile_w: usize,
+    tile_h: usize,
+    state: &mut [OrbitState],
+    cutoff: Cutoff<'_>,
+    out: &mut TileData,
+) -> TileStats {
+    let max_iter = params.max_iter;
+    let (cols, _) = preview_dims(params, (tile_x, tile_y, tile_w, tile_h), 1);
+    let out = out.iterations_mut();
+    out.reserve(state.len());
+    for (dy, row) in state.chunks_mut(cols.max(1)).enumerate() {
+        if cutoff.reached() {
+            break;
+        }
+        let y = params.row_position(tile_y + dy);
+        for (dx, orbit) in row.iter_mut().enumerate() {
+            let (re, im) = params.to_complex((tile_x + dx) as f64, y);
+            orbit.advance(params, re, im, max_iter);
+            out.push(orbit.count(max_iter));
+        }
+    }
+    TileStats::default()
+}
+
+/// Where a pixel's orbit stood when a coarse pass stopped iterating it.
+#[derive(Debug, Clone, Copy)]
+pub struct OrbitState {
+    z_re: f64,
+    z_im: f64,
+    // Iterations done so far, or the escape count once settled
+    iter: u32,
+    // Escaped, or known never to
+    settled: bool,
+}
+
+impl OrbitState {
+    fn start(params: &RenderParams, re: f64, im: f64) -> Self {
+        let (z_re, z_im) = match params.kind {
+            FractalKind::Julia { .. } => (re, im),
+            _ => (0.0, 0.0),
+        };
+        // The same shortcut as `mandelbrot`, so the counts agree
+        let settled = matches!(params.kind, FractalKind::Mandelbrot)
+            && params.power == 2
+            && params.escape_radius_sq() >= 4.0
+            && in_main_body(re, im);
+        let iter = if settled { params.max_iter } else { 0 };
+        Self {
+            z_re,
+            z_im,
+            iter,
+            settled,
+        }
+    }
+
+    // Iterates the orbit of the pixel at `(re, im)` on to `until` iterations
+    #[inline(always)]
+    fn advance(&mut self, params: &RenderParams, re: f64, im: f64, until: u32) {
+        if self.settled || self.iter >= until {
+            return;
+        }
+        let (c_re, c_im) = match params.kind {
+            FractalKind::Julia { c_re, c_im } => (c_re, c_im),
+            _ => (re, im),
+        };
+        let fold_abs = matches!(params.kind, FractalKind::BurningShip);
+
+        let (i, z_re, z_im) = orbit_end(
+            self.z_re,
+            self.z_im,
+            c_re,
+            c_im,
+            until - self.iter,
+            params.power,
+            fold_abs,
+            params.escape_radius_sq(),
+        );
+        self.settled = i < until - self.iter;
+        (self.z_re, self.z_im, self.iter) = (z_re, z_im, self.iter + i);
+    }
+
+    fn count(&self, max_iter: u32) -> u32 {
+        if self.settled { self.iter } else { max_iter }
+    }
+}
+
+/// Errors on a `max_iter` of 0, with which every pixel would come out 0
+/// and the smooth and distance math would divide by it.
+pub fn check_max_iter(max_iter: u32) -> PyResult<()> {
+    if max_iter == 0 {
+        return Err(PyValueError::new_err("max_iter must be positive"));
+    }
+    Ok(())
+}
+
+/// Checks a `coarse_iter` argument against what `render_tile_coarse` can
+/// resume: plain f64 escape counts, one sample a pixel.
+pub fn check_coarse_iter(
+    params: &RenderParams,
+    coarse_iter: Option<u32>,
+    deep_zoom: bool,
+) -> PyResult<()> {
+    let Some(coarse_iter) = coarse_iter else {
+        return Ok(());
+    };
+    if coarse_iter == 0 || coarse_iter >= params.max_iter {
+        return Err(PyValueError::new_err(
+            "coarse_iter must be between 1 and max_iter - 1",
+        ));
+    }
+    if params.output != OutputMode::Iterations
+        || params.precision != Precision::F64
+        || params.samples_per_axis > 1
+        || params.interior
+        || params.periodicity
+        || params.check_every > 1
+        || params.solid_guess
+        || deep_zoom
+    {
+        return Err(PyValueError::new_err(
+            "coarse_iter needs single-sample f64 iteration output, without deep zoom, \
+             interior, periodicity, check_every or solid_guess",
+        ));
+    }
+    Ok(())
+}
+
+/// `(cols, rows)` of a `render_tile_preview` of the tile, once clipped to
+/// the image.
+pub fn preview_dims(
+    params: &RenderParams,
+    (tile_x, tile_y, tile_w, tile_h): (usize, usize, usize, usize),
+    stride: usize,
+) -> (usize, usize) {
+    let cols = tile_w.min(params.width.saturating_sub(tile_x));
+    let rows = tile_h.min(params.height.saturating_sub(tile_y));
+    (cols.div_ceil(stride), rows.div_ceil(stride))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn render(
+    params: &RenderParams,
+    orbit: Option<&ReferenceOrbit>,
+    tile_x: usize,
+    tile_y: usize,
+    tile_w: usize,
+    tile_h: usize,
+    stride: usize,
+    cutoff: Cutoff<'_>,
+    out: &mut TileData,
+) -> TileStats {
+    let tile = (tile_x, tile_y, tile_w, tile_h);
+
+    if let Some(orbit) = orbit {
+        return render_perturbed(params, orbit, tile, stride, cutoff, out);
+    }
+
+    #[cfg(feature = "double-double")]
+    if params.precision == Precision::DoubleDouble {
+        return crate::double_double::render(params, tile, stride, cutoff, out);
+    }
+
+    if params.samples_per_axis > 1 {
+        let value_at = |x, y| {
+            let (re, im) = params.to_complex(x, y);
+            float_value(params, re, im)
+        };
+        return supersample_tile(params, tile, stride, cutoff, out, value_at);
+    }
+
+    if params.interior {
+        map_values(params, tile, stride, cutoff, out, |x, y| {
+            let (re, im) = params.to_complex(x, y);
+            interior_value(params, re, im)
+        });
+        return TileStats::default();
+    }
+
+    // Previews are strided, so their neighbors aren't adjacent pixels
+    if params.solid_guess && stride == 1 {
+        let guessed = guess_tile(params, tile, cutoff, out.iterations_mut(), |x, y| {
+            let (re, im) = params.to_complex(x, y);
+            escape_count(params, re, im)
+        });
+        return TileStats {
+            guessed,
+            ..TileStats::default()
+        };
+    }
+
+    #[cfg(feature = "simd")]
+    if params.output == OutputMode::Iterations
+        && stride == 1
+        && !params.periodicity
+        && params.power == 2
+        && matches!(params.kind, FractalKind::Mandelbrot)
+        && params.precision == Precision::F64
+        && params.check_every == 1
+    {
+        crate::simd::mandelbrot_tile(params, tile, cutoff, out.iterations_mut());
+        return TileStats::default();
+    }
+
+    match params.output {
+        OutputMode::Iterations => map_pixels(
+            params,
+            tile,
+            stride,
+            cutoff,
+            out.iterations_mut(),
+            |x, y| {
+                let (re, im) = params.to_complex(x, y);
+                escape_count(params, re, im)
+            },
+        ),
+        _ => map_values(params, tile, stride, cutoff, out, |x, y| {
+            let (re, im) = params.to_complex(x, y);
+            float_value(params, re, im)
+        }),
+    }
+    TileStats::default()
+}
+
+/// Average of N x N `value_at` samples inside the pixel at `(x, y)`, placed
+/// by `sample_pattern`. Escape counts are averaged in their smooth form.
+fn supersample(params: &RenderParams, x: f64, y: f64, value_at: impl Fn(f64, f64) -> f64) -> f64 {
+    let n = params.samples_per_axis;
+
+    let mut sum = 0.0;
+    for sy in 0..n {
+        for sx in 0..n {
+            let (dx, dy) = params.sample_pattern.offset(sx, sy, n);
+            let value = value_at(x + dx, y + dy);
+            sum += stored_precision(params, value);
+        }
+    }
+
+    sum / (n * n) as f64
+}
+
+/// Supersamples every pixel of the tile, or with `aa_threshold` just the
+/// ones `adaptive_supersample` picks.
+pub(crate) fn supersample_tile(
+    params: &RenderParams,
+    tile: (usize, usize, usize, usize),
+    stride: usize,
+    cutoff: Cutoff<'_>,
+    out: &mut TileData,
+    value_at: impl Fn(f64, f64) -> f64,
+) -> TileStats {
+    let supersampled = match params.aa_threshold {
+        Some(threshold) => {
+            adaptive_supersample(params, tile, stride, cutoff, threshold, out, value_at)
+        }
+        None => {
+            map_values(params, tile, stride, cutoff, out, |x, y| {
+                supersample(params, x, y, &value_at)
+            });
+            out.len()
+        }
+    };
+    TileStats {
+        supersampled,
+        ..TileStats::default()
+    }
+}
+
+/// Two passes over the tile: one sample in the middle of every pixel, then
+/// the full grid for just the pixels whose sample differs from a
+/// 4-neighbor's by more than `threshold`, which are the ones by an edge.
+/// Neighbors just outside the tile are sampled too, so tile seams don't
+/// hide edges. Returns how many pixels got the grid.
+fn adaptive_supersample(
+    params: &RenderParams,
+    tile: (usize, usize, usize, usize),
+    stride: usize,
+    cutoff: Cutoff<'_>,
+    threshold: f64,
+    out: &mut TileData,
+    value_at: impl Fn(f64, f64) -> f64,
+) -> usize {
+    let (tile_x, tile_y, _, _) = tile;
+    let center = |x: f64, y: f64| stored_precision(params, value_at(x + 0.5, y + 0.5));
+
+    let mut coarse = Vec::new();
+    map_pixels(params, tile, stride, cutoff, &mut coarse, center);
+    let (cols, _) = preview_dims(params, tile, stride);
+    let rows = coarse.len().checked_div(cols).unwrap_or(0);
+
+    // The first-pass value of the pixel `(dx, dy)` grid steps from `(col, row)`
+    let neighbor = |col: usize, row: usize, dx: isize, dy: isize| {
+        let col = col.checked_add_signed(dx)?;
+        let row = row.checked_add_signed(dy)?;
+        let (x, y) = (tile_x + col * stride, tile_y + row * stride);
+        if x >= params.width || y >= params.height {
+            return None;
+        }
+        if col < cols && row < rows {
+            return Some(coarse[row * cols + col]);
+        }
+        Some(center(x as f64, params.row_position(y)))
+    };
+
+    let mut values = Vec::with_capacity(coarse.len());
+    let mut supersampled = 0;
+    for (i, &value) in coarse.iter().enumerate() {
+        let (col, row) = (i % cols, i / cols);
+        let edge = [(-1, 0), (1, 0), (0, -1), (0, 1)]
+            .into_iter()
+            .filter_map(|(dx, dy)| neighbor(col, row, dx, dy))
+            .any(|other| (other - value).abs() > threshold);
+        if edge {
+            let (x, y) = (tile_x + col * stride, tile_y + row * stride);
+            values.push(supersample(
+                params,
+                x as f64,
+                params.row_position(y),
+                &value_at,
+            ));
+            supersampled += 1;
+        } else {
+            values.push(value);
+        }
+    }
+
+    if params.output == OutputMode::DoubleSmooth {
+        out.doubles_mut().extend(values);
+    } else {
+        out.values_mut()
+            .extend(values.into_iter().map(|value| value as f32));
+    }
+    supersampled
+}
+
+fn render_perturbed(
+    params: &RenderParams,
+    orbit: &ReferenceOrbit,
+    tile: (usize, usize, usize, usize),
+    stride: usize,
+    cutoff: Cutoff<'_>,
+    out: &mut TileData,
+) -> TileStats {
+    let max_iter = params.max_iter;
+    let smooth_radius_sq = params.smooth_radius_sq();
+
+    if params.samples_per_axis > 1 {
+        let value_at = |x, y| {
+            let (dc_re, dc_im) = params.pixel_offset(x, y);
+            orbit.smooth_escape(dc_re, dc_im, max_iter, smooth_radius_sq)
+        };
+        return supersample_tile(params, tile, stride, cutoff, out, value_at);
+    }
+
+    if params.solid_guess && stride == 1 {
+        let guessed = guess_tile(params, tile, cutoff, out.iterations_mut(), |x, y| {
+            let (dc_re, dc_im) = params.pixel_offset(x, y);
+            orbit.escape_count(dc_re, dc_im, max_iter, params.escape_radius_sq())
+        });
+        return TileStats {
+            guessed,
+            ..TileStats::default()
+        };
+    }
+
+    match params.output {
+        OutputMode::Iterations => map_pixels(
+            params,
+            tile,
+            stride,
+            cutoff,
+            out.iterations_mut(),
+            |x, y| {
+                let (dc_re, dc_im) = params.pixel_offset(x, y);
+                orbit.escape_count(dc_re, dc_im, max_iter, params.escape_radius_sq())
+            },
+        ),
+        // Distance outputs are rejected by `ReferenceOrbit::new`
+        _ => map_values(params, tile, stride, cutoff, out, |x, y| {
+            let (dc_re, dc_im) = params.pixel_offset(x, y);
+            orbit.smooth_escape(dc_re, dc_im, max_iter, smooth_radius_sq)
+        }),
+    }
+    TileStats::default()
+}
+
+/// Escape counts of the tile like `map_pixels`, but by Mariani-Silver
+/// subdivision: a rectangle whose whole border shares one count gets it
+/// inside too, without iterating, and any other rectangle is halved until
+/// it is small enough to just iterate. Sound for the set itself, which is
+/// connected, but a filament thinner than a pixel can cross a rectangle
+/// without touching its border and get painted over. With a cutoff the
+/// tile goes in bands of rows, checked between bands. Returns how many
+/// pixels were filled in.
+pub(crate) fn guess_tile(
+    params: &RenderParams,
+    (tile_x, tile_y, tile_w, tile_h): (usize, usize, usize, usize),
+    cutoff: Cutoff<'_>,
+    out: &mut Vec<u32>,
+    count: impl Fn(f64, f64) -> u32,
+) -> usize {
+    let (cols, rows) = clipped_size(params.width, params.height, tile_x, tile_y, tile_w, tile_h);
+    let band_rows = if cutoff.is_set() {
+        GUESS_BAND_ROWS
+    } else {
+        rows.max(1)
+    };
+    out.reserve(cols * rows);
+
+    let mut guessed = 0;
+    for band_y in (0..rows).step_by(band_rows) {
+        if cutoff.reached() {
+            break;
+        }
+
+        let h = band_rows.min(rows - band_y);
+        let mut band = GuessBand {
+            cols,
+            counts: vec![None; cols * h],
+            count: |col: usize, row: usize| {
+                let y = params.row_position(tile_y + band_y + row);
+                count((tile_x + col) as f64, y)
+            },
+            guessed: 0,
+        };
+        band.fill(0, 0, cols, h);
+        guessed += band.guessed;
+        out.extend(
+            band.counts
+                .into_iter()
+                .map(|count| count.expect("every pixel is iterated or filled")),
+        );
+    }
+    guessed
+}
+
+// Rows `guess_tile` renders between cutoff checks
+const GUESS_BAND_ROWS: usize = 16;
+// Rectangles this narrow or short are iterated pixel by pixel
+const GUESS_MIN_SIDE: usize = 4;
+
+// Counts of one band of a `guess_tile`, iterated as they're first needed
+struct GuessBand<F> {
+    cols: usize,
+    counts: Vec<Option<u32>>,
+    count: F,
+    guessed: usize,
+}
+
+impl<F: Fn(usize, usize) -> u32> GuessBand<F> {
+    fn at(&mut self, col: usize, row: usize) -> u32 {
+        *self.counts[row * self.cols + col].get_or_insert_with(|| (self.count)(col, row))
+    }
+
+    fn fill(&mut self, x: usize, y: usize, w: usize, h: usize) {
+        if w <= GUESS_MIN_SIDE || h <= GUESS_MIN_SIDE {
+            for row in y..y + h {
+                for col in x..x + w {
+                    self.at(col, row);
+                }
+            }
+            return;
+        }
+
+        let first = self.at(x, y);
+        let mut uniform = true;
+        for col in x..x + w {
+            uniform &= self.at(col, y) == first;
+            uniform &= self.at(col, y + h - 1) == first;
+        }
+        for row in y + 1..y + h - 1 {
+            uniform &= self.at(x, row) == first;
+            uniform &= self.at(x + w - 1, row) == first;
+        }
+
+        if uniform {
+            for row in y + 1..y + h - 1 {
+                let start = row * self.cols + x + 1;
+                self.counts[start..start + w - 2].fill(Some(first));
+            }
+            self.guessed += (w - 2) * (h - 2);
+        } else if w >= h {
+            self.fill(x, y, w / 2, h);
+            self.fill(x + w / 2, y, w - w / 2, h);
+        } else {
+            self.fill(x, y, w, h / 2);
+            self.fill(x, y + h / 2, w, h - h / 2);
+        }
+    }
+}
+
+/// Reminds the caller that `solid_guess` trades exactness for speed.
+pub fn warn_solid_guess(py: Python<'_>) -> PyResult<()> {
+    PyErr::warn(
+        py,
+        &py.get_type::<PyUserWarning>(),
+        c"solid_guess can paint over filaments thinner than a pixel",
+        1,
+    )
+}
+
+/// The value a pixel gets in the `Values` or `Doubles` payload.
+#[inline(always)]
+fn float_value(params: &RenderParams, re: f64, im: f64) -> f64 {
+    match params.output {
+        OutputMode::Iterations | OutputMode::Smooth | OutputMode::DoubleSmooth => {
+            smooth_value(params, re, im)
+        }
+        OutputMode::Distance => distance_value(params, re, im) as f64,
+        OutputMode::Trap => trap_value(params, re, im) as f64,
+        OutputMode::Shaded => shaded_value(params, re, im) as f64,
+        OutputMode::Angle => angle_value(params, re, im) as f64,
+        OutputMode::Stripe => stripe_value(params, re, im) as f64,
+    }
+}
+
+/// Smallest `params.trap` distance over `z_1 .. z_n`, where the orbit stops
+/// at escape or at `max_iter`. Interior pixels get a distance too.
+pub fn trap_value(params: &RenderParams, re: f64, im: f64) -> f32 {
+    let (mut z_re, mut z_im, c_re, c_im) = match params.kind {
+        FractalKind::Julia { c_re, c_im } => (re, im, c_re, c_im),
+        _ => (0.0, 0.0, re, im),
+    };
+    let radius_sq = params.escape_radius_sq();
+    let fold_abs = matches!(params.kind, FractalKind::BurningShip);
+
+    let mut closest = f64::INFINITY;
+    for _ in 0..params.max_iter {
+        if z_re * z_re + z_im * z_im > radius_sq {
+            break;
+        }
+
+        (z_re, z_im) = step(z_re, z_im, c_re, c_im, params.power, fold_abs);
+        closest = closest.min(params.trap.distance(z_re, z_im));
+    }
+
+    closest as f32
+}
+
+/// `atan2(Im z, Re z)` of the first z past the escape radius, in `[-pi,
+/// pi]`, for banding by which way orbits leave. NaN for points that never
+/// escape.
+pub fn angle_value(params: &RenderParams, re: f64, im: f64) -> f32 {
+    let (z_re, z_im, c_re, c_im) = match params.kind {
+        FractalKind::Julia { c_re, c_im } => (re, im, c_re, c_im),
+        _ => (0.0, 0.0, re, im),
+    };
+    if matches!(params.kind, FractalKind::Mandelbrot)
+        && params.power == 2
+        && params.escape_radius >= 2.0
+        && in_main_body(c_re, c_im)
+    {
+        return f32::NAN;
+    }
+    let fold_abs = matches!(params.kind, FractalKind::BurningShip);
+
+    let (i, z_re, z_im) = orbit_end(
+        z_re,
+        z_im,
+        c_re,
+        c_im,
+        params.max_iter,
+        params.power,
+        fold_abs,
+        params.escape_radius_sq(),
+    );
+    if i == params.max_iter {
+        f32::NAN
+    } else {
+        z_im.atan2(z_re) as f32
+    }
+}
+
+/// Last z of every pixel in the first `rows` rows of the tile, for
+/// `final_z`: `(z_re, z_im)` pairs as f32, interleaved in pixel order, and
+/// as their bytes with `contiguous`. Escaped points hold their first z past
+/// the escape radius of the output mode, the others their z after
+/// `max_iter` iterations.
+pub fn final_z_tile(
+    params: &RenderParams,
+    (tile_x, tile_y, tile_w, _): (usize, usize, usize, usize),
+    rows: usize,
+    contiguous: bool,
+) -> TileData {
+    let radius_sq = if matches!(params.output, OutputMode::Smooth | OutputMode::DoubleSmooth) {
+        params.smooth_radius_sq()
+    } else {
+        params.escape_radius_sq()
+    };
+    let fold_abs = matches!(params.kind, FractalKind::BurningShip);
+    let mut pairs = Vec::new();
+    let tile = (tile_x, tile_y, tile_w, rows);
+    map_pixels(params, tile, 1, Cutoff::default(), &mut pairs, |x, y| {
+        let (re, im) = params.to_complex(x, y);
+        let (z_re, z_im, c_re, c_im) = match params.kind {
+            FractalKind::Julia { c_re, c_im } => (re, im, c_re, c_im),
+            _ => (0.0, 0.0, re, im),
+        };
+        let (_, z_re, z_im) = orbit_end(
+            z_re,
+            z_im,
+            c_re,
+            c_im,
+            params.max_iter,
+            params.power,
+            fold_abs,
+            radius_sq,
+        );
+        [z_re as f32, z_im as f32]
+    });
+    let z = TileData::Values(pairs.into_iter().flatten().collect());
+    if contiguous { z.to_bytes() } else { z }
+}
+
+/// Errors unless `final_z` can be honored: its z come from the same f64
+/// orbit as the pixel's escape value, one per pixel.
+pub fn check_final_z(params: &RenderParams, final_z: bool, deep_zoom: bool) -> PyResult<()> {
+    if final_z
+        && (!params.output.is_escape_value()
+            || params.precision != Precision::F64
+            || params.samples_per_axis > 1
+            || deep_zoom)
+    {
+        return Err(PyValueError::new_err(
+            "final_z needs single-sample f64 escape output without deep zoom",
+        ));
+    }
+    Ok(())
+}
+
+/// The `(histogram, summary)` flags a render runs with: both on with
+/// `stats_only`, which keeps nothing of the tiles but their statistics and
+/// so errors if there's an `emit_tile` to hand them to.
+pub fn resolve_stats_only(
+    stats_only: bool,
+    emit_tile: Option<&Py<PyAny>>,
+    histogram: bool,
+    summary: bool,
+) -> PyResult<(bool, bool)> {
+    if stats_only && emit_tile.is_some() {
+        return Err(PyValueError::new_err(
+            "stats_only emits no tiles; pass emit_tile=None",
+        ));
+    }
+    Ok((histogram || stats_only, summary || stats_only))
+}
+
+/// Stripe average: the mean of `(1 + sin(stripe_freq * arg z)) / 2` over
+/// `z_1 .. z_n`, in `[0, 1]`. Escaped points blend it with the mean without
+/// `z_n` by how far past the smooth radius `z_n` landed, so stripes carry on
+/// smoothly across iteration bands; points that never escape take the
+/// plain mean over all `max_iter` steps.
+pub fn stripe_value(params: &RenderParams, re: f64, im: f64) -> f32 {
+    let (mut z_re, mut z_im, c_re, c_im) = match params.kind {
+        FractalKind::Julia { c_re, c_im } => (re, im, c_re, c_im),
+        _ => (0.0, 0.0, re, im),
+    };
+    let radius_sq = params.smooth_radius_sq();
+    let fold_abs = matches!(params.kind, FractalKind::BurningShip);
+    let stripe = |z_re: f64, z_im: f64| 0.5 + 0.5 * (params.stripe_freq * z_im.atan2(z_re)).sin();
+
+    let (mut sum, mut last, mut n) = (0.0, 0.0, 0u32);
+    for _ in 0..params.max_iter {
+        let norm_sq = z_re * z_re + z_im * z_im;
+        if norm_sq > radius_sq {
+            // An orbit escaping without a step has no stripes to average
+            if n == 0 {
+                return 0.5;
+            }
+            let mean = sum / n as f64;
+            if n == 1 {
+                return mean as f32;
+            }
+            let previous = (sum - last) / (n - 1) as f64;
+            // 0 just past the radius, 1 a whole step past it
+            let past =
+                ((norm_sq.ln() / radius_sq.ln()).ln() / (params.power as f64).ln()).clamp(0.0, 1.0);
+            return (mean + (previous - mean) * past) as f32;
+        }
+
+        (z_re, z_im) = step(z_re, z_im, c_re, c_im, params.power, fold_abs);
+        last = stripe(z_re, z_im);
+        sum += last;
+        n += 1;
+    }
+
+    if n == 0 { 0.5 } else { (sum / n as f64) as f32 }
+}
+
+/// The escape value `float_value` would give, except that points which never
+/// escape hold `-(1 + |z|^2)` of their final z instead of `max_iter`, which
+/// is always negative. Iterates in f64 and skips the main-body shortcut, so
+/// it costs the full `max_iter` on every interior pixel.
+pub fn interior_value(params: &RenderParams, re: f64, im: f64) -> f64 {
+    let (z_re, z_im, c_re, c_im) = match params.kind {
+        FractalKind::Julia { c_re, c_im } => (re, im, c_re, c_im),
+        _ => (0.0, 0.0, re, im),
+    };
+    let smooth = matches!(params.output, OutputMode::Smooth | OutputMode::DoubleSmooth);
+    let radius_sq = if smooth {
+        params.smooth_radius_sq()
+    } else {
+        params.escape_radius_sq()
+    };
+    let fold_abs = matches!(params.kind, FractalKind::BurningShip);
+
+    let (i, z_re, z_im) = orbit_end(
+        z_re,
+        z_im,
+        c_re,
+        c_im,
+        params.max_iter,
+        params.power,
+        fold_abs,
+        radius_sq,
+    );
+    let norm = z_re * z_re + z_im * z_im;
+
+    if i == params.max_iter {
+        -(1.0 + norm)
+    } else if smooth {
+        let nu = ((norm.ln() / 2.0) / std::f64::consts::LN_2).ln() / (params.power as f64).ln();
+        (i as f64 + 1.0 - nu).max(0.0)
+    } else {
+        i as f64
+    }
+}
+
+/// Iterates until `|z|^2 > radius_sq` and returns the iteration together
+/// with the last z, which has escaped unless the iteration is `max_iter`.
+#[inline(always)]
+#[allow(clippy::too_many_arguments)]
+fn orbit_end(
+    mut z_re: f64,
+    mut z_im: f64,
+    c_re: f64,
+    c_im: f64,
+    max_iter: u32,
+    power: u32,
+    fold_abs: bool,
+    radius_sq: f64,
+) -> (u32, f64, f64) {
+    for i in 0..max_iter {
+        if z_re * z_re + z_im * z_im > radius_sq {
+            return (i, z_re, z_im);
+        }
+
+        (z_re, z_im) = step(z_re, z_im, c_re, c_im, power, fold_abs);
+    }
+
+    (max_iter, z_re, z_im)
+}
+
+// One generic `z^power + c` step, folding first for Burning Ship
+#[inline(always)]
+fn step(z_re: f64, z_im: f64, c_re: f64, c_im: f64, power: u32, fold_abs: bool) -> (f64, f64) {
+    let (z_re, z_im) = if fold_abs {
+        (z_re.abs(), z_im.abs())
+    } else {
+        (z_re, z_im)
+    };
+    let (p_re, p_im) = if power == 2 {
+        (z_re * z_re - z_im * z_im, 2.0 * z_re * z_im)
+    } else {
+        complex_powi(z_re, z_im, power)
+    };
+
+    (p_re + c_re, p_im + c_im)
+}
+
+/// Integer escape iteration for the pixel at `(re, im)`.
+#[inline(always)]
+pub fn escape_count(params: &RenderParams, re: f64, im: f64) -> u32 {
+    let max_iter = params.max_iter;
+    let r2 = params.escape_radius_sq();
+
+    if params.power != 2 {
+        return match params.kind {
+            FractalKind::Julia { c_re, c_im } => {
+                multibrot(re, im, c_re, c_im, params.power, max_iter, r2)
+            }
+            _ => multibrot(0.0, 0.0, re, im, params.power, max_iter, r2),
+        };
+    }
+
+    match (params.kind, params.precision) {
+        (FractalKind::Mandelbrot, Precision::F64) if params.periodicity => {
+            if r2 >= 4.0 && in_main_body(re, im) {
+                max_iter
+            } else {
+                quadratic_periodic(0.0, 0.0, re, im, max_iter, r2)
+            }
+        }
+        (FractalKind::Julia { c_re, c_im }, Precision::F64) if params.periodicity => {
+            quadratic_periodic(re, im, c_re, c_im, max_iter, r2)
+        }
+        (FractalKind::Mandelbrot, Precision::F64) if params.check_every > 1 => {
+            mandelbrot_coarse(re, im, max_iter, r2, params.check_every as u32)
+        }
+        (FractalKind::Mandelbrot, Precision::F64) => mandelbrot(re, im, max_iter, r2),
+        (FractalKind::Mandelbrot, Precision::F32) => {
+            mandelbrot_f32(re as f32, im as f32, max_iter, r2 as f32)
+        }
+        (FractalKind::Julia { c_re, c_im }, Precision::F64) => {
+            julia(re, im, c_re, c_im, max_iter, r2)
+        }
+        (FractalKind::Julia { c_re, c_im }, Precision::F32) => julia_f32(
+            re as f32,
+            im as f32,
+            c_re as f32,
+            c_im as f32,
+            max_iter,
+            r2 as f32,
+        ),
+        (FractalKind::BurningShip, _) => burning_ship(re, im, max_iter, r2),
+        (_, Precision::DoubleDouble) => {
+            unreachable!("double-double pixels are rendered by double_double::render")
+        }
+    }
+}
+
+/// Smooth escape value for the pixel at `(re, im)`.
+#[inline(always)]
+pub fn smooth_value(params: &RenderParams, re: f64, im: f64) -> f64 {
+    let max_iter = params.max_iter;
+    let power = params.power;
+    let r2 = params.smooth_radius_sq();
+
+    match params.kind {
+        FractalKind::Mandelbrot if power == 2 && in_main_body(re, im) => max_iter as f64,
+        FractalKind::Mandelbrot => smooth_escape(0.0, 0.0, re, im, max_iter, power, false, r2),
+        FractalKind::Julia { c_re, c_im } => {
+            smooth_escape(re, im, c_re, c_im, max_iter, power, false, r2)
+        }
+        FractalKind::BurningShip => smooth_escape(0.0, 0.0, re, im, max_iter, power, true, r2),
+    }
+}
+
+/// Busy-waits until the tile started at `start` has taken `TIME_MULTIPLIER`
+/// times as long as it did, so schedulers can be load-tested on cheap views.
+/// Spinning rather than sleeping keeps the core as busy as real work would.
+pub fn simulate_load(start: Instant) {
+    let target = start.elapsed().as_nanos() * crate::TIME_MULTIPLIER;
+    while start.elapsed().as_nanos() < target {
+        std::hint::spin_loop();
+    }
+}
+
+/// Asks the optional `should_cancel` callable whether to stop the render.
+pub fn cancel_requested(py: Python<'_>, should_cancel: Option<&Py<PyAny>>) -> PyResult<bool> {
+    match should_cancel {
+        Some(should_cancel) => should_cancel.bind(py).call0()?.is_truthy(),
+        None => Ok(false),
+    }
+}
+
+/// Forwards `units_done / units_total` to an optional `on_progress` callable,
+/// only when the whole percentage changes. Every renderer counts units in
+/// finished pixels, so clipped edge tiles and subdivided ones move the bar
+/// by their share of the image.
+pub struct Progress {
+    units_total: usize,
+    last_percent: Option<usize>,
+}
+
+impl Progress {
+    pub fn new(units_total: usize) -> Self {
+        Self {
+            units_total,
+            last_percent: None,
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        py: Python<'_>,
+        on_progress: Option<&Py<PyAny>>,
+        units_done: usize,
+    ) -> PyResult<()> {
+        let Some(on_progress) = on_progress else {
+            return Ok(());
+        };
+
+        let fraction = match self.units_total {
+            0 => 1.0,
+            total => units_done as f64 / total as f64,
+        };
+        let percent = (fraction * 100.0) as usize;
+        if self.last_percent != Some(percent) {
+            self.last_percent = Some(percent);
+            on_progress.bind(py).call1((fraction,))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Hands finished tiles to `emit_tile`, one per call, or as lists of up to
+/// `batch_size` to save on Python calls when tiles are small. Without an
+/// `emit_tile` tiles are dropped, and callers can skip building them.
+pub struct Emitter<'a> {
+    emit_tile: Option<&'a Py<PyAny>>,
+    batch_size: Option<usize>,
+    batch: Vec<TileUpdate>,
+}
+
+impl<'a> Emitter<'a> {
+    pub fn new(emit_tile: Option<&'a Py<PyAny>>, batch_size: Option<usize>) -> PyResult<Self> {
+        if batch_size == Some(0) {
+            return Err(PyValueError::new_err("batch_size must be positive"));
+        }
+        Ok(Self {
+            emit_tile,
+            batch_size,
+            batch: Vec::with_capacity(batch_size.unwrap_or(0)),
+        })
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.emit_tile.is_some()
+    }
+
+    pub fn emit(&mut self, py: Python<'_>, update: TileUpdate) -> PyResult<()> {
+        let Some(emit_tile) = self.emit_tile else {
+            return Ok(());
+        };
+        let Some(batch_size) = self.batch_size else {
+            emit_tile.call1(py, (update,))?;
+            return Ok(());
+        };
+        self.batch.push(update);
+        if self.batch.len() >= batch_size {
+            self.flush(py)?;
+        }
+        Ok(())
+    }
+
+    /// Emits whatever is left of the last batch.
+    pub fn flush(&mut self, py: Python<'_>) -> PyResult<()> {
+        if let Some(emit_tile) = self.emit_tile
+            && !self.batch.is_empty()
+        {
+            emit_tile.call1(py, (std::mem::take(&mut self.batch),))?;
+        }
+        Ok(())
+    }
+}
+
+pub const ORDER_RASTER: u8 = 0;
+pub const ORDER_BOUSTROPHEDON: u8 = 1;
+pub const ORDER_CENTER_OUT: u8 = 2;
+pub const ORDER_SHUFFLED: u8 = 3;
+
+/// Order in which tiles are rendered and emitted. It never changes their
+/// task_ids, which stay raster indices.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TileOrder {
+    Raster,
+    /// Raster, with every other row right to left
+    Boustrophedon,
+    /// Nearest tile to the image center first
+    CenterOut,
+    /// A reproducible permutation of the given seed
+    Shuffled {
+        seed: u64,
+    },
+}
+
+impl TileOrder {
+    pub fn from_code(code: u8, seed: u64) -> PyResult<Self> {
+        match code {
+            ORDER_RASTER => Ok(TileOrder::Raster),
+            ORDER_BOUSTROPHEDON => Ok(TileOrder::Boustrophedon),
+            ORDER_CENTER_OUT => Ok(TileOrder::CenterOut),
+            ORDER_SHUFFLED => Ok(TileOrder::Shuffled { seed }),
+            _ => Err(PyValueError::new_err(format!("unknown emit_order {code}"))),
+        }
+    }
+
+    /// Indices into `tiles`, a `tiles_for` grid, in this order.
+    pub fn arrange(
+        self,
+        tiles: &[(usize, usize, usize, usize)],
+        width: usize,
+        height: usize,
+    ) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..tiles.len()).collect();
+
+        match self {
+            TileOrder::Raster => {}
+            TileOrder::Boustrophedon => {
+                let row_h = tiles.first().map_or(1, |tile| tile.3);
+                order.sort_by_key(|&i| {
+                    let (x, y, _, _) = tiles[i];
+                    let row = y / row_h;
+                    (row, if row % 2 == 1 { usize::MAX - x } else { x })
+                });
+            }
+            TileOrder::CenterOut => {
+                let distance = |i: usize| {
+                    let (x, y, w, h) = tiles[i];
+                    let dx = (x as f64 + w as f64 / 2.0) - width as f64 / 2.0;
+                    let dy = (y as f64 + h as f64 / 2.0) - height as f64 / 2.0;
+                    dx * dx + dy * dy
+                };
+                order.sort_by(|&a, &b| distance(a).total_cmp(&distance(b)));
+            }
+            TileOrder::Shuffled { seed } => {
+                // Fisher-Yates driven by splitmix64
+                let mut state = seed;
+                let mut next = || {
+                    state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+                    let mut z = state;
+                    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+                    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+                    z ^ (z >> 31)
+                };
+                for i in (1..order.len()).rev() {
+                    let j = (next() % (i as u64 + 1)) as usize;
+                    order.swap(i, j);
+                }
+            }
+        }
+
+        order
+    }
+}
+
+/// Every tile of the grid as `(x, y, w, h)` in raster order, with edge
+/// tiles clipped to the image. A tile's task_id is its index in this list.
+pub fn tiles_for(
+    width: usize,
+    height: usize,
+    tile_w: usize,
+    tile_h: usize,
+) -> Vec<(usize, usize, usize, usize)> {
+    (0..height)
+        .step_by(tile_h)
+        .flat_map(|ty| (0..width).step_by(tile_w).map(move |tx| (tx, ty)))
+        .map(|(tx, ty)| {
+            let (w, h) = clipped_size(width, height, tx, ty, tile_w, tile_h);
+            (tx, ty, w, h)
+        })
+        .collect()
+}
+
+// Iterations the cost estimate follows each sample to, and the samples a
+// side it takes per tile
+const COST_SAMPLE_ITER: u32 = 64;
+const COST_SAMPLES_PER_AXIS: usize = 4;
+
+/// Rough cost of rendering the tile at `(x, y, w, h)`, in iterations: a
+/// grid of its points iterated to at most `COST_SAMPLE_ITER`, scaled up to
+/// the tile's pixels. A point still bound by then is charged the whole of
+/// `max_iter`, unless it's in the main cardioid or bulb that `mandelbrot`
+/// skips. It only has to tell interior-heavy tiles from cheap ones; deep
+/// zooms, below f64 resolution, estimate every tile the same.
+pub fn estimate_tile_cost(
+    params: &RenderParams,
+    tile_x: usize,
+    tile_y: usize,
+    tile_w: usize,
+    tile_h: usize,
+) -> u64 {
+    let sample = RenderParams {
+        max_iter: COST_SAMPLE_ITER.min(params.max_iter),
+        precision: Precision::F64,
+        periodicity: false,
+        check_every: 1,
+        ..*params
+    };
+    let skips_main_body = matches!(params.kind, FractalKind::Mandelbrot) && params.power == 2;
+    let n = COST_SAMPLES_PER_AXIS;
+    let mut total = 0;
+    for sy in 0..n {
+        for sx in 0..n {
+            let x = tile_x as f64 + (sx as f64 + 0.5) * tile_w as f64 / n as f64;
+            let mut y = tile_y as f64 + (sy as f64 + 0.5) * tile_h as f64 / n as f64;
+            if params.flip_y {
+                y = params.height as f64 - y;
+            }
+            let (re, im) = params.to_complex(x, y);
+            total += if skips_main_body && in_main_body(re, im) {
+                1
+            } else {
+                match escape_count(&sample, re, im) {
+                    i if i >= sample.max_iter => params.max_iter,
+                    i => i.max(1),
+                }
+            } as u64;
+        }
+    }
+    total * (tile_w * tile_h) as u64 / (n * n) as u64
+}
+
+/// Splits a tile into its four quadrants, skipping empty ones. A quadrant's
+/// `task_id` is `4 * parent + q` one level deeper, with `q` in raster order,
+/// so `(depth, task_id)` is unique and the parent is `task_id / 4`.
+pub fn subdivide(
+    task_id: usize,
+    tile_x: usize,
+    tile_y: usize,
+    tile_w: usize,
+    tile_h: usize,
+) -> impl Iterator<Item = (usize, usize, usize, usize, usize)> {
+    let (left_w, top_h) = (tile_w.div_ceil(2), tile_h.div_ceil(2));
+
+    [
+        (tile_x, tile_y, left_w, top_h),
+        (tile_x + left_w, tile_y, tile_w - left_w, top_h),
+        (tile_x, tile_y + top_h, left_w, tile_h - top_h),
+        (
+            tile_x + left_w,
+            tile_y + top_h,
+            tile_w - left_w,
+            tile_h - top_h,
+        ),
+    ]
+    .into_iter()
+    .enumerate()
+    .filter(|&(_, (_, _, w, h))| w > 0 && h > 0)
+    .map(move |(q, (x, y, w, h))| (4 * task_id + q, x, y, w, h))
+}
+
+/// Tile size once clipped to the image, as `(cols, rows)`.
+pub fn clipped_size(
     width: usize,
     height: usize,
     tile_x: usize,
     tile_y: usize,
     tile_w: usize,
     tile_h: usize,
-    max_iter: u16,
-) -> Vec<u16> {
-    let mut out = Vec::with_capacity(tile_w * tile_h);
+) -> (usize, usize) {
+    (
+        tile_w.min(width.saturating_sub(tile_x)),
+        tile_h.min(height.saturating_sub(tile_y)),
+    )
+}
+
+// Walks the tile in row-major order, clipped to the image, appending `f`
+// of each pixel's coordinates to `out`. Only every `stride`-th pixel of
+// every `stride`-th row is visited. Rows past `cutoff` are skipped.
+pub(crate) fn map_pixels<T: Clone>(
+    params: &RenderParams,
+    (tile_x, tile_y, tile_w, tile_h): (usize, usize, usize, usize),
+    stride: usize,
+    cutoff: Cutoff<'_>,
+    out: &mut Vec<T>,
+    f: impl Fn(f64, f64) -> T,
+) {
+    let RenderParams { width, height, .. } = *params;
+    if params.traversal == Traversal::ZOrder {
+        return map_pixels_z_order(
+            params,
+            (tile_x, tile_y, tile_w, tile_h),
+            stride,
+            cutoff,
+            out,
+            f,
+        );
+    }
+
+    out.reserve(tile_w.div_ceil(stride) * tile_h.div_ceil(stride));
+
+    for dy in (0..tile_h).step_by(stride) {
+        let y = tile_y + dy;
+        if y >= height {
+            break;
+        }
+
+        if cutoff.reached() {
+            break;
+        }
+
+        for dx in (0..tile_w).step_by(stride) {
+            let x = tile_x + dx;
+            if x >= width {
+                break;
+            }
+
+            out.push(f(x as f64, params.row_position(y)));
+        }
+    }
+}
+
+/// `map_pixels` for `Traversal::ZOrder`: square blocks as wide as the
+/// tile's shorter side, in raster order, each walked along the Morton curve.
+/// Values still land row-major, and a cutoff, checked as often as rows,
+/// keeps just the leading rows finished by then, which the curve only
+/// finishes a block's height at a time.
+fn map_pixels_z_order<T: Clone>(
+    params: &RenderParams,
+    tile: (usize, usize, usize, usize),
+    stride: usize,
+    cutoff: Cutoff<'_>,
+    out: &mut Vec<T>,
+    f: impl Fn(f64, f64) -> T,
+) {
+    let (tile_x, tile_y, _, _) = tile;
+    let (cols, rows) = preview_dims(params, tile, stride);
+    if cols == 0 || rows == 0 || cutoff.reached() {
+        return;
+    }
+    let value_at = |col: usize, row: usize| {
+        f(
+            (tile_x + col * stride) as f64,
+            params.row_position(tile_y + row * stride),
+        )
+    };
+
+    // The first pixel is also the first on the curve; until their turn it
+    // stands in for the rest
+    let start = out.len();
+    out.resize(start + cols * rows, value_at(0, 0));
+    let mut left_in_row = vec![cols; rows];
+    left_in_row[0] -= 1;
+    let mut done = 1;
+
+    let side = cols.min(rows).next_power_of_two();
+    for block_y in (0..rows).step_by(side) {
+        for block_x in (0..cols).step_by(side) {
+            for index in 0..side * side {
+                let col = block_x + morton_axis(index);
+                let row = block_y + morton_axis(index >> 1);
+                if col >= cols || row >= rows || (col, row) == (0, 0) {
+                    continue;
+                }
+                if done % cols == 0 && cutoff.reached() {
+                    let finished = left_in_row.iter().take_while(|&&left| left == 0).count();
+                    out.truncate(start + finished * cols);
+                    return;
+                }
+                out[start + row * cols + col] = value_at(col, row);
+                left_in_row[row] -= 1;
+                done += 1;
+            }
+        }
+    }
+}
+
+// The coordinate held in the even bits of a Morton index
+#[inline(always)]
+fn morton_axis(index: usize) -> usize {
+    let mut x = index as u64 & 0x5555_5555_5555_5555;
+    x = (x | x >> 1) & 0x3333_3333_3333_3333;
+    x = (x | x >> 2) & 0x0f0f_0f0f_0f0f_0f0f;
+    x = (x | x >> 4) & 0x00ff_00ff_00ff_00ff;
+    x = (x | x >> 8) & 0x0000_ffff_0000_ffff;
+    x = (x | x >> 16) & 0x0000_0000_ffff_ffff;
+    x as usize
+}
+
+/// `value` rounded to the precision `map_values` stores it at. Samples are
+/// averaged at that precision too.
+#[inline(always)]
+fn stored_precision(params: &RenderParams, value: f64) -> f64 {
+    if params.output == OutputMode::DoubleSmooth {
+        value
+    } else {
+        value as f32 as f64
+    }
+}
+
+/// `map_pixels` into the tile's f64 buffer for `OutputMode::DoubleSmooth`,
+/// or its f32 one otherwise.
+pub(crate) fn map_values(
+    params: &RenderParams,
+    tile: (usize, usize, usize, usize),
+    stride: usize,
+    cutoff: Cutoff<'_>,
+    out: &mut TileData,
+    f: impl Fn(f64, f64) -> f64,
+) {
+    if params.output == OutputMode::DoubleSmooth {
+        map_pixels(params, tile, stride, cutoff, out.doubles_mut(), f)
+    } else {
+        map_pixels(params, tile, stride, cutoff, out.values_mut(), |x, y| {
+            f(x, y) as f32
+        })
+    }
+}
+
+#[inline(always)]
+pub fn mandelbrot(c_re: f64, c_im: f64, max_iter: u32, radius_sq: f64) -> u32 {
+    // The shortcut assumes the usual radius; interior orbits can pass a smaller one
+    if radius_sq >= 4.0 && in_main_body(c_re, c_im) {
+        return max_iter;
+    }
+
+    let mut z_re = 0.0;
+    let mut z_im = 0.0;
+
+    for i in 0..max_iter {
+        let re2 = z_re * z_re;
+        let im2 = z_im * z_im;
+
+        if re2 + im2 > radius_sq {
+            return i;
+        }
+
+        z_im = 2.0 * z_re * z_im + c_im;
+        z_re = re2 - im2 + c_re;
+    }
+
+    max_iter
+}
+
+/// `mandelbrot`, but only testing for escape every `check_every` iterations,
+/// which saves the test and its branch on the iterations in between. An
+/// escape is reported at the next test, up to `check_every - 1` late, and
+/// one in the last stretch before `max_iter` at `max_iter - 1`, so it still
+/// counts as exterior.
+#[inline(always)]
+pub fn mandelbrot_coarse(
+    c_re: f64,
+    c_im: f64,
+    max_iter: u32,
+    radius_sq: f64,
+    check_every: u32,
+) -> u32 {
+    if radius_sq >= 4.0 && in_main_body(c_re, c_im) {
+        return max_iter;
+    }
+
+    let mut z_re = 0.0f64;
+    let mut z_im = 0.0f64;
+    // Orbits that escaped between tests can overflow to infinity and then NaN
+    let escaped = |z_re: f64, z_im: f64| {
+        let norm = z_re * z_re + z_im * z_im;
+        norm > radius_sq || norm.is_nan()
+    };
+
+    let mut i = 0;
+    while i < max_iter {
+        if escaped(z_re, z_im) {
+            return i;
+        }
+
+        let steps = check_every.min(max_iter - i);
+        for _ in 0..steps {
+            (z_re, z_im) = (z_re * z_re - z_im * z_im + c_re, 2.0 * z_re * z_im + c_im);
+        }
+        i += steps;
+    }
+
+    if escaped(z_re, z_im) {
+        max_iter - 1
+    } else {
+        max_iter
+    }
+}
+
+/// Iterations `mandelbrot` actually ran to return `count` for `c`: the
+/// count itself, except for main-body points, which it answers without
+/// iterating at all.
+#[inline(always)]
+pub fn mandelbrot_iterations(
+    c_re: f64,
+    c_im: f64,
+    count: u32,
+    max_iter: u32,
+    radius_sq: f64,
+) -> u32 {
+    if count == max_iter && radius_sq >= 4.0 && in_main_body(c_re, c_im) {
+        0
+    } else {
+        count
+    }
+}
+
+#[inline(always)]
+pub fn mandelbrot_f32(c_re: f32, c_im: f32, max_iter: u32, radius_sq: f32) -> u32 {
+    if radius_sq >= 4.0 && in_main_body(c_re as f64, c_im as f64) {
+        return max_iter;
+    }
+
+    let mut z_re = 0.0f32;
+    let mut z_im = 0.0f32;
+
+    for i in 0..max_iter {
+        let re2 = z_re * z_re;
+        let im2 = z_im * z_im;
+
+        if re2 + im2 > radius_sq {
+            return i;
+        }
+
+        z_im = 2.0 * z_re * z_im + c_im;
+        z_re = re2 - im2 + c_re;
+    }
+
+    max_iter
+}
+
+/// True inside the main cardioid or the period-2 bulb, where every point is
+/// known not to escape.
+#[inline(always)]
+pub fn in_main_body(c_re: f64, c_im: f64) -> bool {
+    let im2 = c_im * c_im;
+
+    let q = (c_re - 0.25) * (c_re - 0.25) + im2;
+    if q * (q + (c_re - 0.25)) <= 0.25 * im2 {
+        return true;
+    }
+
+    (c_re + 1.0) * (c_re + 1.0) + im2 <= 1.0 / 16.0
+}
+
+#[inline(always)]
+pub fn burning_ship(c_re: f64, c_im: f64, max_iter: u32, radius_sq: f64) -> u32 {
+    let mut z_re: f64 = 0.0;
+    let mut z_im: f64 = 0.0;
+
+    for i in 0..max_iter {
+        let re2 = z_re * z_re;
+        let im2 = z_im * z_im;
+
+        if re2 + im2 > radius_sq {
+            return i;
+        }
+
+        z_im = 2.0 * (z_re * z_im).abs() + c_im;
+        z_re = re2 - im2 + c_re;
+    }
+
+    max_iter
+}
+
+#[inline(always)]
+pub fn julia(
+    mut z_re: f64,
+    mut z_im: f64,
+    c_re: f64,
+    c_im: f64,
+    max_iter: u32,
+    radius_sq: f64,
+) -> u32 {
+    for i in 0..max_iter {
+        let re2 = z_re * z_re;
+        let im2 = z_im * z_im;
+
+        if re2 + im2 > radius_sq {
+            return i;
+        }
+
+        z_im = 2.0 * z_re * z_im + c_im;
+        z_re = re2 - im2 + c_re;
+    }
 
-    for dy in 0..tile_h {
-        let y = tile_y + dy;
-        if y >= height {
-            break;
-        }
+    max_iter
+}
 
-        for dx in 0..tile_w {
-            let x = tile_x + dx;
-            if x >= width {
-                break;
-            }
+// Closer than this to an earlier z, an orbit counts as periodic
+const PERIOD_EPSILON: f64 = 1e-13;
+
+/// `julia`, but returns `max_iter` as soon as z comes back to within
+/// `PERIOD_EPSILON` of a saved value (Brent's method: the saved z moves on
+/// after a window that doubles each time, so cycles of any period are
+/// caught once the window outgrows them). Costs a compare per iteration and
+/// pays off on interior-heavy views.
+pub fn quadratic_periodic(
+    mut z_re: f64,
+    mut z_im: f64,
+    c_re: f64,
+    c_im: f64,
+    max_iter: u32,
+    radius_sq: f64,
+) -> u32 {
+    let (mut saved_re, mut saved_im) = (z_re, z_im);
+    let mut window = 8;
+    let mut left = window;
+
+    for i in 0..max_iter {
+        let re2 = z_re * z_re;
+        let im2 = z_im * z_im;
+
+        if re2 + im2 > radius_sq {
+            return i;
+        }
 
-            let c_re = (x as f64 / width as f64) * 3.5 - 2.5;
-            let c_im = (y as f64 / height as f64) * 2.0 - 1.0;
+        z_im = 2.0 * z_re * z_im + c_im;
+        z_re = re2 - im2 + c_re;
 
-            out.push(mandelbrot(c_re, c_im, max_iter));
+        if (z_re - saved_re).abs() < PERIOD_EPSILON && (z_im - saved_im).abs() < PERIOD_EPSILON {
+            return max_iter;
+        }
+        left -= 1;
+        if left == 0 {
+            (saved_re, saved_im) = (z_re, z_im);
+            window *= 2;
+            left = window;
         }
     }
 
-    out
+    max_iter
 }
 
 #[inline(always)]
-pub fn mandelbrot(c_re: f64, c_im: f64, max_iter: u16) -> u16 {
-    let mut z_re = 0.0;
-    let mut z_im = 0.0;
-
+pub fn julia_f32(
+    mut z_re: f32,
+    mut z_im: f32,
+    c_re: f32,
+    c_im: f32,
+    max_iter: u32,
+    radius_sq: f32,
+) -> u32 {
     for i in 0..max_iter {
         let re2 = z_re * z_re;
         let im2 = z_im * z_im;
 
-        if re2 + im2 > 4.0 {
+        if re2 + im2 > radius_sq {
             return i;
         }
 
@@ -79,3 +3381,1290 @@ pub fn mandelbrot(c_re: f64, c_im: f64, max_iter: u16) -> u16 {
 
     max_iter
 }
+
+/// Distance estimate for the pixel at `(re, im)`.
+#[inline(always)]
+pub fn distance_value(params: &RenderParams, re: f64, im: f64) -> f32 {
+    let max_iter = params.max_iter;
+    let r2 = params.smooth_radius_sq();
+
+    match params.kind {
+        FractalKind::Mandelbrot if in_main_body(re, im) => 0.0,
+        FractalKind::Julia { c_re, c_im } => {
+            distance_estimate_julia(re, im, c_re, c_im, max_iter, r2)
+        }
+        _ => distance_estimate(re, im, max_iter, r2),
+    }
+}
+
+/// Exterior distance estimate `|z| ln|z| / |dz/dc|` from `c` to the
+/// Mandelbrot set, or 0.0 for points that never escape.
+#[inline(always)]
+pub fn distance_estimate(c_re: f64, c_im: f64, max_iter: u32, radius_sq: f64) -> f32 {
+    match mandelbrot_derivative(c_re, c_im, max_iter, radius_sq) {
+        Some((z_re, z_im, dz_re, dz_im)) => {
+            exterior_distance(z_re * z_re + z_im * z_im, dz_re, dz_im)
+        }
+        None => 0.0,
+    }
+}
+
+/// Julia counterpart of `distance_estimate`, using `dz/dz0` instead.
+#[inline(always)]
+pub fn distance_estimate_julia(
+    z_re: f64,
+    z_im: f64,
+    c_re: f64,
+    c_im: f64,
+    max_iter: u32,
+    radius_sq: f64,
+) -> f32 {
+    match julia_derivative(z_re, z_im, c_re, c_im, max_iter, radius_sq) {
+        Some((z_re, z_im, dz_re, dz_im)) => {
+            exterior_distance(z_re * z_re + z_im * z_im, dz_re, dz_im)
+        }
+        None => 0.0,
+    }
+}
+
+/// `(z, dz/dc)` of the Mandelbrot orbit of `c` once it passes `radius_sq`,
+/// or None if it never does.
+#[inline(always)]
+fn mandelbrot_derivative(
+    c_re: f64,
+    c_im: f64,
+    max_iter: u32,
+    radius_sq: f64,
+) -> Option<(f64, f64, f64, f64)> {
+    let (mut z_re, mut z_im) = (0.0f64, 0.0f64);
+    let (mut dz_re, mut dz_im) = (0.0f64, 0.0f64);
+
+    for _ in 0..max_iter {
+        let re2 = z_re * z_re;
+        let im2 = z_im * z_im;
+
+        if re2 + im2 > radius_sq {
+            return Some((z_re, z_im, dz_re, dz_im));
+        }
+
+        // dz = 2 z dz + 1
+        (dz_re, dz_im) = (
+            2.0 * (z_re * dz_re - z_im * dz_im) + 1.0,
+            2.0 * (z_re * dz_im + z_im * dz_re),
+        );
+        z_im = 2.0 * z_re * z_im + c_im;
+        z_re = re2 - im2 + c_re;
+    }
+
+    None
+}
+
+/// Julia counterpart of `mandelbrot_derivative`, with `dz/dz0`.
+#[inline(always)]
+fn julia_derivative(
+    mut z_re: f64,
+    mut z_im: f64,
+    c_re: f64,
+    c_im: f64,
+    max_iter: u32,
+    radius_sq: f64,
+) -> Option<(f64, f64, f64, f64)> {
+    let (mut dz_re, mut dz_im) = (1.0f64, 0.0f64);
+
+    for _ in 0..max_iter {
+        let re2 = z_re * z_re;
+        let im2 = z_im * z_im;
+
+        if re2 + im2 > radius_sq {
+            return Some((z_re, z_im, dz_re, dz_im));
+        }
+
+        // dz = 2 z dz
+        (dz_re, dz_im) = (
+            2.0 * (z_re * dz_re - z_im * dz_im),
+            2.0 * (z_re * dz_im + z_im * dz_re),
+        );
+        z_im = 2.0 * z_re * z_im + c_im;
+        z_re = re2 - im2 + c_re;
+    }
+
+    None
+}
+
+/// Brightness of the pixel at `(re, im)` under `params.light`, or 0.0 for
+/// points that never escape.
+#[inline(always)]
+pub fn shaded_value(params: &RenderParams, re: f64, im: f64) -> f32 {
+    let max_iter = params.max_iter;
+    let r2 = params.smooth_radius_sq();
+
+    let escaped = match params.kind {
+        FractalKind::Mandelbrot if in_main_body(re, im) => None,
+        FractalKind::Julia { c_re, c_im } => julia_derivative(re, im, c_re, c_im, max_iter, r2),
+        _ => mandelbrot_derivative(re, im, max_iter, r2),
+    };
+    match escaped {
+        Some((z_re, z_im, dz_re, dz_im)) => params.light.shade(z_re, z_im, dz_re, dz_im),
+        None => 0.0,
+    }
+}
+
+#[inline(always)]
+fn exterior_distance(z_norm_sq: f64, dz_re: f64, dz_im: f64) -> f32 {
+    let z_abs = z_norm_sq.sqrt();
+    let dz_abs = (dz_re * dz_re + dz_im * dz_im).sqrt();
+    (z_abs * z_abs.ln() / dz_abs) as f32
+}
+
+// (2^16)^2: a large bailout keeps the log-log normalization accurate
+pub const SMOOTH_ESCAPE_RADIUS_SQ: f64 = 4_294_967_296.0;
+
+/// Fractional escape count `i + 1 - log_d(log2 |z|)`, or `max_iter` for points
+/// that never escape. Start from `z = 0` for Mandelbrot and `z = pixel` for Julia;
+/// `fold_abs` takes `|Re z| + i|Im z|` before raising to `power`, as Burning Ship does.
+#[inline(always)]
+#[allow(clippy::too_many_arguments)]
+pub fn smooth_escape(
+    mut z_re: f64,
+    mut z_im: f64,
+    c_re: f64,
+    c_im: f64,
+    max_iter: u32,
+    power: u32,
+    fold_abs: bool,
+    radius_sq: f64,
+) -> f64 {
+    let ln_power = (power as f64).ln();
+
+    for i in 0..max_iter {
+        if fold_abs {
+            z_re = z_re.abs();
+            z_im = z_im.abs();
+        }
+
+        let re2 = z_re * z_re;
+        let im2 = z_im * z_im;
+
+        if re2 + im2 > radius_sq {
+            let log_z = (re2 + im2).ln() / 2.0;
+            let nu = (log_z / std::f64::consts::LN_2).ln() / ln_power;
+            return (i as f64 + 1.0 - nu).max(0.0);
+        }
+
+        if power == 2 {
+            z_im = 2.0 * z_re * z_im + c_im;
+            z_re = re2 - im2 + c_re;
+        } else {
+            let (p_re, p_im) = complex_powi(z_re, z_im, power);
+            z_re = p_re + c_re;
+            z_im = p_im + c_im;
+        }
+    }
+
+    max_iter as f64
+}
+
+/// `z^d + c` escape count for `d > 2`, starting from `z`.
+#[inline(always)]
+pub fn multibrot(
+    mut z_re: f64,
+    mut z_im: f64,
+    c_re: f64,
+    c_im: f64,
+    power: u32,
+    max_iter: u32,
+    radius_sq: f64,
+) -> u32 {
+    for i in 0..max_iter {
+        if z_re * z_re + z_im * z_im > radius_sq {
+            return i;
+        }
+
+        let (p_re, p_im) = complex_powi(z_re, z_im, power);
+        z_re = p_re + c_re;
+        z_im = p_im + c_im;
+    }
+
+    max_iter
+}
+
+/// `z^n` by repeated multiplication.
+#[inline(always)]
+pub fn complex_powi(re: f64, im: f64, n: u32) -> (f64, f64) {
+    let (mut p_re, mut p_im) = (re, im);
+    for _ in 1..n {
+        (p_re, p_im) = (p_re * re - p_im * im, p_re * im + p_im * re);
+    }
+
+    (p_re, p_im)
+}
+
+/// Mandelbrot orbit of the view center, iterated in double-double and kept
+/// as f64. Pixels then only track their small offset `dz` from it:
+/// `dz' = (2Z + dz) dz + dc`, which f64 handles at any zoom.
+///
+/// From Python, `compute_reference_orbit` makes one to pass as
+/// `reference_orbit`, so frames around the same center iterate it once.
+#[pyclass(frozen)]
+#[derive(Debug, Clone)]
+pub struct ReferenceOrbit {
+    pub center_re: DoubleDouble,
+    pub center_im: DoubleDouble,
+    // Iterations it was computed for, which renders may not exceed
+    #[pyo3(get)]
+    pub max_iter: u32,
+    // Z_0 = 0 up to the first point past SMOOTH_ESCAPE_RADIUS_SQ, or max_iter
+    z: Vec<(f64, f64)>,
+}
+
+#[pymethods]
+impl ReferenceOrbit {
+    fn __len__(&self) -> usize {
+        self.len()
+    }
+}
+
+impl ReferenceOrbit {
+    /// Center given as decimal strings, since an f64 center would already
+    /// have lost the digits a deep zoom needs.
+    pub fn new(params: &RenderParams, center_re: &str, center_im: &str) -> PyResult<Self> {
+        Self::check_params(params)?;
+        Self::compute(center_re, center_im, params.max_iter)
+    }
+
+    /// Errors unless frames of `params` can be rendered around an orbit.
+    pub fn check_params(params: &RenderParams) -> PyResult<()> {
+        if !matches!(params.kind, FractalKind::Mandelbrot) || params.power != 2 {
+            return Err(PyValueError::new_err(
+                "deep zoom only supports the power 2 Mandelbrot set",
+            ));
+        }
+        if !params.output.is_escape_value() || params.interior {
+            return Err(PyValueError::new_err(
+                "deep zoom does not support distance output or interior coloring",
+            ));
+        }
+        if params.precision == Precision::F32 {
+            return Err(PyValueError::new_err(
+                "deep zoom iterates its offsets in f64, so f32 precision does not apply",
+            ));
+        }
+        if params.periodicity {
+            return Err(PyValueError::new_err(
+                "deep zoom does not check orbits for periodicity",
+            ));
+        }
+        if params.check_every > 1 {
+            return Err(PyValueError::new_err(
+                "deep zoom tests every iteration for escape, so check_every does not apply",
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn compute(center_re: &str, center_im: &str, max_iter: u32) -> PyResult<Self> {
+        let (c_re, c_im) = (parse_coordinate(center_re)?, parse_coordinate(center_im)?);
+
+        let mut z = Vec::with_capacity(max_iter as usize + 1);
+        let (mut z_re, mut z_im) = (DoubleDouble::ZERO, DoubleDouble::ZERO);
+        for _ in 0..=max_iter {
+            let (re, im) = (z_re.to_f64(), z_im.to_f64());
+            z.push((re, im));
+            if re * re + im * im > SMOOTH_ESCAPE_RADIUS_SQ {
+                break;
+            }
+
+            (z_re, z_im) = (z_re.sqr() - z_im.sqr() + c_re, (z_re * z_im) * 2.0 + c_im);
+        }
+
+        Ok(Self {
+            center_re: c_re,
+            center_im: c_im,
+            max_iter,
+            z,
+        })
+    }
+
+    /// Iterations the reference itself survives before escaping.
+    pub fn len(&self) -> usize {
+        self.z.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.z.is_empty()
+    }
+
+    /// Runs the pixel at offset `dc` from the center until `|z|^2` passes
+    /// `radius_sq`, returning the iteration and the final `z`.
+    ///
+    /// Whenever the full `z` gets smaller than `dz`, or the reference has
+    /// escaped first, the pixel is rebased onto the start of the orbit
+    /// (`dz = z`), which keeps `dz` small and avoids glitched regions.
+    #[inline(always)]
+    fn iterate(
+        &self,
+        dc_re: f64,
+        dc_im: f64,
+        max_iter: u32,
+        radius_sq: f64,
+    ) -> Option<(u32, f64, f64)> {
+        let (mut dz_re, mut dz_im) = (0.0f64, 0.0f64);
+        let mut m = 0;
+
+        for i in 0..max_iter {
+            let (mut ref_re, mut ref_im) = self.z[m];
+            let (z_re, z_im) = (ref_re + dz_re, ref_im + dz_im);
+            let norm = z_re * z_re + z_im * z_im;
+
+            if norm > radius_sq {
+                return Some((i, z_re, z_im));
+            }
+
+            if norm < dz_re * dz_re + dz_im * dz_im || m + 1 == self.z.len() {
+                (dz_re, dz_im) = (z_re, z_im);
+                (ref_re, ref_im) = (0.0, 0.0);
+                m = 0;
+            }
+
+            let (t_re, t_im) = (2.0 * ref_re + dz_re, 2.0 * ref_im + dz_im);
+            (dz_re, dz_im) = (
+                t_re * dz_re - t_im * dz_im + dc_re,
+                t_re * dz_im + t_im * dz_re + dc_im,
+            );
+            m += 1;
+        }
+
+        None
+    }
+
+    /// Perturbed counterpart of `mandelbrot`.
+    #[inline(always)]
+    pub fn escape_count(&self, dc_re: f64, dc_im: f64, max_iter: u32, radius_sq: f64) -> u32 {
+        self.iterate(dc_re, dc_im, max_iter, radius_sq)
+            .map_or(max_iter, |(i, _, _)| i)
+    }
+
+    /// Perturbed counterpart of `smooth_escape` for power 2.
+    #[inline(always)]
+    pub fn smooth_escape(&self, dc_re: f64, dc_im: f64, max_iter: u32, radius_sq: f64) -> f64 {
+        match self.iterate(dc_re, dc_im, max_iter, radius_sq) {
+            Some((i, z_re, z_im)) => {
+                let log_z = (z_re * z_re + z_im * z_im).ln() / 2.0;
+                let nu = (log_z / std::f64::consts::LN_2).log2();
+                (i as f64 + 1.0 - nu).max(0.0)
+            }
+            None => max_iter as f64,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn naive_mandelbrot(c_re: f64, c_im: f64, max_iter: u32) -> u32 {
+        let (mut z_re, mut z_im) = (0.0f64, 0.0f64);
+        for i in 0..max_iter {
+            if z_re * z_re + z_im * z_im > 4.0 {
+                return i;
+            }
+            (z_re, z_im) = (z_re * z_re - z_im * z_im + c_re, 2.0 * z_re * z_im + c_im);
+        }
+        max_iter
+    }
+
+    #[test]
+    fn main_body_shortcut_matches_naive_loop() {
+        let mut shortcut_taken = 0;
+        for y in 0..=240 {
+            for x in 0..=320 {
+                let (c_re, c_im) = (-2.2 + x as f64 * 0.01, -1.2 + y as f64 * 0.01);
+                let count = mandelbrot(c_re, c_im, 2000, 4.0);
+                assert_eq!(
+                    count,
+                    naive_mandelbrot(c_re, c_im, 2000),
+                    "at {c_re} {c_im}"
+                );
+                shortcut_taken += in_main_body(c_re, c_im) as u32;
+            }
+        }
+        assert!(shortcut_taken > 1000);
+    }
+
+    #[test]
+    fn tiles_cover_each_pixel_once() {
+        for (width, height, tile_w, tile_h) in [(100, 100, 32, 32), (64, 48, 16, 16), (7, 5, 8, 8)]
+        {
+            let tiles = tiles_for(width, height, tile_w, tile_h);
+            let mut covered = vec![0u8; width * height];
+            for &(x, y, w, h) in &tiles {
+                assert!(w <= tile_w && h <= tile_h && x + w <= width && y + h <= height);
+                for row in y..y + h {
+                    covered[row * width + x..row * width + x + w]
+                        .iter_mut()
+                        .for_each(|c| *c += 1);
+                }
+            }
+            assert!(covered.iter().all(|&c| c == 1));
+            assert_eq!(
+                tiles.len(),
+                width.div_ceil(tile_w) * height.div_ceil(tile_h)
+            );
+        }
+    }
+
+    fn params(width: usize, height: usize, max_iter: u32) -> RenderParams {
+        RenderParams::plain_mandelbrot(
+            width,
+            height,
+            max_iter,
+            Viewport::default_for(width, height),
+        )
+    }
+
+    #[test]
+    fn point_trap_distance_at_the_set_center() {
+        let params = RenderParams {
+            output: OutputMode::Trap,
+            ..params(8, 8, 100)
+        };
+        // 0 is fixed and -1 cycles through 0
+        assert_eq!(trap_value(&params, 0.0, 0.0), 0.0);
+        assert_eq!(trap_value(&params, -1.0, 0.0), 0.0);
+        // 0.1 climbs from 0.1 toward its fixed point near 0.113
+        assert_eq!(trap_value(&params, 0.1, 0.0), 0.1);
+        // 2 -> 6 escapes, having come no closer than 2
+        assert_eq!(trap_value(&params, 2.0, 0.0), 2.0);
+        let moved = RenderParams {
+            trap: OrbitTrap::Point { re: 0.1, im: 0.0 },
+            ..params
+        };
+        assert_eq!(trap_value(&moved, 0.1, 0.0), 0.0);
+    }
+
+    #[test]
+    fn checksum_is_fnv1a_of_the_le_bytes() {
+        assert_eq!(
+            TileData::Bytes(Vec::new()).checksum(),
+            0xcbf2_9ce4_8422_2325
+        );
+        assert_eq!(
+            TileData::Bytes(b"a".to_vec()).checksum(),
+            0xaf63_dc4c_8601_ec8c
+        );
+        assert_eq!(
+            TileData::Iterations(vec![0x61]).checksum(),
+            TileData::Bytes(vec![0x61, 0, 0, 0]).checksum()
+        );
+    }
+
+    #[test]
+    fn reference_tile_checksum_is_stable() {
+        let mut out = TileData::default();
+        render_tile(&params(64, 48, 200), 16, 16, 32, 16, &mut out);
+        // Only changes if some escape count of the tile does
+        assert_eq!(out.checksum(), 0xe878_ee5b_4ee5_8cb8);
+    }
+
+    fn assert_offsets(pattern: SamplePattern, n: u32, expected: &[(f64, f64)]) {
+        let samples = (0..n).flat_map(|sy| (0..n).map(move |sx| pattern.offset(sx, sy, n)));
+        for ((x, y), &(ex, ey)) in samples.zip(expected) {
+            assert!(
+                (x - ex).abs() < 1e-15 && (y - ey).abs() < 1e-15,
+                "{pattern:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn sample_patterns_give_the_documented_offsets() {
+        assert_offsets(
+            SamplePattern::Grid,
+            2,
+            &[(0.25, 0.25), (0.75, 0.25), (0.25, 0.75), (0.75, 0.75)],
+        );
+        // Every sample on its own row and column of the 4 x 4 grid
+        assert_offsets(
+            SamplePattern::RotatedGrid,
+            2,
+            &[
+                (0.125, 0.625),
+                (0.625, 0.875),
+                (0.375, 0.125),
+                (0.875, 0.375),
+            ],
+        );
+        assert_offsets(
+            SamplePattern::Halton,
+            2,
+            &[
+                (0.5, 1.0 / 3.0),
+                (0.25, 2.0 / 3.0),
+                (0.75, 1.0 / 9.0),
+                (0.125, 4.0 / 9.0),
+            ],
+        );
+    }
+
+    #[test]
+    fn sample_patterns_stay_inside_the_pixel() {
+        for pattern in [
+            SamplePattern::Grid,
+            SamplePattern::RotatedGrid,
+            SamplePattern::Halton,
+        ] {
+            for n in 1..=5 {
+                for (sx, sy) in (0..n).flat_map(|sy| (0..n).map(move |sx| (sx, sy))) {
+                    let (x, y) = pattern.offset(sx, sy, n);
+                    assert!((0.0..1.0).contains(&x) && (0.0..1.0).contains(&y));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn check_every_one_is_the_exact_count() {
+        for y in 0..=120 {
+            for x in 0..=160 {
+                let (c_re, c_im) = (-2.2 + x as f64 * 0.02, -1.2 + y as f64 * 0.02);
+                let exact = mandelbrot(c_re, c_im, 1000, 4.0);
+                assert_eq!(mandelbrot_coarse(c_re, c_im, 1000, 4.0, 1), exact);
+                for k in [4, 16, 64] {
+                    let coarse = mandelbrot_coarse(c_re, c_im, 1000, 4.0, k);
+                    assert_eq!(coarse == 1000, exact == 1000, "at {c_re} {c_im}");
+                    assert!(coarse >= exact && coarse < exact + k, "at {c_re} {c_im}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn cancel_flag_stops_a_tile_between_rows() {
+        // Boundary-heavy, so each row takes a while even in a debug build
+        let params = RenderParams {
+            viewport: Viewport {
+                center_re: -0.745,
+                center_im: 0.11,
+                scale: 1e-5,
+            },
+            ..params(256, 256, 20_000)
+        };
+        let cancel = AtomicBool::new(true);
+        let cutoff = Cutoff {
+            deadline: None,
+            cancel: Some(&cancel),
+        };
+        let mut out = TileData::default();
+        render_tile_until(&params, 0, 0, 256, 256, cutoff, &mut out);
+        assert!(matches!(&out, TileData::Iterations(counts) if counts.is_empty()));
+
+        cancel.store(false, Ordering::Relaxed);
+        let rows = std::thread::scope(|scope| {
+            scope.spawn(|| {
+                std::thread::sleep(std::time::Duration::from_millis(20));
+                cancel.store(true, Ordering::Relaxed);
+            });
+            render_tile_until(&params, 0, 0, 256, 256, cutoff, &mut out);
+            let TileData::Iterations(counts) = &out else {
+                unreachable!("iteration output always renders counts")
+            };
+            assert_eq!(counts.len() % 256, 0);
+            counts.len() / 256
+        });
+        assert!(rows < 256, "rendered all {rows} rows");
+    }
+
+    #[test]
+    fn angle_is_the_argument_of_the_escaping_z() {
+        let params = RenderParams {
+            output: OutputMode::Angle,
+            ..params(8, 8, 500)
+        };
+        let radius_sq = params.escape_radius_sq();
+        for (c_re, c_im) in [(0.5, 0.5), (-2.1, 0.3), (0.3, -0.6)] {
+            let (mut z_re, mut z_im) = (0.0f64, 0.0f64);
+            while z_re * z_re + z_im * z_im <= radius_sq {
+                (z_re, z_im) = (z_re * z_re - z_im * z_im + c_re, 2.0 * z_re * z_im + c_im);
+            }
+            let angle = angle_value(&params, c_re, c_im);
+            assert!(
+                (angle - z_im.atan2(z_re) as f32).abs() < 1e-6,
+                "at {c_re} {c_im}: {angle}"
+            );
+        }
+        // c = -2.1 + 0.3i is past the radius after a single step
+        assert_eq!(angle_value(&params, -2.1, 0.3), 0.3f64.atan2(-2.1) as f32);
+        assert!(angle_value(&params, -0.2, 0.1).is_nan());
+        assert!(angle_value(&params, -1.0, 0.0).is_nan());
+    }
+
+    #[test]
+    fn julia_angles_stay_in_range() {
+        let params = RenderParams {
+            output: OutputMode::Angle,
+            kind: FractalKind::Julia {
+                c_re: -0.8,
+                c_im: 0.156,
+            },
+            ..params(64, 48, 300)
+        };
+        let mut out = TileData::default();
+        render_tile(&params, 0, 0, 64, 48, &mut out);
+        let TileData::Values(angles) = &out else {
+            unreachable!("angle output renders f32 values")
+        };
+        let escaped = angles.iter().filter(|a| !a.is_nan()).count();
+        assert!(escaped > 64 * 48 / 2);
+        let pi = std::f32::consts::PI;
+        assert!(
+            angles
+                .iter()
+                .all(|&a| a.is_nan() || (-pi..=pi).contains(&a))
+        );
+    }
+
+    #[test]
+    fn angle_rejects_supersampling() {
+        let params = RenderParams {
+            output: OutputMode::Angle,
+            samples_per_axis: 2,
+            ..params(8, 8, 100)
+        };
+        assert!(params.validate().is_err());
+    }
+
+    #[test]
+    fn packed_tiles_fill_their_layout() {
+        let counts = TileData::Iterations((0..24 * 10).collect());
+        for (max_iter, format, bytes_per_pixel) in [
+            (200, TileFormat::Uint8, 1),
+            (1000, TileFormat::Uint16, 2),
+            (100_000, TileFormat::Uint32, 4),
+        ] {
+            let (data, emitted) = counts.to_emitted(None, max_iter, true, Encoding::Raw);
+            assert_eq!(emitted, format);
+            let layout = TileLayout::new(emitted, 24, 10);
+            assert_eq!(layout.bytes_per_pixel, bytes_per_pixel);
+            assert_eq!(layout.stride, layout.width * layout.bytes_per_pixel);
+            let TileData::Bytes(bytes) = data else {
+                unreachable!("contiguous counts are packed to bytes")
+            };
+            assert_eq!(bytes.len(), (layout.stride * layout.height) as usize);
+        }
+        for (format, bytes_per_pixel) in [(TileFormat::Float32, 4), (TileFormat::Float64, 8)] {
+            assert_eq!(TileLayout::new(format, 7, 3).stride, 7 * bytes_per_pixel);
+        }
+        let rgba = TileLayout::new(TileFormat::Rgba8, 16, 16);
+        assert_eq!((rgba.format, rgba.stride), (FORMAT_RGBA8, 64));
+    }
+
+    #[test]
+    fn pixel_center_shifts_by_half_a_pixel() {
+        let viewport = Viewport {
+            center_re: -1.0,
+            center_im: -0.25,
+            scale: 0.5,
+        };
+        let corner = RenderParams::plain_mandelbrot(4, 4, 100, viewport);
+        let center = RenderParams {
+            pixel_center: true,
+            ..corner
+        };
+        assert_eq!(corner.to_complex(0.0, 0.0), (-2.0, -1.25));
+        assert_eq!(center.to_complex(0.0, 0.0), (-1.75, -1.0));
+        assert_eq!(center.to_complex(3.0, 3.0), (-0.25, 0.5));
+        // Centers of a 4x4 image sit symmetrically about the view center
+        let (re0, im0) = center.to_complex(0.0, 0.0);
+        let (re3, im3) = center.to_complex(3.0, 3.0);
+        assert_eq!(((re0 + re3) / 2.0, (im0 + im3) / 2.0), (-1.0, -0.25));
+
+        // Supersampled pixels already spread their samples around the center
+        let supersampled = |pixel_center| RenderParams {
+            samples_per_axis: 2,
+            pixel_center,
+            ..corner
+        };
+        assert_eq!(
+            supersampled(true).pixel_offset(1.0, 2.0),
+            supersampled(false).pixel_offset(1.0, 2.0)
+        );
+    }
+
+    #[test]
+    fn coarse_then_resume_equals_a_full_render() {
+        let counts = |out: &TileData| {
+            let TileData::Iterations(counts) = out else {
+                unreachable!("iteration output always renders counts")
+            };
+            counts.clone()
+        };
+        let julia = RenderParams {
+            kind: FractalKind::Julia {
+                c_re: -0.8,
+                c_im: 0.156,
+            },
+            ..params(64, 48, 2000)
+        };
+        let cubic = RenderParams {
+            power: 3,
+            ..params(64, 48, 2000)
+        };
+        for params in [params(64, 48, 2000), julia, cubic] {
+            for (tile, coarse_iter) in [
+                ((0, 0, 64, 48), 50),
+                ((16, 8, 20, 30), 1),
+                ((0, 0, 64, 48), 2000),
+            ] {
+                let (x, y, w, h) = tile;
+                let mut full = TileData::default();
+                render_tile(&params, x, y, w, h, &mut full);
+
+                let (mut state, mut coarse) = (Vec::new(), TileData::default());
+                render_tile_coarse(&params, x, y, w, h, coarse_iter, &mut state, &mut coarse);
+                // Still-bounded pixels show as interior after the coarse pass
+                for (c, f) in counts(&coarse).iter().zip(counts(&full)) {
+                    assert!(*c == f || (*c == 2000 && f >= coarse_iter));
+                }
+                let mut resumed = TileData::default();
+                resume_tile(
+                    &params,
+                    x,
+                    y,
+                    w,
+                    h,
+                    &mut state,
+                    Cutoff::default(),
+                    &mut resumed,
+                );
+                assert_eq!(
+                    counts(&resumed),
+                    counts(&full),
+                    "tile {tile:?} from {coarse_iter}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn non_finite_viewports_are_rejected() {
+        let with_viewport = |center_re: f64, center_im: f64, scale: f64| RenderParams {
+            viewport: Viewport {
+                center_re,
+                center_im,
+                scale,
+            },
+            ..params(64, 48, 100)
+        };
+        assert!(with_viewport(-0.5, 0.0, 0.01).validate().is_ok());
+        for (center_re, center_im, scale) in [
+            (-0.5, 0.0, 0.0),
+            (-0.5, 0.0, -0.01),
+            (-0.5, 0.0, f64::NAN),
+            (-0.5, 0.0, f64::INFINITY),
+            (f64::INFINITY, 0.0, 0.01),
+            (0.0, f64::NAN, 0.01),
+            // Finite center and scale, but the corners overflow
+            (1.7e308, 0.0, 1e307),
+        ] {
+            let params = with_viewport(center_re, center_im, scale);
+            assert!(
+                params.validate().is_err(),
+                "{center_re} {center_im} {scale}"
+            );
+        }
+        let julia = RenderParams {
+            kind: FractalKind::Julia {
+                c_re: f64::NAN,
+                c_im: 0.0,
+            },
+            ..params(64, 48, 100)
+        };
+        assert!(julia.validate().is_err());
+    }
+
+    #[test]
+    fn final_z_is_the_last_orbit_point() {
+        let params = RenderParams {
+            pixel_center: true,
+            ..RenderParams::plain_mandelbrot(
+                3,
+                1,
+                20,
+                Viewport {
+                    center_re: 0.0,
+                    center_im: 0.1,
+                    scale: 1.0,
+                },
+            )
+        };
+        // Pixel centers at c = -1 + 0.1i, 0.1i and 1 + 0.1i
+        let TileData::Values(pairs) = final_z_tile(&params, (0, 0, 3, 1), 1, false) else {
+            unreachable!("final_z is f32 pairs")
+        };
+        assert_eq!(pairs.len(), 6);
+        for (pixel, c_re) in [-1.0, 0.0, 1.0].into_iter().enumerate() {
+            let (mut z_re, mut z_im) = (0.0f64, 0.0f64);
+            for _ in 0..20 {
+                if z_re * z_re + z_im * z_im > 4.0 {
+                    break;
+                }
+                (z_re, z_im) = (z_re * z_re - z_im * z_im + c_re, 2.0 * z_re * z_im + 0.1);
+            }
+            assert_eq!(
+                (pairs[2 * pixel], pairs[2 * pixel + 1]),
+                (z_re as f32, z_im as f32),
+                "at {c_re}"
+            );
+        }
+        // 1 + 0.1i escapes at 1.99 + 0.3i, past |z| = 2
+        assert_eq!((pairs[4], pairs[5]), (1.99f64 as f32, 0.3f64 as f32));
+
+        let TileData::Bytes(bytes) = final_z_tile(&params, (0, 0, 3, 1), 1, true) else {
+            unreachable!("contiguous final_z is bytes")
+        };
+        let packed: Vec<u8> = pairs.iter().flat_map(|v| v.to_ne_bytes()).collect();
+        assert_eq!(bytes, packed);
+    }
+
+    #[test]
+    fn stripe_freq_zero_is_a_constant_field() {
+        let julia = FractalKind::Julia {
+            c_re: -0.8,
+            c_im: 0.156,
+        };
+        for kind in [FractalKind::Mandelbrot, julia] {
+            let constant = RenderParams {
+                output: OutputMode::Stripe,
+                stripe_freq: 0.0,
+                kind,
+                ..params(64, 48, 200)
+            };
+            let mut out = TileData::default();
+            render_tile(&constant, 0, 0, 64, 48, &mut out);
+            let TileData::Values(values) = &out else {
+                unreachable!("stripe output renders f32 values")
+            };
+            assert_eq!(values.len(), 64 * 48);
+            assert!(values.iter().all(|&v| v == 0.5), "{kind:?}");
+
+            let striped = RenderParams {
+                stripe_freq: 5.0,
+                ..constant
+            };
+            render_tile(&striped, 0, 0, 64, 48, &mut out);
+            let TileData::Values(values) = &out else {
+                unreachable!("stripe output renders f32 values")
+            };
+            assert!(values.iter().all(|v| (0.0..=1.0).contains(v)));
+            assert!(values.iter().any(|&v| v < 0.25) && values.iter().any(|&v| v > 0.75));
+        }
+    }
+
+    #[test]
+    fn interior_tile_is_uniform() {
+        let interior = Viewport {
+            center_re: -0.2,
+            center_im: 0.0,
+            scale: 1e-3,
+        };
+        let params = RenderParams::plain_mandelbrot(32, 32, 200, interior);
+        let mut out = TileData::default();
+        render_tile(&params, 0, 0, 32, 32, &mut out);
+        assert_eq!(out.uniform_value(), Some(200.0));
+        let TileData::Iterations(first) = out.first_pixel() else {
+            unreachable!("iteration output always renders counts")
+        };
+        assert_eq!(first, [200]);
+
+        let edge = Viewport {
+            center_re: -0.75,
+            center_im: 0.0,
+            scale: 0.1,
+        };
+        render_tile(
+            &RenderParams::plain_mandelbrot(32, 32, 200, edge),
+            0,
+            0,
+            32,
+            32,
+            &mut out,
+        );
+        assert_eq!(out.uniform_value(), None);
+        assert_eq!(TileData::Rgba(vec![0; 4]).uniform_value(), None);
+    }
+
+    #[test]
+    fn tile_into_a_larger_buffer_matches_render_tile() {
+        let viewport = Viewport {
+            center_re: -0.6,
+            center_im: 0.0,
+            scale: 0.08,
+        };
+        let params = RenderParams::plain_mandelbrot(40, 30, 300, viewport);
+        let (stride, offset) = (57, 3 * 57 + 5);
+        // An inner tile and one clipped by the image's corner
+        for tile in [(8, 4, 16, 12), (32, 24, 16, 12)] {
+            let mut buf = vec![u16::MAX; 30 * stride];
+            let len = tile_into_len(&params, tile, offset, stride).unwrap();
+            assert!(len <= buf.len());
+            render_tile_into(&params, tile, &mut buf, offset, stride);
+
+            let (x, y, w, h) = tile;
+            let mut out = TileData::default();
+            render_tile(&params, x, y, w, h, &mut out);
+            let TileData::Iterations(counts) = &out else {
+                unreachable!("iteration output always renders counts")
+            };
+            let (cols, rows) = (w.min(40 - x), h.min(30 - y));
+            assert_eq!(counts.len(), cols * rows);
+            for (i, &cell) in buf.iter().enumerate() {
+                let (row, col) = (
+                    i.wrapping_sub(offset) / stride,
+                    i.wrapping_sub(offset) % stride,
+                );
+                if i >= offset && row < rows && col < cols {
+                    assert_eq!(cell as u32, counts[row * cols + col]);
+                } else {
+                    assert_eq!(cell, u16::MAX, "wrote outside the tile at {i}");
+                }
+            }
+        }
+        assert_eq!(tile_into_len(&params, (8, 4, 16, 12), 0, 15), None);
+        assert_eq!(tile_into_len(&params, (40, 0, 16, 12), 0, 1), Some(0));
+    }
+
+    #[test]
+    fn mirrored_rows_render_the_same() {
+        let viewport = Viewport {
+            center_re: -0.6,
+            center_im: 0.0,
+            scale: 0.1,
+        };
+        let row = |params: &RenderParams, y| {
+            let mut out = TileData::default();
+            render_tile(params, 0, y, params.width, 1, &mut out);
+            out.checksum()
+        };
+        for height in [8, 9, 30, 31] {
+            for (pixel_center, flip_y) in
+                [(false, false), (true, false), (false, true), (true, true)]
+            {
+                let params = RenderParams {
+                    pixel_center,
+                    flip_y,
+                    ..RenderParams::plain_mandelbrot(24, height, 200, viewport)
+                };
+                let case = format!("height {height}, pixel_center {pixel_center}, flip_y {flip_y}");
+                // Only the row on the far edge from the axis lacks a mirror,
+                // and only at pixel corners
+                let edge = if flip_y { height - 1 } else { 0 };
+                for y in 0..height {
+                    let Some(source) = params.mirror_row(y) else {
+                        assert!(!pixel_center && y == edge, "{case}: row {y} has no mirror");
+                        continue;
+                    };
+                    assert_eq!(params.mirror_row(source), Some(y), "{case}");
+                    assert_eq!(row(&params, y), row(&params, source), "{case}: row {y}");
+                }
+                // Unflipped, as `render_image` renders, the rows down to the
+                // axis hold a mirror of every row past them
+                let rendered = (0..height)
+                    .take_while(|&y| params.mirror_row(y).is_none_or(|source| source >= y))
+                    .count();
+                if !flip_y {
+                    assert!(rendered <= height / 2 + 1, "{case}");
+                    assert!(
+                        (rendered..height)
+                            .all(|y| params.mirror_row(y).is_some_and(|source| source < rendered))
+                    );
+                }
+            }
+        }
+        let off_axis = Viewport {
+            center_im: 0.05,
+            ..viewport
+        };
+        assert_eq!(
+            RenderParams::plain_mandelbrot(24, 8, 200, off_axis).mirror_row(3),
+            None
+        );
+    }
+
+    #[test]
+    fn z_order_lands_pixels_row_major() {
+        let viewport = Viewport {
+            center_re: -0.745,
+            center_im: 0.11,
+            scale: 2e-4,
+        };
+        let row_major = RenderParams::plain_mandelbrot(45, 38, 300, viewport);
+        let z_order = RenderParams {
+            traversal: Traversal::ZOrder,
+            ..row_major
+        };
+        // Square, wide, tall, thin, non-power-of-two and clipped tiles
+        let tiles = [
+            (0, 0, 16, 16),
+            (3, 5, 13, 7),
+            (0, 0, 7, 29),
+            (2, 1, 1, 9),
+            (32, 24, 17, 17),
+            (0, 0, 45, 38),
+        ];
+        for tile in tiles {
+            for stride in [1, 2, 3] {
+                let coords = |params: &RenderParams| {
+                    let mut out = Vec::new();
+                    map_pixels(params, tile, stride, Cutoff::default(), &mut out, |x, y| {
+                        (x, y)
+                    });
+                    out
+                };
+                assert_eq!(
+                    coords(&z_order),
+                    coords(&row_major),
+                    "tile {tile:?}, stride {stride}"
+                );
+            }
+            for output in [
+                OutputMode::Iterations,
+                OutputMode::Smooth,
+                OutputMode::Distance,
+                OutputMode::DoubleSmooth,
+            ] {
+                let render = |params: &RenderParams| {
+                    let params = RenderParams { output, ..*params };
+                    let mut out = TileData::default();
+                    let (x, y, w, h) = tile;
+                    render_tile(&params, x, y, w, h, &mut out);
+                    (out.len(), out.checksum())
+                };
+                assert_eq!(
+                    render(&z_order),
+                    render(&row_major),
+                    "tile {tile:?}, {output:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn zero_max_iter_is_rejected() {
+        assert!(check_max_iter(0).is_err());
+        assert!(check_max_iter(1).is_ok());
+        assert!(params(64, 48, 0).validate().is_err());
+        assert!(params(64, 48, 1).validate().is_ok());
+    }
+
+    #[test]
+    fn normalized_interior_is_exactly_one() {
+        let interior = Viewport {
+            center_re: -0.2,
+            center_im: 0.0,
+            scale: 1e-3,
+        };
+        for max_iter in [50, 1000, 12345] {
+            for output in [OutputMode::Smooth, OutputMode::DoubleSmooth] {
+                let params = RenderParams {
+                    output,
+                    ..RenderParams::plain_mandelbrot(16, 16, max_iter, interior)
+                };
+                let mut out = TileData::default();
+                render_tile(&params, 0, 0, 16, 16, &mut out);
+                let normalized = out.normalized(max_iter);
+                assert_eq!(
+                    normalized.uniform_value(),
+                    Some(1.0),
+                    "{output:?} at {max_iter}"
+                );
+            }
+        }
+        // Exterior values scale down, and interior ones marked negative go to 1
+        let TileData::Values(values) = TileData::Values(vec![0.0, 25.0, 50.0, -1.0]).normalized(50)
+        else {
+            unreachable!("values stay values")
+        };
+        assert_eq!(values, [0.0, 0.5, 1.0, 1.0]);
+        let TileData::Doubles(doubles) = TileData::Doubles(vec![12.5, 80.0, -3.0]).normalized(50)
+        else {
+            unreachable!("doubles stay doubles")
+        };
+        assert_eq!(doubles, [0.25, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn periodicity_keeps_counts_identical() {
+        let views = [
+            // Period-3 bulb, the default full view and the seahorse valley
+            (FractalKind::Mandelbrot, -0.122, 0.745, 2e-3),
+            (FractalKind::Mandelbrot, -0.75, 0.0, 0.05),
+            (FractalKind::Mandelbrot, -0.745, 0.11, 2e-4),
+            // The rabbit
+            (
+                FractalKind::Julia {
+                    c_re: -0.123,
+                    c_im: 0.745,
+                },
+                0.0,
+                0.0,
+                0.05,
+            ),
+        ];
+        for (kind, center_re, center_im, scale) in views {
+            let viewport = Viewport {
+                center_re,
+                center_im,
+                scale,
+            };
+            let plain = RenderParams {
+                kind,
+                ..RenderParams::plain_mandelbrot(48, 32, 2000, viewport)
+            };
+            let periodic = RenderParams {
+                periodicity: true,
+                ..plain
+            };
+            let mut out = TileData::default();
+            render_tile(&plain, 0, 0, 48, 32, &mut out);
+            let expected = out.checksum();
+            render_tile(&periodic, 0, 0, 48, 32, &mut out);
+            assert_eq!(
+                out.checksum(),
+                expected,
+                "{kind:?} at {center_re} {center_im}"
+            );
+        }
+    }
+
+    #[test]
+    fn f32_precision_only_applies_to_quadratic_counts() {
+        let f32 = RenderParams {
+            precision: Precision::F32,
+            ..params(64, 48, 100)
+        };
+        let julia = FractalKind::Julia {
+            c_re: -0.8,
+            c_im: 0.156,
+        };
+        for ok in [f32, RenderParams { kind: julia, ..f32 }] {
+            assert!(ok.validate().is_ok());
+        }
+        let rejected = [
+            RenderParams {
+                output: OutputMode::Smooth,
+                ..f32
+            },
+            RenderParams {
+                output: OutputMode::Distance,
+                ..f32
+            },
+            RenderParams {
+                kind: FractalKind::BurningShip,
+                ..f32
+            },
+            RenderParams { power: 3, ..f32 },
+            RenderParams {
+                samples_per_axis: 2,
+                ..f32
+            },
+            RenderParams {
+                interior: true,
+                ..f32
+            },
+        ];
+        for params in rejected {
+            assert!(params.validate().is_err(), "{params:?}");
+        }
+        assert!(ReferenceOrbit::check_params(&f32).is_err());
+    }
+
+    #[test]
+    fn periodicity_only_applies_to_f64_quadratic_counts() {
+        let periodic = RenderParams {
+            periodicity: true,
+            ..params(64, 48, 100)
+        };
+        let julia = FractalKind::Julia {
+            c_re: -0.8,
+            c_im: 0.156,
+        };
+        for ok in [
+            periodic,
+            RenderParams {
+                kind: julia,
+                ..periodic
+            },
+        ] {
+            assert!(ok.validate().is_ok());
+        }
+        let rejected = [
+            RenderParams {
+                precision: Precision::F32,
+                ..periodic
+            },
+            RenderParams {
+                precision: Precision::DoubleDouble,
+                ..periodic
+            },
+            RenderParams {
+                output: OutputMode::Smooth,
+                ..periodic
+            },
+            RenderParams {
+                kind: FractalKind::BurningShip,
+                ..periodic
+            },
+            RenderParams {
+                power: 3,
+                ..periodic
+            },
+            RenderParams {
+                samples_per_axis: 2,
+                ..periodic
+            },
+        ];
+        for params in rejected {
+            assert!(params.validate().is_err(), "{params:?}");
+        }
+        assert!(ReferenceOrbit::check_params(&periodic).is_err());
+    }
+
+    #[test]
+    fn check_every_only_applies_to_f64_mandelbrot_counts() {
+        let coarse = RenderParams {
+            check_every: 8,
+            ..params(64, 48, 100)
+        };
+        assert!(coarse.validate().is_ok());
+        let rejected = [
+            RenderParams {
+                kind: FractalKind::Julia {
+                    c_re: -0.8,
+                    c_im: 0.156,
+                },
+                ..coarse
+            },
+            RenderParams {
+                precision: Precision::F32,
+                ..coarse
+            },
+            RenderParams {
+                periodicity: true,
+                ..coarse
+            },
+            RenderParams { power: 3, ..coarse },
+            RenderParams {
+                output: OutputMode::Distance,
+                ..coarse
+            },
+            RenderParams {
+                samples_per_axis: 2,
+                ..coarse
+            },
+        ];
+        for params in rejected {
+            assert!(params.validate().is_err(), "{params:?}");
+        }
+        assert!(ReferenceOrbit::check_params(&coarse).is_err());
+    }
+}
@@ -0,0 +1,57 @@
+use ::png::{BitDepth, ColorType, Encoder, EncodingError};
+
+/// Encodes 8-bit RGBA pixels, row-major and `width * height * 4` bytes
+/// long, as a PNG file with the `png` crate's default compression.
+pub fn encode_rgba(width: u32, height: u32, rgba: &[u8]) -> Result<Vec<u8>, EncodingError> {
+    let mut png = Vec::new();
+    let mut encoder = Encoder::new(&mut png, width, height);
+    encoder.set_color(ColorType::Rgba);
+    encoder.set_depth(BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(rgba)?;
+    writer.finish()?;
+    Ok(png)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::png::Decoder;
+    use std::io::Cursor;
+
+    fn decode(png: &[u8]) -> (::png::OutputInfo, Vec<u8>) {
+        let mut reader = Decoder::new(Cursor::new(png)).read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size().unwrap()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        pixels.truncate(info.buffer_size());
+        (info, pixels)
+    }
+
+    #[test]
+    fn decodes_to_the_same_pixels() {
+        let (width, height) = (7, 5);
+        let rgba: Vec<u8> = (0..width * height * 4)
+            .map(|i| (i * 37 % 256) as u8)
+            .collect();
+        let (info, pixels) = decode(&encode_rgba(width, height, &rgba).unwrap());
+        assert_eq!((info.width, info.height), (width, height));
+        assert_eq!(
+            (info.color_type, info.bit_depth),
+            (ColorType::Rgba, BitDepth::Eight)
+        );
+        assert_eq!(pixels, rgba);
+    }
+
+    #[test]
+    fn compresses_plain_images() {
+        let rgba = [10, 20, 30, 255].repeat(256 * 256);
+        let png = encode_rgba(256, 256, &rgba).unwrap();
+        assert!(png.len() < rgba.len() / 50);
+        assert_eq!(decode(&png).1, rgba);
+    }
+
+    #[test]
+    fn rejects_a_short_buffer() {
+        assert!(encode_rgba(4, 4, &[0; 4 * 4 * 4 - 1]).is_err());
+    }
+}
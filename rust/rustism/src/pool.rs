@@ -0,0 +1,98 @@
+#[cfg(debug_assertions)]
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, Mutex, mpsc},
+    thread::{self, JoinHandle},
+};
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+// Threads every pool has spawned, see `_threads_spawned`
+#[cfg(debug_assertions)]
+pub static THREADS_SPAWNED: AtomicUsize = AtomicUsize::new(0);
+
+/// Threads the machine can run at once, hyperthreads included, or 1 if
+/// that can't be determined. What `num_threads = 0` stands for.
+pub fn available_threads() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Fixed set of worker threads that run queued jobs until the pool is dropped.
+pub struct ThreadPool {
+    sender: Option<mpsc::Sender<Job>>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl ThreadPool {
+    pub fn new(size: usize) -> Self {
+        Self::with_setup(size, |_| ())
+    }
+
+    /// Like `new`, but thread `i` is pinned to the `i`-th core the process
+    /// may run on, wrapping around when there are more threads than cores.
+    /// Threads stay unpinned where the cores can't be listed.
+    #[cfg(feature = "pin-threads")]
+    pub fn pinned(size: usize) -> Self {
+        let cores = core_affinity::get_core_ids().unwrap_or_default();
+        Self::with_setup(size, move |i| {
+            if !cores.is_empty() {
+                core_affinity::set_for_current(cores[i % cores.len()]);
+            }
+        })
+    }
+
+    // Each thread runs `setup` with its index before taking jobs
+    fn with_setup(size: usize, setup: impl Fn(usize) + Send + Sync + 'static) -> Self {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let setup = Arc::new(setup);
+        #[cfg(debug_assertions)]
+        THREADS_SPAWNED.fetch_add(size.max(1), Ordering::Relaxed);
+
+        let threads = (0..size.max(1))
+            .map(|i| {
+                let (receiver, setup) = (Arc::clone(&receiver), Arc::clone(&setup));
+                thread::spawn(move || {
+                    setup(i);
+                    loop {
+                        // The lock is only held while waiting for the next job
+                        let job = receiver.lock().unwrap().recv();
+                        match job {
+                            // A panicking job must not take the thread down with it
+                            Ok(job) => drop(panic::catch_unwind(AssertUnwindSafe(job))),
+                            Err(_) => break,
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        Self {
+            sender: Some(sender),
+            threads,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.threads.len()
+    }
+
+    pub fn execute(&self, job: impl FnOnce() + Send + 'static) {
+        if let Some(sender) = &self.sender {
+            sender
+                .send(Box::new(job))
+                .expect("pool threads exited early");
+        }
+    }
+}
+
+impl Drop for ThreadPool {
+    fn drop(&mut self) {
+        // Closing the channel lets each thread finish its queue and exit
+        drop(self.sender.take());
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
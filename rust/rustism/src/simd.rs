@@ -0,0 +1,140 @@
+use crate::model::{Cutoff, RenderParams, in_main_body};
+
+/// Pixels iterated together. Plain arrays of this width compile to vector
+/// instructions on stable, without `std::simd` or an extra crate.
+pub const LANES: usize = 4;
+
+/// `mandelbrot` for `LANES` points at once. Escaped lanes keep iterating
+/// but are masked out of the result, so the counts match the scalar
+/// version exactly.
+#[inline(always)]
+pub fn mandelbrot_lanes(
+    c_re: [f64; LANES],
+    c_im: [f64; LANES],
+    max_iter: u32,
+    radius_sq: f64,
+) -> [u32; LANES] {
+    let mut counts = [max_iter; LANES];
+    let mut active: [bool; LANES] =
+        std::array::from_fn(|l| !(radius_sq >= 4.0 && in_main_body(c_re[l], c_im[l])));
+
+    let mut z_re = [0.0f64; LANES];
+    let mut z_im = [0.0f64; LANES];
+
+    for i in 0..max_iter {
+        if !active.contains(&true) {
+            break;
+        }
+
+        let mut re2 = [0.0f64; LANES];
+        let mut im2 = [0.0f64; LANES];
+        for l in 0..LANES {
+            re2[l] = z_re[l] * z_re[l];
+            im2[l] = z_im[l] * z_im[l];
+        }
+
+        for l in 0..LANES {
+            if active[l] && re2[l] + im2[l] > radius_sq {
+                counts[l] = i;
+                active[l] = false;
+            }
+        }
+
+        for l in 0..LANES {
+            z_im[l] = 2.0 * z_re[l] * z_im[l] + c_im[l];
+            z_re[l] = re2[l] - im2[l] + c_re[l];
+        }
+    }
+
+    counts
+}
+
+/// Appends the escape counts of a tile to `out`, walked like `map_pixels`,
+/// `LANES` pixels of a row at a time. Only valid for the plain f64 power-2
+/// Mandelbrot set.
+pub fn mandelbrot_tile(
+    params: &RenderParams,
+    (tile_x, tile_y, tile_w, tile_h): (usize, usize, usize, usize),
+    cutoff: Cutoff<'_>,
+    out: &mut Vec<u32>,
+) {
+    let cols = tile_w.min(params.width.saturating_sub(tile_x));
+    let rows = tile_h.min(params.height.saturating_sub(tile_y));
+    let radius_sq = params.escape_radius_sq();
+
+    out.reserve(cols * rows);
+
+    for dy in 0..rows {
+        if cutoff.reached() {
+            break;
+        }
+        let y = params.row_position(tile_y + dy);
+
+        for x0 in (0..cols).step_by(LANES) {
+            let n = LANES.min(cols - x0);
+
+            // Pad a short last chunk by repeating its final pixel
+            let mut c_re = [0.0; LANES];
+            let mut c_im = [0.0; LANES];
+            for l in 0..LANES {
+                let x = (tile_x + x0 + l.min(n - 1)) as f64;
+                (c_re[l], c_im[l]) = params.to_complex(x, y);
+            }
+
+            out.extend_from_slice(&mandelbrot_lanes(c_re, c_im, params.max_iter, radius_sq)[..n]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Viewport, escape_count, mandelbrot, map_pixels};
+
+    fn scalar_tile(params: &RenderParams, tile: (usize, usize, usize, usize)) -> Vec<u32> {
+        let mut out = Vec::new();
+        map_pixels(params, tile, 1, Cutoff::default(), &mut out, |x, y| {
+            let (re, im) = params.to_complex(x, y);
+            escape_count(params, re, im)
+        });
+        out
+    }
+
+    #[test]
+    fn lanes_match_scalar() {
+        let c_re = [-0.75, 0.3, -1.25, 0.26];
+        let c_im = [0.1, 0.5, 0.02, 0.0];
+        let counts = mandelbrot_lanes(c_re, c_im, 500, 4.0);
+        for l in 0..LANES {
+            assert_eq!(counts[l], mandelbrot(c_re[l], c_im[l], 500, 4.0));
+        }
+    }
+
+    #[test]
+    fn full_tiles_match_scalar() {
+        // Odd widths leave a short last chunk of lanes in every row
+        for (width, height, tile_w, tile_h) in [(64, 48, 64, 48), (61, 37, 13, 9), (7, 5, 3, 2)] {
+            for (flip_y, pixel_center) in [(false, false), (true, true)] {
+                let params = RenderParams {
+                    flip_y,
+                    pixel_center,
+                    ..RenderParams::plain_mandelbrot(
+                        width,
+                        height,
+                        300,
+                        Viewport {
+                            center_re: -0.745,
+                            center_im: 0.11,
+                            scale: 0.02 / width as f64,
+                        },
+                    )
+                };
+                for tile in crate::model::tiles_for(width, height, tile_w, tile_h) {
+                    let mut vectorized = Vec::new();
+                    mandelbrot_tile(&params, tile, Cutoff::default(), &mut vectorized);
+                    assert_eq!(vectorized, scalar_tile(&params, tile), "tile {tile:?}");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,18 @@
+/// Writes a `rustism event=<name> key=value ...` line to stderr when built
+/// with the `trace` feature. Without it, only the values are evaluated, so
+/// they count as used either way.
+macro_rules! trace_event {
+    ($event:literal $(, $key:ident = $value:expr)* $(,)?) => {{
+        #[cfg(feature = "trace")]
+        eprintln!(
+            concat!("rustism event=", $event $(, " ", stringify!($key), "={:?}")*)
+            $(, $value)*
+        );
+        #[cfg(not(feature = "trace"))]
+        {
+            $(let _ = &$value;)*
+        }
+    }};
+}
+
+pub(crate) use trace_event;
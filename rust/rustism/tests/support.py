@@ -0,0 +1,51 @@
+import rustism
+
+# Record fields that depend on scheduling rather than on the render
+TIMING = ("thread_id", "start_time_ms", "duration_ms")
+
+
+def sequential(**kwargs):
+    """Runs `rustism.sequential`, returning its records and emitted tiles."""
+    tiles = []
+    kwargs.setdefault("time_limit_ms", 60_000)
+    records = rustism.sequential(emit_tile=tiles.append, **kwargs)
+    return records, tiles
+
+
+def concurrent(num_threads=2, **kwargs):
+    """Runs `rustism.concurrent`, returning its records and emitted tiles."""
+    tiles = []
+    kwargs.setdefault("time_limit_ms", 60_000)
+    records = rustism.concurrent(emit_tile=tiles.append, num_threads=num_threads, **kwargs)
+    return records, tiles
+
+
+def without_timing(records):
+    return [{k: v for k, v in record.items() if k not in TIMING} for record in records]
+
+
+def tile_key(tile):
+    return (tile["tile_x"], tile["tile_y"], tile["tile_w"], tile["tile_h"])
+
+
+# Typecodes of native-endian counts, for `memoryview.cast`
+TYPECODES = {"uint8": "B", "uint16": "H", "uint32": "I"}
+
+
+def image_rows(data, dtype, width):
+    """Rows of a packed count image such as `render_image` returns."""
+    counts = memoryview(data).cast(TYPECODES[dtype]).tolist()
+    return [counts[at : at + width] for at in range(0, len(counts), width)]
+
+
+def assemble(tiles, width, height):
+    """Stitches the final (non-preview) tiles of a raw render into rows."""
+    image = [[None] * width for _ in range(height)]
+    for tile in tiles:
+        if tile["preview"]:
+            continue
+        data = list(tile["data"])
+        for row in range(tile["rows"]):
+            for col in range(tile["cols"]):
+                image[tile["tile_y"] + row][tile["tile_x"] + col] = data[row * tile["cols"] + col]
+    return image
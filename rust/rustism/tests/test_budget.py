@@ -0,0 +1,44 @@
+import pytest
+import rustism
+
+from support import concurrent, sequential, tile_key, without_timing
+
+VIEW = dict(width=256, height=256, tile_w=32, tile_h=32, max_iter=500)
+
+
+def completed(records):
+    return sorted((r["task_id"], r["pixels_computed"]) for r in records)
+
+
+@pytest.mark.parametrize("budget", [500_000, 3_000_000])
+def test_budget_completes_the_same_tiles_every_run(budget):
+    expected = completed(sequential(iteration_budget=budget, **VIEW)[0])
+    assert 0 < len(expected) < 64
+    for num_threads in (1, 4, 16):
+        for _ in range(2):
+            records, _ = concurrent(num_threads=num_threads, iteration_budget=budget, **VIEW)
+            assert completed(records) == expected
+
+
+def test_deterministic_budget_renders_match():
+    runs = [
+        concurrent(num_threads=4, iteration_budget=1_000_000, deterministic=True, **VIEW)
+        for _ in range(2)
+    ]
+    (records_a, tiles_a), (records_b, tiles_b) = runs
+    assert without_timing(records_a) == without_timing(records_b)
+    assert [t["data"] for t in sorted(tiles_a, key=tile_key)] == [
+        t["data"] for t in sorted(tiles_b, key=tile_key)
+    ]
+
+
+def test_renderer_budget_matches_sequential():
+    renderer = rustism.Renderer(num_threads=4)
+    records = renderer.render(emit_tile=None, time_limit_ms=60_000, iteration_budget=500_000, **VIEW)
+    assert completed(records) == completed(sequential(iteration_budget=500_000, **VIEW)[0])
+    assert renderer.tiles_completed == len(records)
+
+
+def test_budget_needs_escape_values():
+    with pytest.raises(ValueError, match="iteration_budget needs iteration or smooth output"):
+        concurrent(iteration_budget=1000, output_mode=rustism.OUTPUT_DISTANCE, **VIEW)
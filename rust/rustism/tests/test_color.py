@@ -0,0 +1,105 @@
+import sys
+
+import pytest
+import rustism
+
+from support import concurrent, sequential, tile_key
+
+VIEW = dict(width=96, height=64, tile_w=32, tile_h=32, max_iter=200, palette=rustism.PALETTE_CLASSIC)
+
+
+def colored(render, **kw):
+    return [tile["data"] for tile in sorted(render(**VIEW, **kw)[1], key=tile_key)]
+
+
+@pytest.mark.parametrize("render", [sequential, concurrent])
+def test_whole_turns_of_palette_offset_color_alike(render):
+    still = colored(render)
+    assert colored(render, palette_offset=0.0) == still
+    assert colored(render, palette_offset=1.0) == still
+    assert colored(render, palette_offset=3.0) == still
+    assert colored(render, palette_offset=0.5) != still
+    assert colored(render, palette_offset=-0.5) == colored(render, palette_offset=0.5)
+
+
+def test_palette_offset_needs_a_palette():
+    view = dict(VIEW, palette=None)
+    with pytest.raises(ValueError, match="palette_offset only applies with a palette"):
+        sequential(palette_offset=0.5, **view)
+
+
+def test_cycling_a_cached_frame_skips_the_iteration():
+    renderer = rustism.Renderer(num_threads=2, cache_size=16)
+    first = renderer.render(emit_tile=None, time_limit_ms=60_000, **VIEW)
+    cycled = []
+    records = renderer.render(emit_tile=cycled.append, time_limit_ms=60_000, palette_offset=0.5, **VIEW)
+    assert not any(record["cached"] for record in first)
+    assert all(record["cached"] for record in records)
+    assert [tile["data"] for tile in sorted(cycled, key=tile_key)] == colored(sequential, palette_offset=0.5)
+
+
+@pytest.mark.parametrize("flip_y", [False, True])
+@pytest.mark.parametrize("tiles_re, tiles_im, hits", [(1, 0, 4), (0, 1, 3), (-1, 1, 2)])
+def test_panning_by_whole_tiles_reuses_the_cached_ones(flip_y, tiles_re, tiles_im, hits):
+    # A power of two scale, so the pan moves tile origins exactly
+    scale = 1 / 64
+    view = dict(VIEW, scale=scale, flip_y=flip_y)
+    panned = dict(
+        view,
+        center_re=-0.5 + tiles_re * VIEW["tile_w"] * scale,
+        center_im=tiles_im * VIEW["tile_h"] * scale,
+    )
+    renderer = rustism.Renderer(num_threads=2, cache_size=16)
+    renderer.render(emit_tile=None, time_limit_ms=60_000, center_re=-0.5, center_im=0.0, **view)
+    tiles = []
+    records = renderer.render(emit_tile=tiles.append, time_limit_ms=60_000, **panned)
+    assert sum(record["cached"] for record in records) == hits
+    _, fresh = sequential(**panned)
+    assert [tile["data"] for tile in sorted(tiles, key=tile_key)] == [
+        tile["data"] for tile in sorted(fresh, key=tile_key)
+    ]
+
+
+def lookup(lut, counts):
+    return [tuple(lut[3 * count : 3 * count + 3]) for count in counts]
+
+
+def rgb(pixels):
+    # RGBA pixels are native-endian uint32s, R in the lowest byte in memory
+    return [tuple(pixel.to_bytes(4, sys.byteorder)[:3]) for pixel in pixels]
+
+
+def test_colormap_maps_known_counts():
+    lut = rustism.colormap(1000, palette=rustism.PALETTE_GRAYSCALE)
+    assert len(lut) == 1001 * 3
+    assert lookup(lut, [0, 500, 1000]) == [(0, 0, 0), (128, 128, 128), (0, 0, 0)]
+    assert rustism.colormap(1000, palette=rustism.PALETTE_GRAYSCALE) is lut
+
+
+@pytest.mark.parametrize(
+    "kw",
+    [
+        dict(palette=rustism.PALETTE_CLASSIC),
+        dict(palette=rustism.PALETTE_FIRE, exposure=1.5, gamma=0.8, palette_offset=0.3),
+        dict(palette=lambda t: (int(255 * t), 0, 255 - int(255 * t))),
+    ],
+)
+def test_colormap_colors_counts_as_a_render_does(kw):
+    view = dict(width=64, height=48, tile_w=64, tile_h=48, max_iter=200)
+    lut = rustism.colormap(200, **kw)
+    counts = list(sequential(**view)[1][0]["data"])
+    colored = list(sequential(**view, **kw)[1][0]["data"])
+    assert lookup(lut, counts) == rgb(colored)
+
+
+@pytest.mark.parametrize(
+    "kw, message",
+    [
+        (dict(palette=99), "palette"),
+        (dict(exposure=0.0), "exposure"),
+        (dict(palette_offset=float("nan")), "palette_offset must be finite"),
+    ],
+)
+def test_colormap_rejects_bad_arguments(kw, message):
+    with pytest.raises(ValueError, match=message):
+        rustism.colormap(100, **kw)
@@ -0,0 +1,207 @@
+import time
+
+import pytest
+import rustism
+
+from support import concurrent, sequential, tile_key, without_timing
+
+VIEW = dict(width=100, height=70, tile_w=32, tile_h=24, max_iter=100)
+
+
+@pytest.mark.parametrize("num_threads", [1, 3, 8])
+def test_task_ids_match_sequential(num_threads):
+    seq_records, seq_tiles = sequential(**VIEW)
+    con_records, con_tiles = concurrent(num_threads=num_threads, **VIEW)
+
+    assert [r["task_id"] for r in con_records] == [r["task_id"] for r in seq_records]
+    assert [tile_key(r) for r in con_records] == [tile_key(r) for r in seq_records]
+    assert sorted((t["task_id"], tile_key(t)) for t in con_tiles) == sorted(
+        (t["task_id"], tile_key(t)) for t in seq_tiles
+    )
+
+
+@pytest.mark.parametrize("num_threads", [0, 64])
+def test_thread_count_edge_cases_render_every_tile(num_threads):
+    seq_records, seq_tiles = sequential(**VIEW)
+    con_records, con_tiles = concurrent(num_threads=num_threads, **VIEW)
+
+    assert [tile_key(r) for r in con_records] == [tile_key(r) for r in seq_records]
+    assert sorted((t["task_id"], list(t["data"])) for t in con_tiles) == sorted(
+        (t["task_id"], list(t["data"])) for t in seq_tiles
+    )
+
+
+def test_zero_tile_size_is_rejected():
+    with pytest.raises(ValueError):
+        concurrent(num_threads=0, **dict(VIEW, tile_w=0))
+
+
+@pytest.mark.parametrize("render", [sequential, concurrent])
+def test_pixels_computed_counts_rendered_pixels(render):
+    records, tiles = render(**VIEW)
+    assert sum(r["pixels_computed"] for r in records) == VIEW["width"] * VIEW["height"]
+    rendered = {t["task_id"]: len(list(t["data"])) for t in tiles}
+    assert all(r["pixels_computed"] == rendered[r["task_id"]] for r in records)
+
+
+@pytest.mark.parametrize("render", [sequential, concurrent])
+def test_partial_edge_tiles_report_their_pixels(render):
+    records, _ = render(width=100, height=100, tile_w=32, tile_h=32, max_iter=50)
+    by_position = {(r["tile_x"], r["tile_y"]): r["pixels_computed"] for r in records}
+    assert by_position[(0, 0)] == 32 * 32
+    assert by_position[(96, 0)] == 4 * 32
+    assert by_position[(0, 96)] == 32 * 4
+    assert by_position[(96, 96)] == 4 * 4
+
+
+def test_recommended_threads_is_positive():
+    threads = rustism.recommended_threads()
+    assert isinstance(threads, int) and threads >= 1
+
+
+def test_checksums_are_stable_across_renderers():
+    seq_records, _ = sequential(with_checksum=True, **VIEW)
+    again, _ = sequential(with_checksum=True, **VIEW)
+    con_records, _ = concurrent(num_threads=3, with_checksum=True, **VIEW)
+
+    checksums = [r["checksum"] for r in seq_records]
+    assert all(isinstance(checksum, int) for checksum in checksums)
+    assert len(set(checksums)) > 1
+    assert [r["checksum"] for r in again] == checksums
+    assert [r["checksum"] for r in con_records] == checksums
+    assert all(r["checksum"] is None for r in sequential(**VIEW)[0])
+
+
+@pytest.mark.parametrize("render", [sequential, concurrent])
+def test_dirty_rect_renders_only_overlapping_tiles(render):
+    # Tiles are 32x24, so (32, 24, 32, 24) is exactly the second tile of the second row
+    records, tiles = render(dirty_x=32, dirty_y=24, dirty_w=32, dirty_h=24, **VIEW)
+    assert [tile_key(r) for r in records] == [(32, 24, 32, 24)]
+    assert [tile_key(t) for t in tiles] == [(32, 24, 32, 24)]
+
+    records, _ = render(dirty_x=60, dirty_y=20, dirty_w=10, dirty_h=10, **VIEW)
+    assert sorted(tile_key(r)[:2] for r in records) == [(32, 0), (32, 24), (64, 0), (64, 24)]
+
+
+def test_partial_dirty_rect_is_rejected():
+    with pytest.raises(ValueError):
+        sequential(dirty_x=0, dirty_y=0, dirty_w=8, **VIEW)
+
+
+def test_records_come_back_without_emit_tile():
+    expected, _ = sequential(**VIEW)
+    common = dict(VIEW, emit_tile=None, time_limit_ms=60_000)
+    assert without_timing(rustism.sequential(**common)) == without_timing(expected)
+    assert without_timing(rustism.concurrent(num_threads=3, **common)) == without_timing(expected)
+
+
+@pytest.mark.parametrize("render", [sequential, concurrent])
+def test_should_cancel_interrupts_the_tile_in_flight(render):
+    start = time.perf_counter()
+    records, tiles = render(
+        width=600, height=600, tile_w=600, tile_h=600, max_iter=200_000,
+        should_cancel=lambda: time.perf_counter() - start > 0.05,
+    )
+    assert time.perf_counter() - start < 0.5
+    assert records == []
+    assert len(tiles) == 1 and tiles[0]["partial"]
+    assert 0 < tiles[0]["rows"] < 600
+
+
+def test_cost_balance_only_reorders_tiles():
+    view = dict(width=256, height=256, tile_w=32, tile_h=32, max_iter=2000, center_re=-0.6, scale=2.8 / 256)
+    _, plain = concurrent(num_threads=1, emit_during=True, **view)
+    _, tiles = concurrent(num_threads=1, emit_during=True, cost_balance=True, **view)
+    assert [t["data"] for t in sorted(tiles, key=tile_key)] == [t["data"] for t in sorted(plain, key=tile_key)]
+    # A single worker emits them in the order it takes them
+    assert [t["task_id"] for t in plain] == list(range(64))
+    assert [t["task_id"] for t in tiles] != list(range(64))
+
+
+@pytest.mark.parametrize("focus", [dict(focus_x=16, focus_y=48), dict(focus_x=90)])
+def test_focus_renders_the_nearest_tiles_first(focus):
+    view = dict(width=96, height=96, tile_w=32, tile_h=32, max_iter=100)
+    _, tiles = concurrent(num_threads=1, emit_during=True, **focus, **view)
+    fx, fy = focus["focus_x"], focus.get("focus_y", 48)
+
+    def distance_sq(task_id):
+        x, y = task_id % 3 * 32 + 16, task_id // 3 * 32 + 16
+        return (x - fx) ** 2 + (y - fy) ** 2
+
+    # Equally near tiles go in task_id order
+    assert [tile["task_id"] for tile in tiles] == sorted(range(9), key=lambda task_id: (distance_sq(task_id), task_id))
+
+
+def renderer(**kwargs):
+    tiles = []
+    records = rustism.Renderer(num_threads=2).render(emit_tile=tiles.append, time_limit_ms=60_000, **kwargs)
+    return records, tiles
+
+
+@pytest.mark.parametrize("render", [sequential, concurrent, renderer])
+@pytest.mark.parametrize("tile_w, tile_h", [(0, 4), (4, 0)])
+def test_zero_tile_sizes_raise_value_error(render, tile_w, tile_h):
+    with pytest.raises(ValueError, match="tile dimensions must be positive"):
+        render(width=10, height=10, tile_w=tile_w, tile_h=tile_h, max_iter=50)
+
+
+@pytest.mark.parametrize("render", [sequential, concurrent, renderer])
+@pytest.mark.parametrize("width, height", [(0, 0), (10, 0), (0, 10)])
+def test_empty_images_render_nothing(render, width, height):
+    assert render(width=width, height=height, tile_w=4, tile_h=4, max_iter=50) == ([], [])
+    records, summary = render(width=width, height=height, tile_w=4, tile_h=4, max_iter=50, summary=True)[0]
+    assert records == [] and (summary.tiles_rendered, summary.total_pixels) == (0, 0)
+
+
+def test_empty_render_image():
+    data, dtype = rustism.render_image(0, 5, 50)
+    assert data == b"" and dtype == "uint8"
+
+
+PIN_THREADS = rustism.capabilities()["pin_threads"]
+
+
+@pytest.mark.skipif(not PIN_THREADS, reason="built without the pin-threads feature")
+@pytest.mark.parametrize("num_threads", [1, 3, 16])
+def test_pinned_threads_render_the_same_tiles(num_threads):
+    _, plain = concurrent(num_threads=num_threads, **VIEW)
+    _, pinned = concurrent(num_threads=num_threads, pin_threads=True, **VIEW)
+    assert [t["data"] for t in sorted(pinned, key=tile_key)] == [t["data"] for t in sorted(plain, key=tile_key)]
+
+
+@pytest.mark.skipif(PIN_THREADS, reason="built with the pin-threads feature")
+def test_pin_threads_needs_the_feature():
+    with pytest.raises(ValueError, match="pin-threads feature"):
+        concurrent(pin_threads=True, **VIEW)
+
+
+threads_spawned = getattr(rustism, "_threads_spawned", None)
+needs_hook = pytest.mark.skipif(threads_spawned is None, reason="needs a debug build")
+# Two 32x32 tiles
+INLINE_VIEW = dict(width=64, height=32, tile_w=32, tile_h=32, max_iter=100)
+
+
+def progress(fraction):
+    pass
+
+
+@needs_hook
+def test_fewer_tiles_than_threads_render_without_spawning():
+    before = threads_spawned()
+    records, tiles = concurrent(num_threads=16, **INLINE_VIEW)
+    assert threads_spawned() == before
+
+    # on_progress needs the calling thread free, so it keeps the pool
+    pool_records, pool_tiles = concurrent(num_threads=16, on_progress=progress, **INLINE_VIEW)
+    assert threads_spawned() == before + 16
+    seq_records, seq_tiles = sequential(**INLINE_VIEW)
+    for got, got_tiles in [(records, tiles), (pool_records, pool_tiles)]:
+        assert [tile_key(r) for r in sorted(got, key=tile_key)] == [tile_key(r) for r in seq_records]
+        assert [t["data"] for t in sorted(got_tiles, key=tile_key)] == [t["data"] for t in seq_tiles]
+
+
+@needs_hook
+def test_more_tiles_than_threads_spawn_the_pool():
+    before = threads_spawned()
+    concurrent(num_threads=1, **INLINE_VIEW)
+    assert threads_spawned() == before + 1
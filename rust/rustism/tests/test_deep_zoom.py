@@ -0,0 +1,88 @@
+import pytest
+import rustism
+
+from support import assemble, concurrent, sequential, tile_key
+
+# A well-known deep zoom target in the seahorse valley
+CENTER_RE = "-0.743643887037158704752191506114774"
+CENTER_IM = "0.131825904205311970493132056385139"
+VIEW = dict(width=32, height=32, tile_w=32, tile_h=32, max_iter=30_000, scale=1e-20)
+
+
+def distinct_counts(center_re, center_im, **kwargs):
+    image = assemble(sequential(center_re=center_re, center_im=center_im, **VIEW, **kwargs)[1], 32, 32)
+    return {count for row in image for count in row}
+
+
+def test_perturbation_resolves_detail_past_f64():
+    # At 1e-20 per pixel every f64 pixel coordinate rounds to the center
+    assert len(distinct_counts(float(CENTER_RE), float(CENTER_IM))) == 1
+    assert len(distinct_counts(CENTER_RE, CENTER_IM)) > 100
+    assert len(distinct_counts(0.0, 0.0, deep_center=(CENTER_RE, CENTER_IM))) > 100
+
+
+def test_string_center_matches_float_center():
+    view = dict(width=24, height=16, tile_w=8, tile_h=8, max_iter=200, scale=1e-3)
+    _, from_strings = sequential(center_re="-0.745", center_im="0.11", **view)
+    _, from_floats = sequential(center_re=-0.745, center_im=0.11, **view)
+    assert [list(t["data"]) for t in from_strings] == [list(t["data"]) for t in from_floats]
+
+
+@pytest.mark.parametrize("coordinate", ["", "abc", "1.2.3", "1e", "0x10", "nan", "inf"])
+def test_malformed_coordinates_are_rejected(coordinate):
+    with pytest.raises(ValueError, match="invalid coordinate"):
+        sequential(width=8, height=8, tile_w=8, tile_h=8, max_iter=50, center_re=coordinate, center_im="0")
+
+
+@pytest.mark.parametrize("coordinate", ["1e400", "-1e309"])
+def test_coordinates_past_f64_are_rejected(coordinate):
+    view = dict(width=8, height=8, tile_w=8, tile_h=8, max_iter=50)
+    with pytest.raises(ValueError, match="past the range of f64"):
+        sequential(center_re=coordinate, center_im="0", **view)
+    with pytest.raises(ValueError, match="past the range of f64"):
+        rustism.compute_reference_orbit(coordinate, "0", 50)
+
+
+def test_tiny_exponents_parse_as_tiny_coordinates():
+    for coordinate in ("1e-310", "100000000000000000000e-320"):
+        orbit = rustism.compute_reference_orbit(coordinate, "0", 50)
+        assert len(orbit) == 51
+
+
+def test_view_around_a_reference_orbit_is_validated():
+    orbit = rustism.compute_reference_orbit("1.7e308", "0", 50)
+    with pytest.raises(ValueError, match="past the range of f64"):
+        sequential(width=8, height=8, tile_w=8, tile_h=8, max_iter=50, scale=1e307, reference_orbit=orbit)
+
+
+def test_deep_zoom_needs_counts_past_u16():
+    view = dict(VIEW, max_iter=200_000, scale=1e-25)
+    image = assemble(sequential(center_re=CENTER_RE, center_im=CENTER_IM, **view)[1], 32, 32)
+    counts = [count for row in image for count in row]
+    assert len(set(counts)) > 100
+    assert max(counts) > 65_535 and 200_000 not in counts
+
+    # Capped at the old u16 limit those pixels all look interior
+    image = assemble(sequential(center_re=CENTER_RE, center_im=CENTER_IM, **dict(view, max_iter=65_535))[1], 32, 32)
+    assert sum(count == 65_535 for row in image for count in row) == sum(count > 65_535 for count in counts)
+
+
+@pytest.mark.parametrize("render", [sequential, concurrent])
+def test_precomputed_orbit_matches_an_inline_one(render):
+    view = dict(VIEW, tile_w=16, tile_h=16)
+    orbit = rustism.compute_reference_orbit(CENTER_RE, CENTER_IM, VIEW["max_iter"])
+    _, inline = render(center_re=CENTER_RE, center_im=CENTER_IM, **view)
+    _, cached = render(reference_orbit=orbit, **view)
+    assert [t["data"] for t in sorted(cached, key=tile_key)] == [t["data"] for t in sorted(inline, key=tile_key)]
+    # And once more from the same orbit
+    _, again = render(reference_orbit=orbit, **view)
+    assert [t["data"] for t in sorted(again, key=tile_key)] == [t["data"] for t in sorted(cached, key=tile_key)]
+
+
+def test_renderer_reuses_a_precomputed_orbit():
+    orbit = rustism.compute_reference_orbit(CENTER_RE, CENTER_IM, VIEW["max_iter"])
+    renderer = rustism.Renderer(num_threads=2)
+    tiles = []
+    renderer.render(emit_tile=tiles.append, time_limit_ms=60_000, reference_orbit=orbit, **VIEW)
+    _, inline = sequential(center_re=CENTER_RE, center_im=CENTER_IM, **VIEW)
+    assert [t["data"] for t in tiles] == [t["data"] for t in inline]
@@ -0,0 +1,49 @@
+import pytest
+import rustism
+
+from support import concurrent, without_timing
+
+VIEW = dict(width=200, height=150, tile_w=24, tile_h=20, max_iter=300, with_checksum=True, deterministic=True)
+
+
+def emitted(tiles):
+    return [(t["task_id"], t["depth"], t["data"]) for t in tiles]
+
+
+@pytest.mark.parametrize(
+    "kw",
+    [
+        dict(),
+        dict(contiguous=True, smooth=True),
+        dict(palette=rustism.PALETTE_CLASSIC, cost_balance=True, contiguous=True),
+        dict(output_mode=rustism.OUTPUT_DOUBLE_SMOOTH, samples_per_axis=2, contiguous=True),
+    ],
+)
+def test_thread_count_changes_nothing(kw):
+    runs = [concurrent(num_threads=num_threads, **VIEW, **kw) for num_threads in (1, 4, 16)]
+    (records, tiles), *others = runs
+    for other_records, other_tiles in others:
+        assert emitted(other_tiles) == emitted(tiles)
+        assert without_timing(other_records) == without_timing(records)
+    assert [t["task_id"] for t in tiles] == sorted(t["task_id"] for t in tiles)
+
+
+def test_histograms_match_across_thread_counts():
+    histograms = [concurrent(num_threads=n, histogram=True, **VIEW)[0][1] for n in (1, 4, 16)]
+    assert histograms[0] == histograms[1] == histograms[2]
+    assert sum(histograms[0]) == 200 * 150
+
+
+@pytest.mark.parametrize(
+    "kw",
+    [
+        dict(subdivide_after_ms=5),
+        dict(emit_during=True),
+        dict(max_pending_tiles=4),
+        dict(preview_stride=4),
+        dict(coarse_iter=20),
+    ],
+)
+def test_scheduling_options_are_rejected(kw):
+    with pytest.raises(ValueError, match="deterministic renders can't use"):
+        concurrent(**VIEW, **kw)
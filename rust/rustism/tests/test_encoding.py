@@ -0,0 +1,56 @@
+import array
+
+import pytest
+import rustism
+
+from support import concurrent, sequential, tile_key
+
+VIEW = dict(width=100, height=70, tile_w=32, tile_h=24)
+
+
+@pytest.mark.parametrize("render", [sequential, concurrent])
+@pytest.mark.parametrize("max_iter", [200, 1000, 100_000])
+def test_delta_tiles_decode_to_the_raw_counts(render, max_iter):
+    _, raw = render(max_iter=max_iter, **VIEW)
+    _, delta = render(max_iter=max_iter, encoding=rustism.ENCODING_DELTA_VARINT, **VIEW)
+    raw, delta = sorted(raw, key=tile_key), sorted(delta, key=tile_key)
+    assert [rustism.decode_counts(tile["data"]) for tile in delta] == [list(tile["data"]) for tile in raw]
+    assert all(tile["encoding"] == rustism.ENCODING_DELTA_VARINT for tile in delta)
+    assert sum(len(tile["data"]) for tile in delta) < 100 * 70 * 2
+
+
+def test_decode_rejects_truncated_data():
+    with pytest.raises(ValueError, match="data is not delta-encoded counts"):
+        rustism.decode_counts(b"\x06\xff")
+
+
+# A view well inside the cardioid, and one across the boundary
+INTERIOR = dict(width=32, height=32, tile_w=16, tile_h=16, center_re=-0.2, center_im=0.0, scale=1e-3)
+EDGE = dict(VIEW, center_re=-0.745, center_im=0.11, scale=4e-4)
+
+
+@pytest.mark.parametrize("render", [sequential, concurrent])
+@pytest.mark.parametrize("max_iter", [50, 1000, 12_345])
+@pytest.mark.parametrize("output_mode", [rustism.OUTPUT_SMOOTH, rustism.OUTPUT_DOUBLE_SMOOTH])
+def test_normalized_interior_is_exactly_one(render, max_iter, output_mode):
+    kw = dict(max_iter=max_iter, output_mode=output_mode, encoding=rustism.ENCODING_NORMALIZED)
+    _, tiles = render(**INTERIOR, **kw)
+    assert len(tiles) == 4 and all(list(tile["data"]) == [1.0] * 16 * 16 for tile in tiles)
+    assert all(tile["encoding"] == rustism.ENCODING_NORMALIZED for tile in tiles)
+
+
+@pytest.mark.parametrize("max_iter", [50, 1000])
+def test_normalized_values_are_the_smooth_ones_over_max_iter(max_iter):
+    _, raw = sequential(max_iter=max_iter, smooth=True, **EDGE)
+    _, normalized = sequential(max_iter=max_iter, smooth=True, encoding=rustism.ENCODING_NORMALIZED, **EDGE)
+    for raw_tile, tile in zip(raw, normalized):
+        # Divided as f32, which rounding the exact quotient gives too
+        expected = array.array("f", [min(value / max_iter, 1.0) for value in raw_tile["data"]])
+        assert list(tile["data"]) == list(expected)
+        assert all(0.0 <= value <= 1.0 for value in tile["data"])
+
+
+@pytest.mark.parametrize("kw", [dict(), dict(smooth=True, palette=rustism.PALETTE_FIRE)])
+def test_normalized_needs_uncolored_smooth_output(kw):
+    with pytest.raises(ValueError, match="normalized encoding needs uncolored smooth output"):
+        sequential(max_iter=50, encoding=rustism.ENCODING_NORMALIZED, **INTERIOR, **kw)
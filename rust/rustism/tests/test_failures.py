@@ -0,0 +1,35 @@
+import pytest
+import rustism
+
+from support import concurrent
+
+# Debug builds only
+panic_at_task = getattr(rustism, "_panic_at_task", None)
+needs_hook = pytest.mark.skipif(panic_at_task is None, reason="needs a debug build")
+
+
+def injected_panic(render, task_id=3):
+    panic_at_task(task_id)
+    try:
+        with pytest.raises(RuntimeError, match="a render worker panicked: injected at task 3"):
+            render()
+    finally:
+        panic_at_task(None)
+
+
+# 16 tiles on 16 threads stay on the calling thread, on 4 they use the pool
+@needs_hook
+@pytest.mark.parametrize("num_threads", [16, 4])
+def test_worker_panic_raises(num_threads):
+    view = dict(width=64, height=64, tile_w=16, tile_h=16, max_iter=50)
+    injected_panic(lambda: concurrent(num_threads=num_threads, **view))
+    records, _ = concurrent(num_threads=num_threads, **view)
+    assert len(records) == 16
+
+
+@needs_hook
+def test_renderer_survives_a_worker_panic():
+    renderer = rustism.Renderer(num_threads=4)
+    view = dict(width=256, height=256, tile_w=64, tile_h=64, max_iter=50, time_limit_ms=60_000)
+    injected_panic(lambda: renderer.render(emit_tile=None, **view))
+    assert len(renderer.render(emit_tile=None, **view)) == 16
@@ -0,0 +1,58 @@
+import cmath
+import math
+
+import pytest
+import rustism
+
+# Points around the main cardioid, c = e^it / 2 - e^2it / 4, and the period-2 bulb
+CARDIOID = [cmath.exp(1j * t) / 2 - cmath.exp(2j * t) / 4 for t in (2 * math.pi * k / 64 for k in range(64))]
+BULB = [-1 + cmath.exp(2j * math.pi * k / 32) / 4 for k in range(32)]
+
+
+def bounds(width, height, center_re, center_im, scale):
+    half_w, half_h = width * scale / 2, height * scale / 2
+    return center_re - half_w, center_re + half_w, center_im - half_h, center_im + half_h
+
+
+def contains(view, points):
+    re0, re1, im0, im1 = view
+    return all(re0 <= c.real <= re1 and im0 <= c.imag <= im1 for c in points)
+
+
+@pytest.mark.parametrize("width, height", [(160, 120), (64, 64), (300, 100), (90, 200)])
+def test_view_contains_the_main_cardioid(width, height):
+    center_re, center_im, scale = rustism.auto_fit(width, height)
+    view = bounds(width, height, center_re, center_im, scale)
+    assert contains(view, CARDIOID + BULB)
+    # And hugs the set rather than the whole radius-2 square
+    re0, re1, im0, im1 = view
+    assert re1 - re0 < 4 or im1 - im0 < 4
+    assert center_im == pytest.approx(0.0, abs=scale)
+
+
+def test_margin_widens_the_view():
+    tight = rustism.auto_fit(160, 120, margin=0.0)
+    wide = rustism.auto_fit(160, 120, margin=0.2)
+    assert wide[2] == pytest.approx(tight[2] * 1.4)
+    assert contains(bounds(160, 120, *tight), CARDIOID)
+
+
+def test_sparse_julia_set_gets_the_whole_square():
+    center_re, center_im, scale = rustism.auto_fit(
+        100, 100, fractal_kind=rustism.FRACTAL_JULIA, julia_re=10.0, min_iter=16, margin=0.0
+    )
+    assert center_re == pytest.approx(0.0, abs=1e-12) and center_im == pytest.approx(0.0, abs=1e-12)
+    assert scale == pytest.approx(4.0 / 100)
+
+
+@pytest.mark.parametrize(
+    "kw, match",
+    [
+        (dict(width=0), "width and height must be positive"),
+        (dict(samples=1), "samples must be at least 2"),
+        (dict(margin=-0.1), "margin must not be negative"),
+    ],
+)
+def test_bad_arguments_are_rejected(kw, match):
+    with pytest.raises(ValueError, match=match):
+        rustism.auto_fit(**dict(dict(width=100, height=100), **kw))
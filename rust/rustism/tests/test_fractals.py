@@ -0,0 +1,158 @@
+import math
+
+import pytest
+import rustism
+
+from support import assemble, sequential
+
+
+def burning_ship(c_re, c_im, max_iter):
+    z_re = z_im = 0.0
+    for i in range(max_iter):
+        re2, im2 = z_re * z_re, z_im * z_im
+        if re2 + im2 > 4.0:
+            return i
+        z_im = 2.0 * abs(z_re * z_im) + c_im
+        z_re = re2 - im2 + c_re
+    return max_iter
+
+
+def test_burning_ship_renders_the_small_ship():
+    # The miniature ship on the antenna, left of the main hull
+    width, height, max_iter = 48, 32, 200
+    center_re, center_im, scale = -1.762, -0.028, 0.0015
+    view = dict(
+        width=width,
+        height=height,
+        tile_w=16,
+        tile_h=16,
+        max_iter=max_iter,
+        center_re=center_re,
+        center_im=center_im,
+        scale=scale,
+    )
+    image = assemble(sequential(fractal_kind=rustism.FRACTAL_BURNING_SHIP, **view)[1], width, height)
+
+    expected = [
+        [
+            burning_ship(
+                center_re + (x - width / 2) * scale, center_im + (y - height / 2) * scale, max_iter
+            )
+            for x in range(width)
+        ]
+        for y in range(height)
+    ]
+    assert image == expected
+    counts = [count for row in image for count in row]
+    assert max_iter in counts and min(counts) < 10
+    assert image != assemble(sequential(**view)[1], width, height)
+
+
+def escape_count(c_re, c_im, max_iter, **kwargs):
+    view = dict(width=1, height=1, tile_w=1, tile_h=1, max_iter=max_iter, pixel_center=True)
+    _, tiles = sequential(center_re=c_re, center_im=c_im, scale=1.0, **view, **kwargs)
+    return list(tiles[0]["data"])[0]
+
+
+@pytest.mark.parametrize(
+    "c_re, c_im, expected",
+    [
+        # 0 is fixed, and i cycles i -> 0 -> i
+        (0.0, 0.0, 100),
+        (0.0, 1.0, 100),
+        # 1 -> 2 -> 9, with |2|^2 = 4 not yet past the bailout
+        (1.0, 0.0, 3),
+        (-1.0, 0.0, 3),
+        # 0.5, 0.625, 0.744, 0.912, 1.259, 2.494
+        (0.5, 0.0, 6),
+    ],
+)
+def test_cubic_multibrot_points(c_re, c_im, expected):
+    assert escape_count(c_re, c_im, 100, power=3) == expected
+
+
+@pytest.mark.parametrize("power", [0, 1])
+def test_degenerate_powers_are_rejected(power):
+    with pytest.raises(ValueError):
+        escape_count(0.0, 0.0, 100, power=power)
+
+
+def mandelbrot(c_re, c_im, max_iter):
+    z_re = z_im = 0.0
+    for i in range(max_iter):
+        re2, im2 = z_re * z_re, z_im * z_im
+        if re2 + im2 > 4.0:
+            return i
+        z_im = 2.0 * z_re * z_im + c_im
+        z_re = re2 - im2 + c_re
+    return max_iter
+
+
+def test_escape_radius_two_is_the_classic_bailout():
+    width, height, max_iter, scale = 40, 30, 100, 0.08
+    view = dict(width=width, height=height, tile_w=16, tile_h=16, max_iter=max_iter, scale=scale)
+    expected = [
+        [mandelbrot(-0.75 + (x - width / 2) * scale, (y - height / 2) * scale, max_iter) for x in range(width)]
+        for y in range(height)
+    ]
+    assert assemble(sequential(**view)[1], width, height) == expected
+    assert assemble(sequential(escape_radius=2.0, **view)[1], width, height) == expected
+    assert assemble(sequential(escape_radius=3.0, **view)[1], width, height) != expected
+
+
+@pytest.mark.parametrize("escape_radius", [0.0, -1.0])
+def test_escape_radius_must_be_positive(escape_radius):
+    with pytest.raises(ValueError):
+        escape_count(0.0, 0.0, 100, escape_radius=escape_radius)
+
+
+def escape_angle(c_re, c_im, max_iter):
+    z = 0j
+    for _ in range(max_iter):
+        if abs(z) > 2.0:
+            return math.atan2(z.imag, z.real)
+        z = z * z + complex(c_re, c_im)
+    return math.nan
+
+
+@pytest.mark.parametrize("c_re, c_im", [(0.5, 0.5), (-2.1, 0.3), (0.3, -0.6), (-0.2, 0.1)])
+def test_angle_output_is_the_argument_at_escape(c_re, c_im):
+    angle = escape_count(c_re, c_im, 500, output_mode=rustism.OUTPUT_ANGLE)
+    expected = escape_angle(c_re, c_im, 500)
+    if math.isnan(expected):
+        assert math.isnan(angle)
+    else:
+        assert angle == pytest.approx(expected, abs=1e-6)
+
+
+# Escape radius 2^16 of smooth and stripe output, squared
+SMOOTH_RADIUS_SQ = 2.0**32
+
+
+def stripe_average(c, max_iter, freq=5.0, radius_sq=SMOOTH_RADIUS_SQ):
+    z, total, last, n = 0j, 0.0, 0.0, 0
+    for _ in range(max_iter):
+        norm_sq = abs(z) ** 2
+        if norm_sq > radius_sq:
+            mean = total / n
+            if n == 1:
+                return mean
+            past = min(max(math.log(math.log(norm_sq) / math.log(radius_sq)) / math.log(2), 0.0), 1.0)
+            return mean + ((total - last) / (n - 1) - mean) * past
+        z = z * z + c
+        last = 0.5 + 0.5 * math.sin(freq * math.atan2(z.imag, z.real))
+        total += last
+        n += 1
+    return total / n
+
+
+@pytest.mark.parametrize("c", [0.3 + 0.5j, -0.75 + 0.1j, -0.1 + 0.2j])
+def test_stripe_output_matches_a_reference(c):
+    value = escape_count(c.real, c.imag, 500, output_mode=rustism.OUTPUT_STRIPE)
+    assert value == pytest.approx(stripe_average(c, 500), abs=1e-6)
+
+
+def test_stripe_freq_zero_is_constant():
+    view = dict(width=32, height=24, tile_w=32, tile_h=24, max_iter=100, output_mode=rustism.OUTPUT_STRIPE)
+    assert set(sequential(stripe_freq=0.0, **view)[1][0]["data"]) == {0.5}
+    assert len(set(sequential(**view)[1][0]["data"])) > 100
@@ -0,0 +1,35 @@
+import threading
+
+import pytest
+
+from support import concurrent, sequential
+
+# One tile, so the callback takes the GIL once, after all the work is done
+VIEW = dict(width=300, height=200, tile_w=300, tile_h=200, max_iter=3000)
+
+
+class Counter(threading.Thread):
+    def __init__(self):
+        super().__init__(daemon=True)
+        self.count = 0
+        self.stop = threading.Event()
+
+    def run(self):
+        while not self.stop.is_set():
+            self.count += 1
+
+
+@pytest.mark.parametrize("render", [sequential, concurrent])
+def test_python_threads_run_during_render(render):
+    counter = Counter()
+    counter.start()
+    try:
+        while counter.count == 0:
+            pass
+        before = counter.count
+        render(**VIEW)
+        during = counter.count - before
+    finally:
+        counter.stop.set()
+        counter.join()
+    assert during > 10_000
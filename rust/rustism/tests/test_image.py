@@ -0,0 +1,62 @@
+import array
+
+import pytest
+import rustism
+
+from support import assemble, image_rows, sequential
+
+WIDTH, HEIGHT = 70, 45
+VIEW = dict(center_re=-0.745, center_im=0.11, scale=4e-4)
+
+
+@pytest.mark.parametrize("max_iter, dtype, itemsize", [(200, "uint8", 1), (1000, "uint16", 2), (70_000, "uint32", 4)])
+@pytest.mark.parametrize("num_threads", [1, 3])
+def test_image_matches_tile_by_tile_render(max_iter, dtype, itemsize, num_threads):
+    data, got_dtype = rustism.render_image(WIDTH, HEIGHT, max_iter, num_threads=num_threads, tile_w=16, tile_h=16, **VIEW)
+    assert got_dtype == dtype
+    assert len(data) == WIDTH * HEIGHT * itemsize
+
+    _, tiles = sequential(width=WIDTH, height=HEIGHT, tile_w=32, tile_h=8, max_iter=max_iter, **VIEW)
+    assert image_rows(data, dtype, WIDTH) == assemble(tiles, WIDTH, HEIGHT)
+
+
+# An inner tile and one clipped by the image's corner
+@pytest.mark.parametrize("tile_x, tile_y", [(16, 12), (64, 36)])
+def test_tile_into_a_larger_buffer_matches_a_standalone_tile(tile_x, tile_y):
+    stride, offset, unset = 90, 3 * 90 + 7, 0xFFFF
+    buffer = array.array("H", [unset]) * (HEIGHT * stride)
+    rustism.render_tile_into(buffer, WIDTH, HEIGHT, 1000, tile_x, tile_y, 16, 12, stride=stride, offset=offset, **VIEW)
+
+    _, tiles = sequential(width=WIDTH, height=HEIGHT, tile_w=16, tile_h=12, max_iter=1000, **VIEW)
+    (tile,) = [t for t in tiles if (t["tile_x"], t["tile_y"]) == (tile_x, tile_y)]
+    rows, cols = tile["rows"], tile["cols"]
+    region = [buffer[offset + row * stride + col] for row in range(rows) for col in range(cols)]
+    assert region == list(tile["data"])
+    assert buffer.count(unset) == len(buffer) - rows * cols
+
+
+def test_tiles_into_one_buffer_assemble_the_image():
+    buffer = array.array("H", bytes(2 * WIDTH * HEIGHT))
+    for tile_y in range(0, HEIGHT, 12):
+        for tile_x in range(0, WIDTH, 16):
+            rustism.render_tile_into(buffer, WIDTH, HEIGHT, 1000, tile_x, tile_y, 16, 12, **VIEW)
+    data, dtype = rustism.render_image(WIDTH, HEIGHT, 1000, **VIEW)
+    assert dtype == "uint16" and buffer.tobytes() == data
+
+
+def test_tile_into_rejects_a_short_buffer():
+    with pytest.raises(ValueError, match="buffer is too small"):
+        rustism.render_tile_into(array.array("H", [0]) * 100, WIDTH, HEIGHT, 1000, 0, 0, 16, 12, **VIEW)
+
+
+@pytest.mark.parametrize("height", [44, 45])
+@pytest.mark.parametrize("tile_h", [7, 64])
+@pytest.mark.parametrize("num_threads", [1, 3])
+def test_mirrored_image_matches_a_full_render(height, tile_h, num_threads):
+    view = dict(center_re=-0.6, center_im=0.0, scale=0.05, num_threads=num_threads, tile_w=16, tile_h=tile_h)
+    mirrored = rustism.render_image(WIDTH, height, 300, mirror=True, **view)
+    full = rustism.render_image(WIDTH, height, 300, mirror=False, **view)
+    assert mirrored == full
+    rows = image_rows(*full, WIDTH)
+    # Rows either side of the axis really are copies of each other
+    assert rows[1] == rows[-1] and rows[1] != rows[2]
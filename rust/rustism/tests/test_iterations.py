@@ -0,0 +1,117 @@
+import pytest
+import rustism
+
+from support import TYPECODES, concurrent, sequential, tile_key
+
+# Orbits through the neck at -0.75 + i*eps take about pi / eps iterations
+NECK = dict(width=8, height=8, tile_w=8, tile_h=8, center_re=-0.75, center_im=4e-5, scale=1e-7)
+
+
+@pytest.mark.parametrize("render", [sequential, concurrent])
+def test_counts_past_u16_do_not_wrap(render):
+    records, tiles = render(max_iter=100_000, **NECK)
+    counts = list(tiles[0]["data"])
+    assert tiles[0]["dtype"] == "uint32"
+    assert min(counts) > 65_535
+    assert records[0]["min_iter"] == min(counts)
+
+    _, packed = render(max_iter=100_000, contiguous=True, **NECK)
+    assert memoryview(packed[0]["data"]).cast(TYPECODES["uint32"]).tolist() == counts
+
+
+@pytest.mark.parametrize("max_iter, dtype", [(255, "uint8"), (256, "uint16"), (65_535, "uint16"), (65_536, "uint32")])
+def test_count_width_follows_max_iter(max_iter, dtype):
+    _, tiles = sequential(width=4, height=4, tile_w=4, tile_h=4, max_iter=max_iter)
+    assert tiles[0]["dtype"] == dtype
+    assert rustism.render_image(4, 4, max_iter)[1] == dtype
+
+
+@pytest.mark.parametrize("render", [sequential, concurrent])
+def test_check_every_one_is_the_default(render):
+    view = dict(width=96, height=64, tile_w=32, tile_h=32, max_iter=2000)
+    default, exact, coarse = (
+        sorted(render(**view, **kw)[1], key=tile_key) for kw in ({}, dict(check_every=1), dict(check_every=16))
+    )
+    assert [tile["data"] for tile in exact] == [tile["data"] for tile in default]
+    for exact_tile, coarse_tile in zip(exact, coarse):
+        for e, c in zip(exact_tile["data"], coarse_tile["data"]):
+            assert e <= c < e + 16 and (c == 2000) == (e == 2000)
+
+
+@pytest.mark.parametrize(
+    "kw",
+    [
+        dict(fractal_kind=rustism.FRACTAL_JULIA),
+        dict(precision=rustism.PRECISION_F32),
+        dict(periodicity=True),
+        dict(power=3),
+        dict(smooth=True),
+        dict(samples_per_axis=2),
+        dict(deep_center=("-0.75", "0.1")),
+    ],
+)
+def test_check_every_is_rejected_where_it_would_not_apply(kw):
+    with pytest.raises(ValueError, match="check_every"):
+        sequential(width=16, height=16, tile_w=16, tile_h=16, max_iter=100, check_every=8, **kw)
+
+
+def test_check_every_zero_is_rejected():
+    with pytest.raises(ValueError, match="check_every must be at least 1"):
+        sequential(width=8, height=8, tile_w=8, tile_h=8, max_iter=100, check_every=0)
+
+
+@pytest.mark.parametrize(
+    "kw",
+    [
+        dict(precision=rustism.PRECISION_F32),
+        dict(smooth=True),
+        dict(output_mode=rustism.OUTPUT_DISTANCE),
+        dict(fractal_kind=rustism.FRACTAL_BURNING_SHIP),
+        dict(power=3),
+        dict(samples_per_axis=2),
+        dict(deep_center=("-0.75", "0.1")),
+    ],
+)
+def test_periodicity_is_rejected_where_it_would_not_apply(kw):
+    with pytest.raises(ValueError, match="periodicity"):
+        sequential(width=16, height=16, tile_w=16, tile_h=16, max_iter=100, periodicity=True, **kw)
+
+
+def data(tiles):
+    return [tile["data"] for tile in sorted(tiles, key=tile_key)]
+
+
+@pytest.mark.parametrize("render", [sequential, concurrent])
+def test_coarse_pass_then_refine_equals_a_full_render(render):
+    view = dict(width=96, height=64, tile_w=32, tile_h=32, max_iter=2000)
+    _, full = render(**view)
+    _, tiles = render(coarse_iter=40, **view)
+    previews = [tile for tile in tiles if tile["preview"]]
+    refined = [tile for tile in tiles if not tile["preview"]]
+    assert len(previews) == len(refined) == len(full)
+    assert data(refined) == data(full)
+    assert data(previews) != data(full)
+
+
+def by_tile(tiles):
+    return [tile["final_z"] for tile in sorted(tiles, key=tile_key)]
+
+
+def test_final_z_is_the_same_from_every_renderer():
+    view = dict(width=40, height=30, tile_w=16, tile_h=16, max_iter=100, final_z=True)
+    _, plain = sequential(**dict(view, final_z=False))
+    assert all(tile["final_z"] is None for tile in plain)
+    _, seq = sequential(**view)
+    _, conc = concurrent(num_threads=3, **view)
+    assert by_tile(seq) == by_tile(conc)
+    assert all(len(t["final_z"]) == 2 * t["rows"] * t["cols"] for t in seq)
+
+    _, packed = sequential(contiguous=True, **view)
+    for values, tile in zip(by_tile(seq), sorted(packed, key=tile_key)):
+        assert memoryview(tile["final_z"]).cast("f").tolist() == values
+
+
+@pytest.mark.parametrize("kw", [dict(samples_per_axis=2), dict(precision=rustism.PRECISION_F32)])
+def test_final_z_needs_a_single_f64_orbit(kw):
+    with pytest.raises(ValueError, match="final_z"):
+        sequential(width=8, height=8, tile_w=8, tile_h=8, max_iter=50, final_z=True, **kw)
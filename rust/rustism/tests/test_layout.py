@@ -0,0 +1,31 @@
+import pytest
+import rustism
+
+from support import concurrent, sequential
+
+# Tiles of 32x24 leave clipped edge tiles on the right and bottom
+VIEW = dict(width=100, height=70, tile_w=32, tile_h=24, contiguous=True)
+
+
+@pytest.mark.parametrize("render", [sequential, concurrent])
+@pytest.mark.parametrize(
+    "kw, format, bytes_per_pixel",
+    [
+        (dict(max_iter=200), rustism.FORMAT_UINT8, 1),
+        (dict(max_iter=1000), rustism.FORMAT_UINT16, 2),
+        (dict(max_iter=70_000), rustism.FORMAT_UINT32, 4),
+        (dict(max_iter=200, smooth=True), rustism.FORMAT_FLOAT32, 4),
+        (dict(max_iter=200, output_mode=rustism.OUTPUT_DOUBLE_SMOOTH), rustism.FORMAT_FLOAT64, 8),
+        (dict(max_iter=200, palette=rustism.PALETTE_FIRE), rustism.FORMAT_RGBA8, 4),
+    ],
+)
+def test_stride_is_a_packed_row(render, kw, format, bytes_per_pixel):
+    _, tiles = render(**VIEW, **kw)
+    assert len(tiles) == 4 * 3
+    for tile in tiles:
+        layout = tile["layout"]
+        assert layout["format"] == format and layout["bytes_per_pixel"] == bytes_per_pixel
+        assert (layout["width"], layout["height"]) == (tile["cols"], tile["rows"])
+        assert layout["stride"] == layout["width"] * bytes_per_pixel
+        assert len(tile["data"]) == layout["stride"] * layout["height"]
+    assert {tile["layout"]["width"] for tile in tiles} == {32, 100 - 3 * 32}
@@ -0,0 +1,36 @@
+import array
+
+import pytest
+import rustism
+
+VIEW = dict(width=8, height=8, tile_w=8, tile_h=8, time_limit_ms=60_000, emit_tile=None)
+
+
+def render_tile_into(max_iter):
+    rustism.render_tile_into(array.array("H", [0]) * 64, 8, 8, max_iter, 0, 0, 8, 8)
+
+
+CALLS = {
+    "sequential": lambda max_iter: rustism.sequential(max_iter=max_iter, **VIEW),
+    "concurrent": lambda max_iter: rustism.concurrent(max_iter=max_iter, num_threads=2, **VIEW),
+    "renderer": lambda max_iter: rustism.Renderer(num_threads=1).render(max_iter=max_iter, **VIEW),
+    "render_image": lambda max_iter: rustism.render_image(8, 8, max_iter),
+    "render_tile_into": render_tile_into,
+    "auto_fit": lambda max_iter: rustism.auto_fit(8, 8, max_iter=max_iter),
+    "sample_point": lambda max_iter: rustism.sample_point(0.1, 0.1, max_iter),
+    "compute_reference_orbit": lambda max_iter: rustism.compute_reference_orbit("-0.5", "0", max_iter),
+    "bench": lambda max_iter: rustism.bench(8, 8, 8, 8, max_iter, 1),
+}
+
+
+@pytest.mark.parametrize("name", sorted(CALLS))
+def test_zero_max_iter_is_rejected(name):
+    with pytest.raises(ValueError, match="max_iter must be positive"):
+        CALLS[name](0)
+    CALLS[name](1)
+
+
+@pytest.mark.parametrize("kw", [dict(), dict(smooth=True), dict(output_mode=rustism.OUTPUT_DISTANCE)])
+def test_zero_max_iter_is_rejected_in_every_output_mode(kw):
+    with pytest.raises(ValueError, match="max_iter must be positive"):
+        rustism.sequential(max_iter=0, **VIEW, **kw)
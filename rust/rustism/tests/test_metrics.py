@@ -0,0 +1,49 @@
+import pytest
+import rustism
+
+from support import concurrent, image_rows, sequential
+
+pytestmark = pytest.mark.skipif(not rustism.capabilities()["metrics"], reason="built without the metrics feature")
+
+# 3 x 2 tiles, clipped on the right and bottom
+VIEW = dict(width=50, height=40, tile_w=20, tile_h=24, max_iter=300)
+
+
+def renderer(**kwargs):
+    tiles = []
+    records = rustism.Renderer(num_threads=2).render(emit_tile=tiles.append, time_limit_ms=60_000, **kwargs)
+    return records, tiles
+
+
+def increase(before, after):
+    return {name: after[name] - before[name] for name in before}
+
+
+@pytest.mark.parametrize("render", [sequential, concurrent, renderer])
+def test_counters_grow_by_the_rendered_tiles(render):
+    before = rustism.metrics_snapshot()
+    _, tiles = render(**VIEW)
+    grown = increase(before, rustism.metrics_snapshot())
+
+    assert grown["tiles_rendered_total"] == len(tiles) == 6
+    assert grown["pixels_rendered_total"] == 50 * 40
+    assert grown["iterations_total"] == sum(sum(tile["data"]) for tile in tiles)
+    assert grown["render_seconds_total"] > 0
+    assert grown["tiles_in_flight"] == 0
+
+
+def test_render_image_counts_its_tiles():
+    before = rustism.metrics_snapshot()
+    data, dtype = rustism.render_image(50, 40, 300, center_im=0.1, tile_w=20, tile_h=24, num_threads=2)
+    grown = increase(before, rustism.metrics_snapshot())
+
+    assert (grown["tiles_rendered_total"], grown["pixels_rendered_total"]) == (6, 50 * 40)
+    assert grown["iterations_total"] == sum(map(sum, image_rows(data, dtype, 50)))
+    assert rustism.metrics_snapshot()["tiles_in_flight"] == 0
+
+
+def test_previews_are_not_counted():
+    before = rustism.metrics_snapshot()
+    _, tiles = concurrent(preview_stride=4, **VIEW)
+    grown = increase(before, rustism.metrics_snapshot())
+    assert len(tiles) == 12 and grown["tiles_rendered_total"] == 6
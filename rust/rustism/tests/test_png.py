@@ -0,0 +1,70 @@
+import os
+import struct
+import tempfile
+import zlib
+
+import pytest
+import rustism
+
+from support import sequential
+
+
+def paeth(a, b, c):
+    p = a + b - c
+    pa, pb, pc = abs(p - a), abs(p - b), abs(p - c)
+    if pa <= pb and pa <= pc:
+        return a
+    return b if pb <= pc else c
+
+
+def decode_rgba(png):
+    """(width, height, pixels) of an 8-bit RGBA PNG, unfiltered."""
+    assert png[:8] == b"\x89PNG\r\n\x1a\n"
+    chunks, at = {}, 8
+    while at < len(png):
+        (length,) = struct.unpack(">I", png[at : at + 4])
+        kind, data = png[at + 4 : at + 8], png[at + 8 : at + 8 + length]
+        assert struct.unpack(">I", png[at + 8 + length : at + 12 + length])[0] == zlib.crc32(kind + data)
+        chunks[kind] = chunks.get(kind, b"") + data
+        at += 12 + length
+    width, height, depth, color = struct.unpack(">IIBB", chunks[b"IHDR"][:10])
+    assert (depth, color) == (8, 6)
+
+    raw, stride = zlib.decompress(chunks[b"IDAT"]), width * 4
+    pixels, previous = bytearray(), bytearray(stride)
+    for y in range(height):
+        kind, row = raw[y * (stride + 1)], bytearray(raw[y * (stride + 1) + 1 : (y + 1) * (stride + 1)])
+        for x in range(stride):
+            a = row[x - 4] if x >= 4 else 0
+            b, c = previous[x], previous[x - 4] if x >= 4 else 0
+            row[x] = (row[x] + [0, a, b, (a + b) // 2, paeth(a, b, c)][kind]) & 0xFF
+        pixels += row
+        previous = row
+    return width, height, bytes(pixels)
+
+
+def test_png_holds_the_colored_render():
+    width, height = 75, 50
+    with tempfile.TemporaryDirectory() as directory:
+        path = os.path.join(directory, "view.png")
+        written = rustism.render_to_png(path, width, height, 32, 32, 200)
+        with open(path, "rb") as f:
+            png = f.read()
+    assert written == len(png)
+    # Deflated, well under the raw pixels
+    assert len(png) < width * height * 4 // 2
+
+    view = dict(width=width, height=height, tile_w=32, tile_h=32, max_iter=200)
+    _, tiles = sequential(palette=rustism.PALETTE_CLASSIC, contiguous=True, **view)
+    expected = bytearray(width * height * 4)
+    for tile in tiles:
+        row_len = tile["cols"] * 4
+        for row in range(tile["rows"]):
+            at = ((tile["tile_y"] + row) * width + tile["tile_x"]) * 4
+            expected[at : at + row_len] = tile["data"][row * row_len : (row + 1) * row_len]
+    assert decode_rgba(png) == (width, height, bytes(expected))
+
+
+def test_unwritable_path_raises_os_error():
+    with pytest.raises(OSError):
+        rustism.render_to_png("/nonexistent/view.png", 10, 10, 5, 5, 10)
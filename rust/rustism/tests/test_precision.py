@@ -0,0 +1,70 @@
+import pytest
+import rustism
+
+from support import assemble, sequential
+
+WIDTH, HEIGHT = 160, 120
+
+
+def mismatches(**view):
+    view = dict(width=WIDTH, height=HEIGHT, tile_w=WIDTH, tile_h=HEIGHT, max_iter=500, **view)
+    f64 = assemble(sequential(**view)[1], WIDTH, HEIGHT)
+    f32 = assemble(sequential(precision=rustism.PRECISION_F32, **view)[1], WIDTH, HEIGHT)
+    return [f64[y][x] for y in range(HEIGHT) for x in range(WIDTH) if f64[y][x] != f32[y][x]]
+
+
+def test_f32_only_diverges_near_the_boundary():
+    # Far from the set an orbit escapes within a few iterations
+    for view in (dict(), dict(center_re=-0.745, center_im=0.11, scale=1e-3)):
+        counts = mismatches(**view)
+        assert len(counts) < WIDTH * HEIGHT // 50
+        assert all(count >= 30 for count in counts)
+
+
+def test_f32_degrades_past_its_minimum_scale():
+    counts = mismatches(center_re=-0.745, center_im=0.11, scale=1e-6)
+    assert len(counts) > WIDTH * HEIGHT // 10
+
+
+DOUBLE_DOUBLE = rustism.capabilities()["double_double"]
+DEEP = dict(
+    width=32, height=32, tile_w=32, tile_h=32, max_iter=20_000, scale=3e-17,
+    center_re="-0.7436438870371587", center_im="0.1318259042053120",
+)
+
+
+def equal_neighbours(rows):
+    return sum(a == b for row in rows for a, b in zip(row, row[1:]))
+
+
+@pytest.mark.skipif(DOUBLE_DOUBLE, reason="built with double-double")
+def test_double_double_needs_the_feature():
+    with pytest.raises(ValueError, match="double-double feature"):
+        sequential(precision=rustism.PRECISION_DOUBLE_DOUBLE, **DEEP)
+
+
+@pytest.mark.skipif(not DOUBLE_DOUBLE, reason="built without double-double")
+def test_double_double_resolves_what_f64_quantizes():
+    # A float center keeps the plain f64 path rather than perturbation
+    f64_view = dict(DEEP, center_re=float(DEEP["center_re"]), center_im=float(DEEP["center_im"]))
+    plain = assemble(sequential(**f64_view)[1], 32, 32)
+    dd = assemble(sequential(precision=rustism.PRECISION_DOUBLE_DOUBLE, **DEEP)[1], 32, 32)
+    assert equal_neighbours(plain) > 32 * 31 * 3 // 4
+    assert equal_neighbours(dd) < equal_neighbours(plain) // 2
+
+
+@pytest.mark.parametrize(
+    "kw",
+    [
+        dict(smooth=True),
+        dict(output_mode=rustism.OUTPUT_DISTANCE),
+        dict(fractal_kind=rustism.FRACTAL_BURNING_SHIP),
+        dict(power=3),
+        dict(samples_per_axis=2),
+        dict(deep_center=("-0.75", "0.1")),
+    ],
+)
+def test_f32_is_rejected_where_it_would_not_apply(kw):
+    with pytest.raises(ValueError, match="f32 precision|does not apply"):
+        sequential(width=16, height=16, tile_w=16, tile_h=16, max_iter=100, precision=rustism.PRECISION_F32, **kw)
+
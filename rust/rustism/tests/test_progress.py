@@ -0,0 +1,36 @@
+import itertools
+
+import pytest
+
+from support import concurrent, sequential
+
+# Tiles of 4096, 2304, 384 and 216 pixels, out of 7000
+VIEW = dict(width=100, height=70, tile_w=64, tile_h=64, max_iter=100)
+TILE_PIXELS = [4096, 2304, 384, 216]
+PIXEL_FRACTIONS = [4096 / 7000, 6400 / 7000, 6784 / 7000, 1.0]
+# What any set of finished tiles comes to, in whatever order they finish
+ANY_FINISHED = {sum(tiles) / 7000 for n in range(1, 5) for tiles in itertools.combinations(TILE_PIXELS, n)}
+
+
+def reported(render, **kw):
+    fractions = []
+    render(on_progress=fractions.append, **VIEW, **kw)
+    return fractions
+
+
+def test_sequential_reports_finished_pixels():
+    assert reported(sequential) == PIXEL_FRACTIONS
+
+
+@pytest.mark.parametrize("num_threads", [1, 3])
+def test_concurrent_reports_finished_pixels(num_threads):
+    fractions = reported(concurrent, num_threads=num_threads)
+    # Polling may skip updates, but each one counts whole finished tiles' pixels
+    assert fractions and fractions[-1] == 1.0
+    assert set(fractions) <= ANY_FINISHED
+    assert fractions == sorted(fractions)
+
+
+def test_subdivided_tiles_still_end_at_one():
+    fractions = reported(concurrent, num_threads=2, subdivide_after_ms=0)
+    assert fractions[-1] == 1.0 and fractions == sorted(fractions)
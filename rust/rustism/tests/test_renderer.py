@@ -0,0 +1,155 @@
+import asyncio
+import threading
+import time
+
+import pytest
+import rustism
+
+from support import sequential, tile_key, without_timing
+
+
+def test_progress_can_be_polled_mid_render():
+    renderer = rustism.Renderer(num_threads=2)
+    view = dict(width=512, height=512, tile_w=32, tile_h=32, max_iter=2000, time_limit_ms=60_000)
+    render = threading.Thread(target=renderer.render, kwargs=dict(emit_tile=None, **view))
+
+    samples = []
+    render.start()
+    while render.is_alive():
+        samples.append((renderer.tiles_completed, renderer.elapsed_ms))
+        time.sleep(0.001)
+    render.join()
+
+    tiles = [tiles for tiles, _ in samples]
+    assert tiles == sorted(tiles)
+    assert any(0 < done < 256 for done in tiles)
+    assert renderer.tiles_completed == 256
+    assert [ms for _, ms in samples] == sorted(ms for _, ms in samples)
+
+
+# One tile taking seconds to render in full, even in a release build
+ONE_TILE = dict(width=600, height=600, tile_w=600, tile_h=600, max_iter=200_000)
+SMALL = dict(width=64, height=64, tile_w=32, tile_h=32, max_iter=100)
+
+
+def test_cancel_interrupts_the_tile_in_flight():
+    renderer = rustism.Renderer(num_threads=2)
+    tiles = []
+    threading.Timer(0.05, renderer.cancel).start()
+    start = time.perf_counter()
+    renderer.render(emit_tile=tiles.append, time_limit_ms=60_000, **ONE_TILE)
+    assert time.perf_counter() - start < 0.5
+
+    assert len(tiles) == 1 and tiles[0]["partial"]
+    assert 0 < tiles[0]["rows"] < ONE_TILE["height"]
+    assert len(list(tiles[0]["data"])) == tiles[0]["rows"] * tiles[0]["cols"]
+
+    # Cancelling ends that render only
+    assert len(renderer.render(emit_tile=None, time_limit_ms=60_000, **SMALL)) == 4
+
+
+@pytest.mark.parametrize("positional", [False, True])
+def test_render_async_delivers_tiles_and_the_result_on_the_loop(positional):
+    renderer = rustism.Renderer(num_threads=2)
+    expected_tiles = []
+    expected = renderer.render(emit_tile=expected_tiles.append, time_limit_ms=60_000, **SMALL)
+    tiles, threads = [], set()
+
+    def emit_tile(tile):
+        threads.add(threading.get_ident())
+        tiles.append(tile)
+
+    async def main():
+        if positional:
+            args = [SMALL[key] for key in ("width", "height", "tile_w", "tile_h", "max_iter")]
+            return await renderer.render_async(*args, emit_tile, 60_000)
+        return await renderer.render_async(emit_tile=emit_tile, time_limit_ms=60_000, **SMALL)
+
+    records = asyncio.run(main())
+    assert without_timing(records) == without_timing(expected)
+    assert [tile["data"] for tile in sorted(tiles, key=tile_key)] == [
+        tile["data"] for tile in sorted(expected_tiles, key=tile_key)
+    ]
+    assert threads == {threading.get_ident()}
+
+
+def test_render_async_raises_the_render_error():
+    async def main():
+        await rustism.Renderer(num_threads=2).render_async(
+            emit_tile=None, time_limit_ms=60_000, check_every=0, **SMALL
+        )
+
+    with pytest.raises(ValueError, match="check_every must be at least 1"):
+        asyncio.run(main())
+
+
+def test_cancelling_render_async_stops_the_render():
+    renderer = rustism.Renderer(num_threads=2)
+
+    async def main():
+        task = asyncio.ensure_future(renderer.render_async(emit_tile=None, time_limit_ms=60_000, **ONE_TILE))
+        await asyncio.sleep(0.05)
+        task.cancel()
+        with pytest.raises(asyncio.CancelledError):
+            await task
+        # The next render waits for the cancelled one to let go of the pool
+        return await renderer.render_async(emit_tile=None, time_limit_ms=60_000, **SMALL)
+
+    start = time.perf_counter()
+    assert len(asyncio.run(main())) == 4
+    assert time.perf_counter() - start < 0.5
+
+
+def frame_data(tiles):
+    return [tile["data"] for tile in sorted(tiles, key=tile_key)]
+
+
+def test_zoom_sequence_scales_each_frame_by_the_factor():
+    view = dict(width=64, height=48, tile_w=32, tile_h=24, max_iter=200, center_re=-0.745, center_im=0.11)
+    renderer = rustism.Renderer(num_threads=2)
+    tiles = []
+    results = renderer.render_zoom_sequence(
+        3, 2.0, emit_tile=tiles.append, time_limit_ms=60_000, scale=0.01, **view
+    )
+    assert len(results) == 3 and all(len(records) == 4 for records in results)
+    assert sorted({tile["frame_index"] for tile in tiles}) == [0, 1, 2]
+    for frame_index, scale in enumerate([0.01, 0.005, 0.0025]):
+        frame = [tile for tile in tiles if tile["frame_index"] == frame_index]
+        assert frame_data(frame) == frame_data(sequential(scale=scale, **view)[1])
+
+
+def test_zoom_sequence_rejects_a_bad_factor():
+    with pytest.raises(ValueError, match="zoom_factor must be positive and finite"):
+        rustism.Renderer(num_threads=1).render_zoom_sequence(2, 0.0, width=8, height=8)
+
+
+@pytest.mark.parametrize("num_frames", [2, 5])
+def test_julia_sequence_runs_from_c_start_to_c_end(num_frames):
+    view = dict(width=64, height=48, tile_w=32, tile_h=24, max_iter=200, scale=0.06)
+    c_start, c_end = (-0.8, 0.156), (0.285, 0.01)
+    renderer = rustism.Renderer(num_threads=2)
+    tiles = []
+    results = renderer.render_julia_sequence(
+        num_frames, c_start, c_end, emit_tile=tiles.append, time_limit_ms=60_000, **view
+    )
+    assert len(results) == num_frames
+    assert sorted({tile["frame_index"] for tile in tiles}) == list(range(num_frames))
+
+    def julia(c):
+        return frame_data(sequential(fractal_kind=rustism.FRACTAL_JULIA, julia_re=c[0], julia_im=c[1], **view)[1])
+
+    first = [tile for tile in tiles if tile["frame_index"] == 0]
+    last = [tile for tile in tiles if tile["frame_index"] == num_frames - 1]
+    assert frame_data(first) == julia(c_start)
+    assert frame_data(last) == julia(c_end)
+    assert julia(c_start) != julia(c_end)
+
+
+def test_single_frame_julia_sequence_uses_c_start():
+    view = dict(width=32, height=32, tile_w=32, tile_h=32, max_iter=200, scale=0.1)
+    tiles = []
+    rustism.Renderer(num_threads=1).render_julia_sequence(
+        1, (-0.8, 0.156), (0.285, 0.01), emit_tile=tiles.append, time_limit_ms=60_000, **view
+    )
+    _, expected = sequential(fractal_kind=rustism.FRACTAL_JULIA, julia_re=-0.8, julia_im=0.156, **view)
+    assert frame_data(tiles) == frame_data(expected)
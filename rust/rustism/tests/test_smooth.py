@@ -0,0 +1,25 @@
+import rustism
+
+from support import assemble, sequential
+
+WIDTH = 64
+
+
+def smooth_row(output_mode):
+    # Just right of the cusp at 1/4 orbits crawl through the neck for about
+    # pi / sqrt(1e-8) iterations, and the count drops by ~0.0003 a pixel,
+    # a tenth of an f32 step there
+    view = dict(width=WIDTH, height=1, tile_w=WIDTH, tile_h=1, max_iter=100_000)
+    _, tiles = sequential(center_re=0.25 + 1e-8, center_im=0.0, scale=2e-16, output_mode=output_mode, **view)
+    return tiles, assemble(tiles, WIDTH, 1)[0]
+
+
+def test_double_smooth_resolves_steps_f32_rounds_away():
+    tiles, doubles = smooth_row(rustism.OUTPUT_DOUBLE_SMOOTH)
+    assert tiles[0]["dtype"] == "float64"
+    assert all(31_000 < value < 32_000 for value in doubles)
+    assert all(a > b for a, b in zip(doubles, doubles[1:]))
+
+    tiles, singles = smooth_row(rustism.OUTPUT_SMOOTH)
+    assert tiles[0]["dtype"] == "float32"
+    assert len(set(singles)) < WIDTH // 2
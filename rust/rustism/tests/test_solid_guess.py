@@ -0,0 +1,26 @@
+import warnings
+
+import pytest
+
+from support import sequential
+
+
+def render(**kwargs):
+    with warnings.catch_warnings(record=True) as caught:
+        warnings.simplefilter("always")
+        records, tiles = sequential(width=64, height=64, tile_w=64, tile_h=64, max_iter=500, scale=0.002, **kwargs)
+    return records[0], list(tiles[0]["data"]), [str(w.message) for w in caught]
+
+
+# Far outside the set, and well inside the cardioid
+@pytest.mark.parametrize("center_re, center_im", [(2.5, 2.5), (-0.2, 0.0)])
+def test_uniform_region_is_filled_in(center_re, center_im):
+    exact, exact_counts, _ = render(center_re=center_re, center_im=center_im)
+    guessed, guessed_counts, caught = render(center_re=center_re, center_im=center_im, solid_guess=True)
+
+    assert guessed_counts == exact_counts
+    assert len(set(exact_counts)) == 1
+    # Only the borders of the tile's rectangles get iterated
+    assert exact["guessed"] == 0
+    assert guessed["guessed"] > 0.8 * 64 * 64
+    assert any("solid_guess" in message for message in caught)
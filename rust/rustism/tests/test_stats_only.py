@@ -0,0 +1,35 @@
+import collections
+
+import pytest
+import rustism
+
+VIEW = dict(width=70, height=45, tile_w=16, tile_h=16, max_iter=150, center_re=-0.745, center_im=0.11, scale=4e-4)
+STATS = ["min_iter", "max_iter_reached", "interior_fraction", "total_pixels", "tiles_rendered"]
+
+
+def render(name, **kwargs):
+    kwargs.setdefault("emit_tile", None)
+    if name == "renderer":
+        return rustism.Renderer(num_threads=3).render(time_limit_ms=60_000, **kwargs)
+    if name == "concurrent":
+        kwargs["num_threads"] = 3
+    return getattr(rustism, name)(time_limit_ms=60_000, **kwargs)
+
+
+@pytest.mark.parametrize("name", ["sequential", "concurrent", "renderer"])
+def test_stats_only_matches_a_full_render(name):
+    tiles = []
+    _, histogram, summary = render(name, emit_tile=tiles.append, histogram=True, summary=True, **VIEW)
+    _, stats_histogram, stats_summary = render(name, stats_only=True, **VIEW)
+
+    assert stats_histogram == histogram
+    assert [getattr(stats_summary, stat) for stat in STATS] == [getattr(summary, stat) for stat in STATS]
+    # And both match the counts the emitted tiles hold
+    counts = collections.Counter(count for tile in tiles for count in tile["data"])
+    assert histogram == [counts[i] for i in range(VIEW["max_iter"] + 1)]
+
+
+@pytest.mark.parametrize("name", ["sequential", "concurrent", "renderer"])
+def test_stats_only_rejects_emit_tile(name):
+    with pytest.raises(ValueError, match="stats_only emits no tiles"):
+        render(name, stats_only=True, emit_tile=print, **VIEW)
@@ -0,0 +1,59 @@
+import pytest
+import rustism
+
+from support import assemble, sequential
+
+WIDTH, HEIGHT = 80, 60
+# Filaments off the seahorse valley, mostly boundary at this scale
+BOUNDARY = dict(
+    width=WIDTH,
+    height=HEIGHT,
+    tile_w=32,
+    tile_h=32,
+    max_iter=300,
+    center_re=-0.745,
+    center_im=0.11,
+    scale=2e-4,
+    smooth=True,
+)
+
+
+def neighbour_difference(image):
+    total = 0.0
+    for y in range(HEIGHT - 1):
+        for x in range(WIDTH - 1):
+            total += abs(image[y][x] - image[y][x + 1]) + abs(image[y][x] - image[y + 1][x])
+    return total / ((HEIGHT - 1) * (WIDTH - 1))
+
+
+def test_supersampling_smooths_the_boundary():
+    single = assemble(sequential(**BOUNDARY)[1], WIDTH, HEIGHT)
+    records, tiles = sequential(samples_per_axis=2, **BOUNDARY)
+    double = assemble(tiles, WIDTH, HEIGHT)
+
+    assert all(tile["dtype"] == "float32" for tile in tiles)
+    assert all(record["pixels_computed"] == record["tile_w"] * record["tile_h"] for record in records)
+    assert neighbour_difference(double) < 0.8 * neighbour_difference(single)
+
+
+def test_adaptive_supersampling_only_refines_edges():
+    adaptive = dict(width=64, height=64, tile_w=32, tile_h=32, max_iter=200, samples_per_axis=3, aa_threshold=2.0)
+
+    records, _ = sequential(**adaptive)
+    assert all(0 < r["supersampled"] < r["pixels_computed"] // 4 for r in records)
+
+    # Well inside the cardioid, and far outside the set
+    for center_re, center_im in ((-0.2, 0.0), (1.5, 1.5)):
+        records, _ = sequential(center_re=center_re, center_im=center_im, scale=0.001, **adaptive)
+        assert all(r["supersampled"] == 0 for r in records)
+
+    records, _ = sequential(**dict(adaptive, aa_threshold=None))
+    assert all(r["supersampled"] == r["pixels_computed"] for r in records)
+
+
+@pytest.mark.parametrize("pattern", [rustism.SAMPLES_GRID, rustism.SAMPLES_ROTATED_GRID, rustism.SAMPLES_HALTON])
+def test_sample_patterns_are_reproducible(pattern):
+    view = dict(BOUNDARY, samples_per_axis=2, sample_pattern=pattern)
+    image = assemble(sequential(**view)[1], WIDTH, HEIGHT)
+    assert assemble(sequential(**view)[1], WIDTH, HEIGHT) == image
+    assert assemble(sequential(**dict(view, tile_w=80, tile_h=60))[1], WIDTH, HEIGHT) == image
@@ -0,0 +1,48 @@
+import time
+
+import pytest
+import rustism
+
+from support import concurrent, sequential, tile_key, without_timing
+
+# One tile taking seconds to render in full, even in a release build
+ONE_TILE = dict(width=600, height=600, tile_w=600, tile_h=600, max_iter=200_000)
+
+
+@pytest.mark.parametrize("render", [sequential, concurrent])
+def test_time_limit_cuts_a_single_tile_short(render):
+    start = time.perf_counter()
+    records, tiles = render(time_limit_ms=50, **ONE_TILE)
+    elapsed = time.perf_counter() - start
+
+    assert elapsed < 0.5
+    # The rows that made it are still handed on, but the tile isn't finished
+    assert records == []
+    assert len(tiles) == 1 and tiles[0]["partial"]
+    assert 0 < tiles[0]["rows"] < ONE_TILE["height"]
+    assert len(list(tiles[0]["data"])) == tiles[0]["rows"] * tiles[0]["cols"]
+
+
+# Some 100ms of work in a release build, spread over 64 tiles
+RABBIT = dict(
+    width=128, height=128, tile_w=16, tile_h=16, max_iter=10_000, scale=2.4 / 128,
+    fractal_kind=rustism.FRACTAL_JULIA, julia_re=-0.123, julia_im=0.745,
+)
+
+
+@pytest.mark.parametrize("render", [sequential, concurrent])
+def test_skipping_finished_tasks_resumes_a_time_limited_render(render):
+    full_records, full_tiles = render(**RABBIT)
+    first, first_tiles = render(time_limit_ms=20, **RABBIT)
+    assert 0 < len(first) < len(full_records)
+    finished = [record["task_id"] for record in first]
+    second, second_tiles = render(time_limit_ms=60_000, skip_task_ids=set(finished), **RABBIT)
+
+    resumed = sorted(first + second, key=lambda record: record["task_id"])
+    assert [record["task_id"] for record in resumed] == list(range(len(full_records)))
+    assert without_timing(resumed) == without_timing(full_records)
+    # A tile the first limit cut short is partial, and rendered again in full
+    tiles = [tile for tile in first_tiles + second_tiles if not tile["partial"]]
+    assert [tile["data"] for tile in sorted(tiles, key=tile_key)] == [
+        tile["data"] for tile in sorted(full_tiles, key=tile_key)
+    ]
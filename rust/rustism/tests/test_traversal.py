@@ -0,0 +1,41 @@
+import pytest
+import rustism
+
+from support import concurrent, sequential, tile_key
+
+VIEW = dict(width=90, height=70, max_iter=300, center_re=-0.745, center_im=0.11, scale=2e-4)
+
+
+def data(tiles):
+    return [(tile_key(tile), list(tile["data"])) for tile in sorted(tiles, key=tile_key)]
+
+
+@pytest.mark.parametrize("render", [sequential, concurrent])
+@pytest.mark.parametrize("tile_w, tile_h", [(32, 32), (13, 7), (7, 29), (90, 70)])
+@pytest.mark.parametrize("kw", [dict(), dict(smooth=True), dict(output_mode=rustism.OUTPUT_DISTANCE), dict(preview_stride=3)])
+def test_z_order_matches_row_major(render, tile_w, tile_h, kw):
+    view = dict(VIEW, tile_w=tile_w, tile_h=tile_h, **kw)
+    _, row_major = render(traversal=rustism.TRAVERSAL_ROW_MAJOR, **view)
+    _, z_order = render(traversal=rustism.TRAVERSAL_Z_ORDER, **view)
+    assert data(z_order) == data(row_major)
+
+
+def emission(**kw):
+    records, tiles = sequential(width=96, height=96, tile_w=32, tile_h=32, max_iter=50, **kw)
+    # Records stay in raster order whatever order the tiles went out in
+    assert [record["task_id"] for record in records] == list(range(9))
+    return [tile["task_id"] for tile in tiles]
+
+
+def test_a_shuffle_is_reproducible_from_its_seed():
+    shuffled = emission(emit_order=rustism.ORDER_SHUFFLED, seed=7)
+    assert sorted(shuffled) == list(range(9))
+    assert shuffled != list(range(9))
+    assert emission(emit_order=rustism.ORDER_SHUFFLED, seed=7) == shuffled
+    assert emission(emit_order=rustism.ORDER_SHUFFLED, seed=8) != shuffled
+
+
+def test_center_out_starts_at_the_middle_tile():
+    order = emission(emit_order=rustism.ORDER_CENTER_OUT)
+    assert order[0] == 4
+    assert sorted(order[1:5]) == [1, 3, 5, 7]
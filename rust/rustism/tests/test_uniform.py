@@ -0,0 +1,36 @@
+import pytest
+import rustism
+
+from support import concurrent, sequential, tile_key
+
+# One tile well inside the cardioid, one crossing the boundary
+VIEW = dict(width=64, height=32, tile_w=32, tile_h=32, max_iter=200, center_re=0.2, scale=2e-3)
+
+
+def renderer(**kwargs):
+    tiles = []
+    records = rustism.Renderer(num_threads=2).render(emit_tile=tiles.append, time_limit_ms=60_000, **kwargs)
+    return records, tiles
+
+
+@pytest.mark.parametrize("render", [sequential, concurrent, renderer])
+def test_interior_tile_is_emitted_as_one_value(render):
+    records, tiles = render(skip_uniform=True, **VIEW)
+    _, full = render(**VIEW)
+    records = sorted(records, key=tile_key)
+    tiles, full = sorted(tiles, key=tile_key), sorted(full, key=tile_key)
+
+    interior, edge = tiles
+    assert interior["uniform"] and not edge["uniform"]
+    assert [r["uniform_value"] for r in records] == [200.0, None]
+    assert (interior["layout"]["width"], interior["layout"]["height"]) == (1, 1)
+    assert list(interior["data"]) == [200]
+    # Filling the tile from its one value gives the full render back
+    assert list(full[0]["data"]) == [200] * 32 * 32
+    assert edge["data"] == full[1]["data"]
+
+
+def test_uniform_tiles_are_emitted_in_full_by_default():
+    records, tiles = sequential(**VIEW)
+    assert not any(tile["uniform"] for tile in tiles)
+    assert all(record["uniform_value"] is None for record in records)
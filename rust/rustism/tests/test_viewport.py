@@ -0,0 +1,115 @@
+import math
+import random
+
+import pytest
+import rustism
+
+from support import assemble, concurrent, sequential
+
+WIDTH, HEIGHT, MAX_ITER = 1920, 1080, 100
+# Default view: centered on -0.75, with square pixels fitting 3.5 x 2
+SCALE = max(3.5 / WIDTH, 2.0 / HEIGHT)
+
+
+def mandelbrot(c_re, c_im):
+    z_re = z_im = 0.0
+    for i in range(MAX_ITER):
+        re2, im2 = z_re * z_re, z_im * z_im
+        if re2 + im2 > 4.0:
+            return i
+        z_im = 2.0 * z_re * z_im + c_im
+        z_re = re2 - im2 + c_re
+    return MAX_ITER
+
+
+def pixel(re, im):
+    return round(WIDTH / 2 + (re + 0.75) / SCALE), round(HEIGHT / 2 + im / SCALE)
+
+
+def interior_run(image, start, dx, dy):
+    (x, y), run = start, 0
+    while image[(y + dy * run) * WIDTH + x + dx * run] == MAX_ITER:
+        run += 1
+    return run
+
+
+def test_period_two_bulb_is_round_on_a_wide_canvas():
+    image, dtype = rustism.render_image(WIDTH, HEIGHT, MAX_ITER, num_threads=1)
+    assert dtype == "uint8"
+
+    # Half-chords of the disk of radius 1/4 around -1, 0.1 off its center
+    # each way; they'd differ if one axis were stretched
+    runs = [
+        interior_run(image, pixel(-1.0, 0.1), 1, 0),
+        interior_run(image, pixel(-1.0, -0.1), -1, 0),
+        interior_run(image, pixel(-0.9, 0.0), 0, -1),
+        interior_run(image, pixel(-1.1, 0.0), 0, 1),
+    ]
+    expected = (0.25**2 - 0.1**2) ** 0.5 / SCALE
+    assert max(runs) - min(runs) <= 1
+    assert all(abs(run - expected) <= 3 for run in runs)
+
+
+def test_both_axes_share_the_scale():
+    image, _ = rustism.render_image(WIDTH, HEIGHT, MAX_ITER, num_threads=1)
+    rng = random.Random(41)
+    for _ in range(500):
+        x, y = rng.randrange(WIDTH), rng.randrange(HEIGHT)
+        expected = mandelbrot(-0.75 + (x - WIDTH / 2) * SCALE, (y - HEIGHT / 2) * SCALE)
+        assert image[y * WIDTH + x] == expected, (x, y)
+
+
+def test_flip_y_mirrors_rows():
+    view = dict(width=40, height=30, tile_w=16, tile_h=16, max_iter=100, center_re=-0.6, center_im=0.3, scale=0.02)
+    upright = assemble(sequential(**view)[1], 40, 30)
+    flipped = assemble(sequential(flip_y=True, **view)[1], 40, 30)
+    assert flipped == upright[::-1]
+    assert flipped != upright
+
+
+def test_pixel_center_samples_half_a_pixel_in():
+    width, height, scale, center = 16, 12, 0.02, (-0.75, 0.1)
+    view = dict(width=width, height=height, tile_w=8, tile_h=8, max_iter=MAX_ITER, scale=scale)
+    view.update(center_re=center[0], center_im=center[1])
+
+    def reference(offset):
+        return [
+            [
+                mandelbrot(
+                    center[0] + (x + offset - width / 2) * scale,
+                    center[1] + (y + offset - height / 2) * scale,
+                )
+                for x in range(width)
+            ]
+            for y in range(height)
+        ]
+
+    corners = assemble(sequential(**view)[1], width, height)
+    centers = assemble(sequential(pixel_center=True, **view)[1], width, height)
+    assert corners == reference(0.0)
+    assert centers == reference(0.5)
+    assert centers != corners
+
+
+SMALL = dict(width=16, height=12, tile_w=8, tile_h=8, max_iter=50)
+
+
+@pytest.mark.parametrize("render", [sequential, concurrent])
+@pytest.mark.parametrize(
+    "kw, message",
+    [
+        (dict(scale=0.0), "scale must be positive"),
+        (dict(scale=-0.01), "scale must be positive"),
+        (dict(scale=math.nan), "scale must be positive"),
+        (dict(center_re=math.inf), "center_re and center_im must be finite"),
+        (dict(center_im=math.nan), "center_re and center_im must be finite"),
+        (dict(center_re=1.7e308, scale=1e307), "the view reaches past the range of f64"),
+        (
+            dict(fractal_kind=rustism.FRACTAL_JULIA, julia_re=math.nan),
+            "julia_re and julia_im must be finite",
+        ),
+    ],
+)
+def test_bad_viewports_are_rejected(render, kw, message):
+    with pytest.raises(ValueError, match=message):
+        render(**SMALL, **kw)